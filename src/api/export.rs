@@ -0,0 +1,95 @@
+use cfg_if::cfg_if;
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+use axum::{
+    extract::{rejection::QueryRejection, Path, Query},
+    http::header,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::error_template::AppError;
+use crate::model::export::{self, ExportFormat, TABLE_NAMES};
+use crate::model::parser::MatchRecord;
+use crate::store::MatchStore;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// Single table to export as CSV, every table in long format when missing.
+    pub table: Option<String>,
+}
+
+impl ExportQuery {
+    pub fn from_request(query: Result<Query<ExportQuery>, QueryRejection>) -> Result<Self, AppError> {
+        query
+            .map(|Query(query)| query)
+            .map_err(|rejection| AppError::InvalidExport(rejection.body_text()))
+    }
+}
+
+/// Renders `matches` in the requested format, as a download named after `file_stem`.
+pub fn export_response(matches: &[(String, MatchRecord)], query: &ExportQuery, file_stem: &str) -> Result<Response, AppError> {
+    let body = match (query.format, query.table.as_deref()) {
+        (ExportFormat::Json, _) => {
+            let matches = matches.iter().cloned().collect::<HashMap<String, MatchRecord>>();
+            return Ok(Json(matches).into_response());
+        }
+        (ExportFormat::Csv, Some(table_name)) => {
+            let record = match matches {
+                [(_, record)] => record,
+                _ => return Err(AppError::InvalidExport("A single table can only be exported from one match".to_string())),
+            };
+            let table = export::match_tables(record)
+                .into_iter()
+                .find(|table| table.name == table_name)
+                .ok_or_else(|| AppError::InvalidExport(format!("Unknown table {table_name:?}, expected one of {}", TABLE_NAMES.join(", "))))?;
+
+            let file_name = format!("{file_stem}-{}.csv", table.name);
+            return Ok(download(query.format, &file_name, export::table_csv(&table).into_bytes()));
+        }
+        (ExportFormat::Csv, None) => export::long_csv(matches).into_bytes(),
+        (ExportFormat::Markdown, _) => export::markdown(matches).into_bytes(),
+        (ExportFormat::Xlsx, _) => export::xlsx(matches).map_err(|err| AppError::Export(err.to_string()))?,
+    };
+
+    let file_name = format!("{file_stem}.{}", query.format.extension());
+    Ok(download(query.format, &file_name, body))
+}
+
+fn download(format: ExportFormat, file_name: &str, body: Vec<u8>) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{file_name}\"")),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+pub async fn export_match(Extension(store): Extension<MatchStore>, Path(id): Path<String>, query: Result<Query<ExportQuery>, QueryRejection>) -> Result<Response, AppError> {
+    let query = ExportQuery::from_request(query)?;
+    let stored_match = store.get(&id).ok_or(AppError::NotFound)?;
+
+    export_response(&[(stored_match.match_key, stored_match.record)], &query, &format!("match-{id}"))
+}
+
+pub async fn export_upload(Extension(store): Extension<MatchStore>, Path(upload_id): Path<String>, query: Result<Query<ExportQuery>, QueryRejection>) -> Result<Response, AppError> {
+    let query = ExportQuery::from_request(query)?;
+    let matches = store
+        .upload(&upload_id)
+        .into_iter()
+        .map(|stored_match| (stored_match.match_key, stored_match.record))
+        .collect::<Vec<(String, MatchRecord)>>();
+
+    if matches.is_empty() {
+        return Err(AppError::NotFound);
+    }
+
+    export_response(&matches, &query, &format!("report-{upload_id}"))
+}
+}}
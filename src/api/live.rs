@@ -0,0 +1,21 @@
+use cfg_if::cfg_if;
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+use axum::{response::sse::{Event, KeepAlive, Sse}, Extension};
+use std::convert::Infallible;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+
+use crate::live::{LiveEvent, LiveHub};
+
+pub async fn live_events(Extension(hub): Extension<LiveHub>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let updates = BroadcastStream::new(hub.subscribe())
+        // A lagging client only misses intermediate updates, the next ranking change catches it up
+        .filter_map(|event| event.ok());
+
+    let stream = tokio_stream::once(hub.snapshot())
+        .chain(updates)
+        .map(|event: LiveEvent| Ok(Event::default().json_data(event).unwrap()));
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+}}
@@ -0,0 +1,13 @@
+use leptos::*;
+
+use crate::store::StoredMatch;
+
+#[server(GetMatch, "/api")]
+pub async fn get_match(cx: Scope, id: String) -> Result<Option<StoredMatch>, ServerFnError> {
+    use crate::store::MatchStore;
+
+    let store = use_context::<MatchStore>(cx)
+        .ok_or_else(|| ServerFnError::ServerError("The match store is unavailable".to_string()))?;
+
+    Ok(store.get(&id))
+}
@@ -1 +1,7 @@
+pub mod export;
+pub mod live;
+pub mod matches;
 pub mod parse;
+pub mod players;
+pub mod ratings;
+pub mod validate;
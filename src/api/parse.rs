@@ -1,39 +1,95 @@
 use cfg_if::cfg_if;
 
 cfg_if! { if #[cfg(feature = "ssr")] {
-use axum::{extract::Multipart,  http::{StatusCode}, Json};
+use axum::{extract::{multipart::{MultipartError, MultipartRejection}, rejection::QueryRejection, Multipart, Query}, http::StatusCode, response::Response, Extension};
 use std::io::Write;
-use std::{fs, path};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
 
-use std::collections::HashMap;
+use crate::api::export::{export_response, ExportQuery};
+use crate::config::ServerConfig;
+use crate::error_template::AppError;
+use crate::model::export::ordered_matches;
+use crate::store::{upload_id_of, MatchStore};
 
-use crate::model::parser::{Parser, MatchRecord};
+/// Bounds the uploads parsed or validated at the same time, a parse already running on several threads.
+#[derive(Clone)]
+pub struct ParseSlots(pub(crate) Arc<Semaphore>);
 
-pub async fn parse_log_file(mut multipart: Multipart) -> Result<Json<HashMap<String, MatchRecord>>, (StatusCode, String)> {
-    let mut parsed_log = HashMap::new();
+impl ParseSlots {
+    pub fn new(concurrent_parses: usize) -> Self {
+        ParseSlots(Arc::new(Semaphore::new(concurrent_parses.max(1))))
+    }
+}
 
-    while let Some(field) = multipart.next_field().await.map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))? {
-        let is_log_field = field.name().unwrap().to_string() == "log";
+/// Uploaded log written to the temporary directory, removed when dropped whether parsing it
+/// succeeded or not.
+struct TempLog(PathBuf);
+
+impl Drop for TempLog {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.0) {
+            log::warn!("Cannot remove {}: {}", self.0.display(), err);
+        }
+    }
+}
+
+pub(crate) fn upload_error(err: MultipartError) -> AppError {
+    if err.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        AppError::PayloadTooLarge
+    } else {
+        AppError::InvalidUpload(err.body_text())
+    }
+}
+
+/// Parses an uploaded log, answering with JSON or with the export asked by `?format=`.
+pub async fn parse_log_file(Extension(config): Extension<Arc<ServerConfig>>, Extension(store): Extension<MatchStore>, Extension(slots): Extension<ParseSlots>, query: Result<Query<ExportQuery>, QueryRejection>, multipart: Result<Multipart, MultipartRejection>) -> Result<Response, AppError> {
+    let query = ExportQuery::from_request(query)?;
+    let mut multipart = multipart.map_err(|rejection| AppError::InvalidUpload(rejection.body_text()))?;
+    let mut parsed_log = None;
+
+    while let Some(field) = multipart.next_field().await.map_err(upload_error)? {
+        let is_log_field = field.name() == Some("log");
 
         if is_log_field {
-            let base_path = "./tmp/";
             let random_prefix_filename = uuid::Uuid::new_v4().to_string();
-            let complete_path = format!("{base_path}{random_prefix_filename}_log.txt");
-            let file_path = path::Path::new(complete_path.as_str());
+            let complete_path = config.temp_dir.join(format!("{random_prefix_filename}_log.txt"));
+
+            let data = field.bytes().await.map_err(upload_error)?;
 
-            let data = field.bytes().await.unwrap();
+            let mut file = fs::File::create(&complete_path).map_err(AppError::storage)?;
+            let temp_log = TempLog(complete_path);
+            file.write_all(&data).map_err(AppError::storage)?;
+            drop(file);
 
-            let mut file = fs::File::create(&file_path).map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
-            file.write_all(&data).map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+            // Parsing blocks, so it runs away from the async workers
+            let parser = config.parallel_parser();
+            let log_path = temp_log.0.to_string_lossy().into_owned();
+            let slot = slots.0.acquire().await.expect("the parse slots are never closed");
+            let parse_result = tokio::task::spawn_blocking(move || parser.parse(log_path))
+                .await
+                .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()));
+            drop(slot);
+            drop(temp_log);
 
-            parsed_log = Parser::parse(String::from(file_path.to_str().unwrap()));
+            let mut matches = parse_result?;
+            store.save_upload(&data, &mut matches).map_err(AppError::storage)?;
 
-            fs::remove_file(file_path).map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+            parsed_log = Some(matches);
         } else {
-            return Err((StatusCode::BAD_REQUEST, "Invalid file type or field name".to_string()));
+            return Err(AppError::InvalidUpload("Invalid file type or field name".to_string()));
         }
     }
 
-    Ok(Json(parsed_log))
+    let parsed_log = parsed_log.ok_or(AppError::MissingLogField)?;
+    let file_stem = parsed_log
+        .values()
+        .find_map(|record| record.id.as_deref().and_then(upload_id_of))
+        .map(|upload_id| format!("report-{upload_id}"))
+        .unwrap_or_else(|| "report".to_string());
+
+    export_response(&ordered_matches(&parsed_log), &query, &file_stem)
 }
 }}
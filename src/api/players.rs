@@ -0,0 +1,55 @@
+use leptos::*;
+
+use crate::model::comparison::PlayerComparison;
+use crate::model::player::PlayerProfile;
+
+#[server(GetPlayerProfile, "/api")]
+pub async fn get_player_profile(
+    cx: Scope,
+    name: String,
+) -> Result<Option<PlayerProfile>, ServerFnError> {
+    use crate::store::MatchStore;
+
+    let store = use_context::<MatchStore>(cx)
+        .ok_or_else(|| ServerFnError::ServerError("The match store is unavailable".to_string()))?;
+
+    // Profile links use the logged names, which may belong to a merged identity
+    let identities = store.identities();
+    Ok(PlayerProfile::build(
+        &identities.resolve(&name),
+        &identities.resolve_matches(&store.all()),
+    ))
+}
+
+/// Everyone who played any stored match, sorted by name.
+#[server(GetPlayerNames, "/api")]
+pub async fn get_player_names(cx: Scope) -> Result<Vec<String>, ServerFnError> {
+    use crate::model::player::player_names;
+    use crate::store::MatchStore;
+
+    let store = use_context::<MatchStore>(cx)
+        .ok_or_else(|| ServerFnError::ServerError("The match store is unavailable".to_string()))?;
+
+    Ok(player_names(&store.resolved()).into_iter().collect())
+}
+
+#[server(ComparePlayers, "/api")]
+pub async fn compare_players(
+    cx: Scope,
+    names: Vec<String>,
+) -> Result<PlayerComparison, ServerFnError> {
+    use crate::store::MatchStore;
+
+    let store = use_context::<MatchStore>(cx)
+        .ok_or_else(|| ServerFnError::ServerError("The match store is unavailable".to_string()))?;
+
+    let identities = store.identities();
+    let names = names
+        .iter()
+        .map(|name| identities.resolve(name))
+        .collect::<Vec<String>>();
+    Ok(PlayerComparison::build(
+        &names,
+        &identities.resolve_matches(&store.all()),
+    ))
+}
@@ -0,0 +1,72 @@
+use cfg_if::cfg_if;
+use leptos::*;
+
+use crate::model::rating::{RatingAlgorithm, RatingInput, RatingTable};
+
+#[server(GetRatings, "/api")]
+pub async fn get_ratings(
+    cx: Scope,
+    algorithm: Option<RatingAlgorithm>,
+    input: Option<RatingInput>,
+) -> Result<RatingTable, ServerFnError> {
+    use crate::model::rating::RatingSettings;
+    use crate::store::MatchStore;
+
+    let store = use_context::<MatchStore>(cx)
+        .ok_or_else(|| ServerFnError::ServerError("The match store is unavailable".to_string()))?;
+    let settings = use_context::<RatingSettings>(cx).unwrap_or_default();
+
+    Ok(RatingTable::compute(
+        &store.resolved(),
+        &settings.with_choices(algorithm, input),
+    ))
+}
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+use std::sync::Arc;
+
+use axum::{
+    extract::{rejection::QueryRejection, Path, Query},
+    Extension, Json,
+};
+use serde::Deserialize;
+
+use crate::config::ServerConfig;
+use crate::error_template::AppError;
+use crate::model::rating::PlayerRating;
+use crate::store::MatchStore;
+
+/// Overrides of the configured rating settings, e.g. `?algorithm=trueskill&input=duels`.
+#[derive(Deserialize, Debug, Default)]
+pub struct RatingsQuery {
+    pub algorithm: Option<RatingAlgorithm>,
+    pub input: Option<RatingInput>,
+}
+
+fn compute_ratings(store: &MatchStore, config: &ServerConfig, query: Result<Query<RatingsQuery>, QueryRejection>) -> Result<RatingTable, AppError> {
+    let Query(query) = query.map_err(|rejection| AppError::InvalidRatings(rejection.body_text()))?;
+    let settings = config.ratings.with_choices(query.algorithm, query.input);
+
+    Ok(RatingTable::compute(&store.resolved(), &settings))
+}
+
+/// Every player's rating along with their rating history.
+pub async fn ratings(
+    Extension(store): Extension<MatchStore>,
+    Extension(config): Extension<Arc<ServerConfig>>,
+    query: Result<Query<RatingsQuery>, QueryRejection>,
+) -> Result<Json<RatingTable>, AppError> {
+    compute_ratings(&store, &config, query).map(Json)
+}
+
+pub async fn player_ratings(
+    Extension(store): Extension<MatchStore>,
+    Extension(config): Extension<Arc<ServerConfig>>,
+    Path(player): Path<String>,
+    query: Result<Query<RatingsQuery>, QueryRejection>,
+) -> Result<Json<PlayerRating>, AppError> {
+    let table = compute_ratings(&store, &config, query)?;
+
+    table.player(&store.identities().resolve(&player)).cloned().map(Json).ok_or(AppError::NotFound)
+}
+}}
@@ -0,0 +1,33 @@
+use cfg_if::cfg_if;
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+use axum::{extract::{multipart::MultipartRejection, Multipart}, Extension, Json};
+
+use crate::api::parse::{upload_error, ParseSlots};
+use crate::error_template::AppError;
+use crate::model::validator::{LogValidator, ValidationReport};
+
+/// Checks an uploaded log for anomalies, without parsing or storing it.
+pub async fn validate_log_file(Extension(slots): Extension<ParseSlots>, multipart: Result<Multipart, MultipartRejection>) -> Result<Json<ValidationReport>, AppError> {
+    let mut multipart = multipart.map_err(|rejection| AppError::InvalidUpload(rejection.body_text()))?;
+    let mut report = None;
+
+    while let Some(field) = multipart.next_field().await.map_err(upload_error)? {
+        if field.name() != Some("log") {
+            return Err(AppError::InvalidUpload("Invalid file type or field name".to_string()));
+        }
+
+        let data = field.bytes().await.map_err(upload_error)?;
+        // Validating blocks like parsing does, and takes one of its slots
+        let slot = slots.0.acquire().await.expect("the parse slots are never closed");
+        let validation = tokio::task::spawn_blocking(move || LogValidator::validate_bytes(&data))
+            .await
+            .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()));
+        drop(slot);
+
+        report = Some(validation);
+    }
+
+    report.map(Json).ok_or(AppError::MissingLogField)
+}
+}}
@@ -1,16 +1,30 @@
+use crate::config::{ClientSettings, PARSER_OPTIONS_META, PUBLIC_BASE_URL_META};
 use crate::error_template::{AppError, ErrorTemplate};
 use leptos::*;
 use leptos_meta::*;
 use leptos_router::*;
 
 use crate::pages;
+use pages::compare::ComparePage;
 use pages::home::HomePage;
+use pages::live::LivePage;
+use pages::match_detail::MatchPage;
+use pages::player::PlayerPage;
+use pages::ratings::RatingsPage;
 
 #[component]
 pub fn App(cx: Scope) -> impl IntoView {
     // Provides context that manages stylesheets, titles, meta tags, etc.
     provide_meta_context(cx);
 
+    // Only present while rendering on the server, the browser reads it back from the <meta> tag
+    let client_settings = use_context::<ClientSettings>(cx);
+    let public_base_url = client_settings
+        .as_ref()
+        .and_then(|settings| settings.public_base_url.clone());
+    let parser_options =
+        client_settings.and_then(|settings| serde_json::to_string(&settings.parser_options).ok());
+
     view! {
         cx,
 
@@ -21,6 +35,13 @@ pub fn App(cx: Scope) -> impl IntoView {
         // sets the document title
         <Title text="Quake 3 Arena server log parser"/>
 
+        {public_base_url.map(|public_base_url| view! { cx,
+            <Meta name=PUBLIC_BASE_URL_META content=public_base_url/>
+        })}
+        {parser_options.map(|parser_options| view! { cx,
+            <Meta name=PARSER_OPTIONS_META content=parser_options/>
+        })}
+
         // content for this welcome page
         <Router fallback=|cx| {
             let mut outside_errors = Errors::default();
@@ -32,8 +53,20 @@ pub fn App(cx: Scope) -> impl IntoView {
         }>
             <main class="w-screen h-full min-h-screen pt-12 bg-[#262f3d] text-[#e8ecf0]">
                 <div class="mx-auto max-w-[1080px]">
+                    <nav class="flex flex-row gap-6 mb-8 text-lg font-medium">
+                        <A href="/" class="hover:underline">"Parser"</A>
+                        <A href="/live" class="hover:underline">"Live"</A>
+                        <A href="/compare" class="hover:underline">"Compare"</A>
+                        <A href="/ratings" class="hover:underline">"Ratings"</A>
+                    </nav>
                     <Routes>
                         <Route path="" view=|cx| view! { cx, <HomePage/> }/>
+                        <Route path="live" view=|cx| view! { cx, <LivePage/> }/>
+                        // Rendered once the match is loaded, so shared links carry its title and summary
+                        <Route path="matches/:id" ssr=SsrMode::Async view=|cx| view! { cx, <MatchPage/> }/>
+                        <Route path="players/:name" ssr=SsrMode::Async view=|cx| view! { cx, <PlayerPage/> }/>
+                        <Route path="compare" ssr=SsrMode::Async view=|cx| view! { cx, <ComparePage/> }/>
+                        <Route path="ratings" ssr=SsrMode::Async view=|cx| view! { cx, <RatingsPage/> }/>
                     </Routes>
                 </div>
             </main>
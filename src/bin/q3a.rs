@@ -0,0 +1,328 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use q3aparser::model::anonymise::{AnonymiseOptions, Anonymiser};
+use q3aparser::model::generator::{GeneratorOptions, LogGenerator};
+use q3aparser::model::identity::AliasMap;
+use q3aparser::model::parser::ParserOptions;
+use q3aparser::model::validator::{LogValidator, Severity};
+use q3aparser::report::{matches_from_logs, write_static_report};
+use q3aparser::store::MatchStore;
+
+const USAGE: &str = "Usage:
+  q3a report --out <DIR> <LOG>...          Render the given logs as a static HTML report
+  q3a report --out <DIR> --data-dir <DIR>  Render the uploads stored by the server
+  q3a anonymise <LOG>                      Print the log with pseudonyms for players, GUIDs and IPs
+  q3a anonymise --out <DIR> <LOG>...       Write the anonymised logs to a directory, sharing pseudonyms
+  q3a generate [--out <FILE>]              Print or write a synthetic log, for tests and benchmarks
+  q3a validate [--json] <LOG>...           Report anomalies of the logs, failing when one has errors
+
+Options:
+  --aliases <FILE>   Merge players with the aliases and clan tags of a TOML alias file
+  --dialect <GAME>   Game the logs come from: quake3, openarena, quakelive, urbanterror or
+                     wolfet, detected for each match by default
+  --redact-chat      Replace chat messages of anonymised logs with [redacted]
+  --seed <N>         Seed of the generated log, the same seed giving the same log (default 0)
+  --matches <N>      Matches to generate (default 10)
+  --size <BYTES>     Generate matches until the log reaches a size instead, e.g. 500M or 2G
+  --players <N>      Most players of a generated match (default 8, at most 20)
+  --max-kills <N>    Most kills of a generated match (default 80)
+  --json             Print the validation reports as JSON, one per log";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+
+    let result = match args.first().map(String::as_str) {
+        Some("report") => report(&args[1..]),
+        Some("anonymise") | Some("anonymize") => anonymise(&args[1..]),
+        Some("generate") => generate(&args[1..]),
+        Some("validate") => validate(&args[1..]),
+        Some("-h") | Some("--help") => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        _ => Err(USAGE.to_string()),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn report(args: &[String]) -> Result<(), String> {
+    let mut output_dir = None;
+    let mut data_dir = None;
+    let mut alias_file = None;
+    let mut options = ParserOptions::default();
+    let mut logs = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => {
+                output_dir = Some(PathBuf::from(args.next().ok_or("--out needs a directory")?))
+            }
+            "--data-dir" => {
+                data_dir = Some(PathBuf::from(
+                    args.next().ok_or("--data-dir needs a directory")?,
+                ))
+            }
+            "--aliases" => {
+                alias_file = Some(PathBuf::from(args.next().ok_or("--aliases needs a file")?))
+            }
+            "--dialect" => {
+                options.dialect = Some(args.next().ok_or("--dialect needs a game")?.parse()?)
+            }
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown option {flag}\n\n{USAGE}"))
+            }
+            log => logs.push(PathBuf::from(log)),
+        }
+    }
+
+    let output_dir = output_dir.ok_or_else(|| format!("Missing --out\n\n{USAGE}"))?;
+    let matches = match (data_dir, logs.is_empty()) {
+        (Some(data_dir), true) => MatchStore::open(data_dir)
+            .map_err(|err| err.to_string())?
+            .all(),
+        (None, false) => matches_from_logs(&logs, options).map_err(|err| err.to_string())?,
+        _ => return Err(format!("Pass either log files or --data-dir\n\n{USAGE}")),
+    };
+
+    let aliases = match alias_file {
+        Some(alias_file) => AliasMap::from_file(&alias_file).map_err(|err| err.to_string())?,
+        None => AliasMap::default(),
+    };
+
+    let summary =
+        write_static_report(&matches, &aliases, &output_dir).map_err(|err| err.to_string())?;
+    println!(
+        "Wrote {} pages ({} matches, {} players) to {}",
+        summary.pages,
+        summary.matches,
+        summary.players,
+        output_dir.display()
+    );
+
+    Ok(())
+}
+
+fn anonymise(args: &[String]) -> Result<(), String> {
+    let mut output_dir = None;
+    let mut options = AnonymiseOptions::default();
+    let mut logs = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => {
+                output_dir = Some(PathBuf::from(args.next().ok_or("--out needs a directory")?))
+            }
+            "--redact-chat" => options.redact_chat = true,
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown option {flag}\n\n{USAGE}"))
+            }
+            log => logs.push(PathBuf::from(log)),
+        }
+    }
+
+    let mut anonymiser = Anonymiser::new(options);
+    let open = |log: &PathBuf| {
+        File::open(log)
+            .map(BufReader::new)
+            .map_err(|err| format!("Cannot read {}: {err}", log.display()))
+    };
+
+    match (output_dir, logs.as_slice()) {
+        (None, [log]) => anonymiser
+            .anonymise(open(log)?, io::stdout().lock())
+            .map_err(|err| err.to_string()),
+        (Some(output_dir), [_, ..]) => {
+            fs::create_dir_all(&output_dir)
+                .map_err(|err| format!("Cannot write {}: {err}", output_dir.display()))?;
+
+            for log in logs.iter() {
+                let file_name = log
+                    .file_name()
+                    .ok_or_else(|| format!("{} is not a file", log.display()))?;
+                let output = output_dir.join(file_name);
+                let writer = File::create(&output)
+                    .map(BufWriter::new)
+                    .map_err(|err| format!("Cannot write {}: {err}", output.display()))?;
+
+                anonymiser
+                    .anonymise(open(log)?, writer)
+                    .map_err(|err| format!("Cannot anonymise {}: {err}", log.display()))?;
+            }
+
+            eprintln!(
+                "Anonymised {} logs ({} players) to {}",
+                logs.len(),
+                anonymiser.pseudonyms().len(),
+                output_dir.display()
+            );
+            Ok(())
+        }
+        _ => Err(format!(
+            "Pass a single log, or --out and any number of logs\n\n{USAGE}"
+        )),
+    }
+}
+
+fn generate(args: &[String]) -> Result<(), String> {
+    let mut output = None;
+    let mut options = GeneratorOptions::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{name} needs a value"));
+        match arg.as_str() {
+            "--out" => output = Some(PathBuf::from(value("--out")?)),
+            "--seed" => options.seed = parse_number(value("--seed")?)?,
+            "--matches" => options.matches = parse_number(value("--matches")?)?,
+            "--size" => options.target_bytes = Some(parse_size(value("--size")?)?),
+            "--players" => options.max_players = parse_number(value("--players")?)?,
+            "--max-kills" => options.max_kills = parse_number(value("--max-kills")?)?,
+            other => return Err(format!("Unknown option {other}\n\n{USAGE}")),
+        }
+    }
+
+    let generator = LogGenerator::new(options);
+    match output {
+        None => generator
+            .generate(BufWriter::new(io::stdout().lock()))
+            .map(|_| ())
+            .map_err(|err| err.to_string()),
+        Some(output) => {
+            let writer = File::create(&output)
+                .map(BufWriter::new)
+                .map_err(|err| format!("Cannot write {}: {err}", output.display()))?;
+            let summary = generator
+                .generate(writer)
+                .map_err(|err| format!("Cannot write {}: {err}", output.display()))?;
+
+            eprintln!(
+                "Wrote {} matches ({} lines, {} bytes) to {}",
+                summary.matches,
+                summary.lines,
+                summary.bytes,
+                output.display()
+            );
+            Ok(())
+        }
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid number {value:?}"))
+}
+
+/// Bytes, with an optional K, M or G suffix counting in powers of 1024.
+fn parse_size(value: &str) -> Result<u64, String> {
+    let upper = value.trim().to_uppercase();
+    let digits = upper.trim_end_matches('B');
+    let (digits, unit) = match digits.chars().last() {
+        Some('K') => (&digits[..digits.len() - 1], 1 << 10),
+        Some('M') => (&digits[..digits.len() - 1], 1 << 20),
+        Some('G') => (&digits[..digits.len() - 1], 1 << 30),
+        _ => (digits, 1),
+    };
+
+    let size = digits
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid size {value:?}"))?;
+
+    size.checked_mul(unit)
+        .ok_or_else(|| format!("Size {value:?} is too large"))
+}
+
+fn validate(args: &[String]) -> Result<(), String> {
+    let mut json = false;
+    let mut logs = Vec::new();
+
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown option {flag}\n\n{USAGE}"))
+            }
+            log => logs.push(PathBuf::from(log)),
+        }
+    }
+    if logs.is_empty() {
+        return Err(format!("Pass the logs to validate\n\n{USAGE}"));
+    }
+
+    let mut errors = 0;
+    for log in logs.iter() {
+        let file =
+            File::open(log).map_err(|err| format!("Cannot read {}: {err}", log.display()))?;
+        let report = LogValidator::validate(BufReader::new(file))
+            .map_err(|err| format!("Cannot read {}: {err}", log.display()))?;
+        errors += report.count(Severity::Error);
+
+        if json {
+            println!(
+                "{}",
+                serde_json::to_string(&report).map_err(|err| err.to_string())?
+            );
+            continue;
+        }
+
+        for anomaly in report.anomalies.iter() {
+            println!(
+                "{}:{}: {}: {}",
+                log.display(),
+                anomaly.line,
+                anomaly.severity,
+                anomaly.message
+            );
+        }
+        let untrusted = report
+            .untrusted_matches()
+            .into_iter()
+            .collect::<Vec<String>>();
+        let untrusted = if untrusted.is_empty() {
+            String::new()
+        } else {
+            format!(", untrusted matches: {}", untrusted.join(", "))
+        };
+        println!(
+            "{}: {} lines, {} matches, {} errors, {} warnings{untrusted}",
+            log.display(),
+            report.lines,
+            report.matches,
+            report.count(Severity::Error),
+            report.count(Severity::Warning)
+        );
+    }
+
+    match errors {
+        0 => Ok(()),
+        errors => Err(format!("{errors} errors found")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_read_their_unit() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("4k"), Ok(4 << 10));
+        assert_eq!(parse_size("2G"), Ok(2 << 30));
+        assert_eq!(parse_size("3 MB"), Ok(3 << 20));
+        assert!(parse_size("big").is_err());
+        assert!(parse_size("20000000000G").is_err());
+    }
+}
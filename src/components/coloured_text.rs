@@ -0,0 +1,26 @@
+use leptos::*;
+
+use crate::model::names::{colour_segments, strip_colour_codes, PALETTE};
+
+/// Name or chat message with its colour codes drawn in the Quake 3 palette.
+///
+/// Black and blue get a light outline to stay readable on the dark background. Coloured text
+/// carries its plain form as a tooltip.
+#[component]
+pub fn ColouredText(cx: Scope, #[prop(into)] text: String) -> impl IntoView {
+    let plain = strip_colour_codes(&text);
+    let title = (plain != text).then_some(plain);
+
+    let segments = colour_segments(&text)
+        .into_iter()
+        .map(|segment| match segment.colour {
+            Some(index) => {
+                let outline = if index == 0 || index == 4 { "; text-shadow: 0 0 2px #e8ecf0" } else { "" };
+                view! { cx, <span style=format!("color: {}{outline}", PALETTE[index])>{segment.text}</span> }.into_view(cx)
+            }
+            None => segment.text.into_view(cx),
+        })
+        .collect_view(cx);
+
+    view! { cx, <span title=title>{segments}</span> }
+}
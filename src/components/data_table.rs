@@ -0,0 +1,269 @@
+use std::cmp::Ordering;
+
+use leptos::*;
+
+use crate::components::coloured_text::ColouredText;
+use crate::model::names::strip_colour_codes;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Sort {
+    pub column: usize,
+    pub direction: SortDirection,
+}
+
+impl Sort {
+    pub fn ascending(column: usize) -> Self {
+        Sort {
+            column,
+            direction: SortDirection::Ascending,
+        }
+    }
+
+    pub fn descending(column: usize) -> Self {
+        Sort {
+            column,
+            direction: SortDirection::Descending,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Column {
+    pub title: String,
+    /// Numeric columns are right aligned and sorted from the highest value first.
+    pub numeric: bool,
+}
+
+impl Column {
+    pub fn text(title: &str) -> Self {
+        Column {
+            title: title.to_string(),
+            numeric: false,
+        }
+    }
+
+    pub fn number(title: &str) -> Self {
+        Column {
+            title: title.to_string(),
+            numeric: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cell {
+    Text(String),
+    Integer(i64),
+    /// Shown with `precision` decimal places, sorted by the full value.
+    Decimal {
+        value: f64,
+        precision: usize,
+    },
+    Link {
+        text: String,
+        href: String,
+    },
+}
+
+impl Cell {
+    pub fn text(text: impl ToString) -> Self {
+        Cell::Text(text.to_string())
+    }
+
+    pub fn integer(value: impl Into<i64>) -> Self {
+        Cell::Integer(value.into())
+    }
+
+    pub fn link(text: impl ToString, href: impl ToString) -> Self {
+        Cell::Link {
+            text: text.to_string(),
+            href: href.to_string(),
+        }
+    }
+
+    /// What the cell shows, colour codes left out, also used by the filter and to sort.
+    pub fn display(&self) -> String {
+        match self {
+            Cell::Text(text) | Cell::Link { text, .. } => strip_colour_codes(text),
+            Cell::Integer(value) => value.to_string(),
+            Cell::Decimal { value, precision } => format!("{value:.precision$}"),
+        }
+    }
+
+    fn number(&self) -> Option<f64> {
+        match self {
+            Cell::Integer(value) => Some(*value as f64),
+            Cell::Decimal { value, .. } => Some(*value),
+            Cell::Text(_) | Cell::Link { .. } => None,
+        }
+    }
+
+    fn compare(&self, other: &Cell) -> Ordering {
+        match (self.number(), other.number()) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            _ => self
+                .display()
+                .to_lowercase()
+                .cmp(&other.display().to_lowercase()),
+        }
+    }
+}
+
+/// Table sorted by clicking its headers and filtered by the text of any of its cells.
+///
+/// Sorting is stable, so rows that tie keep the order they were given in.
+#[component]
+pub fn DataTable(
+    cx: Scope,
+    columns: Vec<Column>,
+    rows: Vec<Vec<Cell>>,
+    /// Order used until a header is clicked, the given order when not set.
+    #[prop(optional)]
+    default_sort: Option<Sort>,
+) -> impl IntoView {
+    let (sort, set_sort) = create_signal(cx, default_sort);
+    let (filter, set_filter) = create_signal(cx, String::new());
+    let rows = store_value(cx, rows);
+    let numeric_columns = store_value(
+        cx,
+        columns
+            .iter()
+            .map(|column| column.numeric)
+            .collect::<Vec<bool>>(),
+    );
+
+    let visible_rows = move || {
+        let filter = filter.get().to_lowercase();
+        let mut visible_rows = rows.with_value(|rows| {
+            rows.iter()
+                .filter(|row| {
+                    filter.is_empty()
+                        || row
+                            .iter()
+                            .any(|cell| cell.display().to_lowercase().contains(&filter))
+                })
+                .cloned()
+                .collect::<Vec<Vec<Cell>>>()
+        });
+
+        if let Some(sort) = sort.get() {
+            visible_rows.sort_by(|a, b| {
+                let ordering = a[sort.column].compare(&b[sort.column]);
+                match sort.direction {
+                    SortDirection::Ascending => ordering,
+                    SortDirection::Descending => ordering.reverse(),
+                }
+            });
+        }
+
+        visible_rows
+    };
+
+    let sort_by = move |column: usize, numeric: bool| {
+        set_sort.update(|sort| {
+            *sort = match sort {
+                Some(sort)
+                    if sort.column == column && sort.direction == SortDirection::Ascending =>
+                {
+                    Some(Sort::descending(column))
+                }
+                Some(sort) if sort.column == column => Some(Sort::ascending(column)),
+                _ if numeric => Some(Sort::descending(column)),
+                _ => Some(Sort::ascending(column)),
+            }
+        })
+    };
+
+    let headers = columns
+        .into_iter()
+        .enumerate()
+        .map(|(index, column)| {
+            let aria_sort = move || match sort.get() {
+                Some(sort)
+                    if sort.column == index && sort.direction == SortDirection::Ascending =>
+                {
+                    "ascending"
+                }
+                Some(sort) if sort.column == index => "descending",
+                _ => "none",
+            };
+            let indicator = move || match aria_sort() {
+                "ascending" => " ▲",
+                "descending" => " ▼",
+                _ => "",
+            };
+
+            view! { cx,
+                <th
+                    scope="col"
+                    class="px-6 py-3 cursor-pointer select-none"
+                    class:text-right=column.numeric
+                    aria-sort=aria_sort
+                    on:click=move |_| sort_by(index, column.numeric)
+                >
+                    {column.title}
+                    {indicator}
+                </th>
+            }
+        })
+        .collect_view(cx);
+
+    view! { cx,
+        <div class="flex flex-col gap-2">
+            <input
+                type="search"
+                placeholder="Filter"
+                aria-label="Filter rows"
+                class="px-3 py-1 text-sm rounded-lg bg-gray-700 border border-gray-600 placeholder-gray-400"
+                prop:value=move || filter.get()
+                on:input=move |event| set_filter.set(event_target_value(&event))
+            />
+            <div class="relative overflow-x-auto shadow-md sm:rounded-lg">
+                <table class="w-full text-sm text-left text-gray-400">
+                    <thead class="text-xs uppercase bg-gray-700 text-gray-400">
+                        <tr>{headers}</tr>
+                    </thead>
+                    <tbody>
+                        {move || {
+                            let visible_rows = visible_rows();
+                            if visible_rows.is_empty() {
+                                return view! { cx,
+                                    <tr class="border-b bg-gray-900 border-gray-700">
+                                        <td class="px-6 py-4 text-center" colspan=numeric_columns.with_value(Vec::len)>"No matching rows"</td>
+                                    </tr>
+                                }
+                                .into_view(cx);
+                            }
+
+                            visible_rows
+                                .into_iter()
+                                .map(|row| view! { cx,
+                                    <tr class="border-b bg-gray-900 border-gray-700">
+                                        {row.into_iter().enumerate().map(|(index, cell)| {
+                                            let numeric = numeric_columns.with_value(|numeric_columns| numeric_columns[index]);
+                                            let content = match cell {
+                                                Cell::Link { text, href } => view! { cx,
+                                                    <a href=href class="hover:underline"><ColouredText text/></a>
+                                                }.into_view(cx),
+                                                Cell::Text(text) => view! { cx, <ColouredText text/> }.into_view(cx),
+                                                cell => cell.display().into_view(cx),
+                                            };
+
+                                            view! { cx, <td class="px-6 py-4" class:text-right=numeric>{content}</td> }
+                                        }).collect_view(cx)}
+                                    </tr>
+                                })
+                                .collect_view(cx)
+                        }}
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
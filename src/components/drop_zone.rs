@@ -0,0 +1,164 @@
+use leptos::*;
+
+use crate::components::upload_status::format_size;
+
+/// How much of a file is read to preview and check it before uploading.
+const PREVIEW_BYTES: f64 = 8.0 * 1024.0;
+const PREVIEW_LINES: usize = 12;
+
+/// First lines of a picked file and whether they look like a server log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogPreview {
+    pub name: String,
+    pub size: f64,
+    pub lines: Vec<String>,
+    pub warning: Option<String>,
+}
+
+impl LogPreview {
+    /// Checks `head`, the first bytes of the file, for what every Quake 3 Arena log starts with.
+    pub fn from_head(name: String, size: f64, head: &str) -> Self {
+        let warning = if size == 0.0 {
+            Some("The file is empty.".to_string())
+        } else if head.contains('\0') {
+            Some("This looks like a binary file, not a Quake 3 Arena server log.".to_string())
+        } else if !head.contains("InitGame:") {
+            Some(format!(
+                "No InitGame: line in the first {}, this does not look like a Quake 3 Arena server log. Configs and qconsole.log files cannot be parsed.",
+                format_size(PREVIEW_BYTES)
+            ))
+        } else {
+            None
+        };
+
+        LogPreview {
+            name,
+            size,
+            lines: head.lines().take(PREVIEW_LINES).map(String::from).collect(),
+            warning,
+        }
+    }
+}
+
+/// A file waiting to be uploaded.
+#[derive(Clone, Debug)]
+struct SelectedLog {
+    file: web_sys::File,
+    preview: LogPreview,
+}
+
+async fn read_preview(file: web_sys::File) -> SelectedLog {
+    let head = match file.slice_with_f64_and_f64(0.0, PREVIEW_BYTES) {
+        Ok(blob) => wasm_bindgen_futures::JsFuture::from(blob.text())
+            .await
+            .ok()
+            .and_then(|text| text.as_string())
+            .unwrap_or_default(),
+        Err(_) => String::new(),
+    };
+
+    SelectedLog {
+        preview: LogPreview::from_head(file.name(), file.size(), &head),
+        file,
+    }
+}
+
+/// Area to drop a log on, or click to browse for one, showing what was picked before `on_submit`
+/// gets it.
+#[component]
+pub fn LogDropZone<F>(cx: Scope, disabled: ReadSignal<bool>, on_submit: F) -> impl IntoView
+where
+    F: Fn(web_sys::File) + Copy + 'static,
+{
+    let (dragging, set_dragging) = create_signal(cx, false);
+    let preview_action = create_action(cx, |file: &web_sys::File| read_preview(file.clone()));
+    let selected = preview_action.value();
+
+    let select = move |file: Option<web_sys::File>| {
+        if let Some(file) = file {
+            preview_action.dispatch(file);
+        }
+    };
+
+    let submit = move |_| {
+        if let Some(selected_log) = selected.get_untracked() {
+            selected.set(None);
+            on_submit(selected_log.file);
+        }
+    };
+
+    view! { cx,
+        <div class="flex flex-col gap-4">
+            <label
+                for="file-input"
+                class="flex flex-col gap-2 items-center justify-center w-full h-40 border-2 border-dashed rounded-lg cursor-pointer bg-gray-800 hover:bg-gray-700"
+                class:border-blue-500=move || dragging.get()
+                class:border-gray-600=move || !dragging.get()
+                class:opacity-50=move || disabled.get()
+                on:dragover=move |event| {
+                    event.prevent_default();
+                    set_dragging.set(true);
+                }
+                on:dragleave=move |_| set_dragging.set(false)
+                on:drop=move |event| {
+                    event.prevent_default();
+                    set_dragging.set(false);
+                    if !disabled.get_untracked() {
+                        select(event.data_transfer().and_then(|data| data.files()).and_then(|files| files.get(0)));
+                    }
+                }
+            >
+                <span class="text-lg font-medium">"Drop a server log here"</span>
+                <span class="text-sm text-gray-400">"or click to browse"</span>
+                <input
+                    type="file"
+                    accept=".log,.txt"
+                    id="file-input"
+                    name="file-input"
+                    class="sr-only"
+                    prop:disabled=move || disabled.get()
+                    on:change=move |event| {
+                        let input = event_target::<web_sys::HtmlInputElement>(&event);
+                        select(input.files().and_then(|files| files.get(0)));
+                        // Lets the same file be picked again
+                        input.set_value("");
+                    }
+                />
+            </label>
+
+            {move || selected.get().map(|selected_log| {
+                let preview = selected_log.preview;
+
+                view! { cx,
+                    <div class="flex flex-col gap-3 p-4 rounded-lg bg-[#3c4450]">
+                        <div class="flex flex-row gap-4 items-baseline">
+                            <span class="font-medium break-all">{preview.name}</span>
+                            <span class="text-sm text-gray-300">{format_size(preview.size)}</span>
+                        </div>
+                        {preview.warning.map(|warning| view! { cx,
+                            <p class="text-sm text-yellow-300" role="alert">{warning}</p>
+                        })}
+                        <pre class="p-2 text-xs text-left overflow-auto max-h-48 rounded bg-gray-900">{preview.lines.join("\n")}</pre>
+                        <div class="flex flex-row gap-2 justify-end">
+                            <button
+                                type="button"
+                                class="px-3 py-1 rounded-lg bg-gray-700 hover:bg-gray-600 text-sm"
+                                on:click=move |_| selected.set(None)
+                            >
+                                "Clear"
+                            </button>
+                            <button
+                                type="button"
+                                class="px-3 py-1 rounded-lg bg-blue-600 hover:bg-blue-500 text-sm font-medium"
+                                prop:disabled=move || disabled.get()
+                                on:click=submit
+                            >
+                                "Parse log"
+                            </button>
+                        </div>
+                    </div>
+                }
+            })}
+        </div>
+    }
+}
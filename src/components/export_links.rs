@@ -0,0 +1,42 @@
+use leptos::*;
+
+use crate::model::export::TABLE_NAMES;
+
+/// Download links for an export endpoint, one per format.
+///
+/// `tables` adds a CSV link for each table, which the API only allows for single matches.
+#[component]
+pub fn ExportLinks(cx: Scope, endpoint: String, #[prop(optional)] tables: bool) -> impl IntoView {
+    let link = |query: String, label: String| {
+        view! { cx,
+            <a
+                href=format!("{endpoint}?{query}")
+                download=""
+                class="px-3 py-1 rounded-lg bg-gray-700 hover:bg-gray-600 text-sm"
+            >
+                {label}
+            </a>
+        }
+    };
+
+    let mut links = vec![
+        link("format=csv".to_string(), "CSV".to_string()),
+        link("format=markdown".to_string(), "Markdown".to_string()),
+        link("format=xlsx".to_string(), "XLSX".to_string()),
+    ];
+    if tables {
+        links.extend(TABLE_NAMES.iter().map(|table| {
+            link(
+                format!("format=csv&table={table}"),
+                format!("CSV ({table})"),
+            )
+        }));
+    }
+
+    view! { cx,
+        <div class="flex flex-row flex-wrap gap-2 justify-center items-center">
+            <span class="text-sm text-gray-400">"Download:"</span>
+            {links.collect_view(cx)}
+        </div>
+    }
+}
@@ -0,0 +1,102 @@
+use leptos::*;
+
+use crate::components::coloured_text::ColouredText;
+use crate::model::parser::{Highlight, MatchRecord};
+
+fn multi_kill_name(kills: u16) -> String {
+    match kills {
+        2 => "Double kill".to_string(),
+        3 => "Triple kill".to_string(),
+        kills => format!("{kills}x multi-kill"),
+    }
+}
+
+/// Badges for a match's first blood, longest streak, best multi-kill and biggest streak ended.
+#[component]
+pub fn HighlightBadges(cx: Scope, record: MatchRecord) -> impl IntoView {
+    let badge = |label: &'static str, content: View, class: &'static str| {
+        view! { cx,
+            <span class=format!("px-3 py-1 rounded-full text-sm {class}")>
+                <span class="font-bold">{label}</span>
+                " "
+                {content}
+            </span>
+        }
+    };
+    let name = |player: &str| view! { cx, <ColouredText text=player/> };
+
+    let mut badges = vec![];
+    if let Some(player) = &record.awards.first_blood {
+        badges.push(badge(
+            "First blood",
+            name(player).into_view(cx),
+            "bg-red-800",
+        ));
+    }
+    if let Some((player, streak)) = record.awards.top_streak() {
+        let content = view! { cx, {name(player)}{format!(" ({streak} frags)")} }.into_view(cx);
+        badges.push(badge("Longest streak", content, "bg-orange-700"));
+    }
+    if let Some((player, kills)) = record.awards.top_multi_kill() {
+        let content =
+            view! { cx, {name(player)}{format!(" ({})", multi_kill_name(kills))} }.into_view(cx);
+        badges.push(badge("Best multi-kill", content, "bg-purple-700"));
+    }
+    let biggest_streak_ended = record
+        .highlights
+        .iter()
+        .filter_map(|highlight| match highlight {
+            Highlight::StreakEnded {
+                player,
+                streak,
+                ended_by,
+                ..
+            } => Some((player, *streak, ended_by)),
+            _ => None,
+        })
+        .fold(
+            None,
+            |biggest: Option<(&String, u16, &String)>, ended| match biggest {
+                Some(biggest) if biggest.1 >= ended.1 => Some(biggest),
+                _ => Some(ended),
+            },
+        );
+    if let Some((player, streak, ended_by)) = biggest_streak_ended {
+        let content = view! { cx,
+            {name(ended_by)}" ended "{name(player)}{format!("'s {streak}-kill streak")}
+        }
+        .into_view(cx);
+        badges.push(badge("Streak ended", content, "bg-gray-600"));
+    }
+
+    (!badges.is_empty()).then(|| view! { cx,
+        <div class="flex flex-row flex-wrap gap-2 justify-center">{badges.collect_view(cx)}</div>
+    })
+}
+
+/// Awards the rules gave for a match, one card each.
+#[component]
+pub fn AwardList(cx: Scope, record: MatchRecord) -> impl IntoView {
+    let cards = record
+        .awards
+        .titles
+        .into_iter()
+        .map(|award| view! { cx,
+            <div class="flex flex-col px-4 py-2 rounded-lg bg-gray-700 text-center" title=award.id>
+                <span class="text-sm text-gray-400">{award.title}</span>
+                <span class="font-medium">{award.players.iter().enumerate().map(|(index, player)| view! { cx,
+                    {(index > 0).then_some(", ")}
+                    <ColouredText text=player.clone()/>
+                }).collect_view(cx)}</span>
+                <span class="text-sm">{award.detail}</span>
+            </div>
+        })
+        .collect::<Vec<_>>();
+
+    (!cards.is_empty()).then(|| view! { cx,
+        <div class="flex flex-col gap-2">
+            <h3 class="text-xl font-medium text-center">"Awards"</h3>
+            <div class="flex flex-row flex-wrap gap-2 justify-center">{cards.collect_view(cx)}</div>
+        </div>
+    })
+}
@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use leptos::*;
+
+use crate::components::coloured_text::ColouredText;
+use crate::model::parser::{format_game_time, MatchRecord};
+use crate::model::replay::Replay;
+
+const SPEEDS: [u32; 5] = [1, 2, 4, 8, 16];
+const TICK: Duration = Duration::from_millis(250);
+
+/// Short label and colour standing for a means of death in the feed.
+fn weapon_icon(means: &str) -> (&'static str, &'static str) {
+    match means.trim_end_matches("_SPLASH") {
+        "MOD_GAUNTLET" => ("G", "bg-gray-500"),
+        "MOD_MACHINEGUN" => ("MG", "bg-yellow-700"),
+        "MOD_SHOTGUN" => ("SG", "bg-orange-700"),
+        "MOD_GRENADE" => ("GL", "bg-green-700"),
+        "MOD_ROCKET" => ("RL", "bg-red-700"),
+        "MOD_PLASMA" => ("PG", "bg-purple-700"),
+        "MOD_RAILGUN" => ("RG", "bg-teal-700"),
+        "MOD_LIGHTNING" => ("LG", "bg-sky-700"),
+        "MOD_BFG" => ("BFG", "bg-lime-700"),
+        "MOD_NAIL" => ("NG", "bg-stone-600"),
+        "MOD_CHAINGUN" => ("CG", "bg-amber-700"),
+        "MOD_PROXIMITY_MINE" => ("PL", "bg-rose-800"),
+        "MOD_TELEFRAG" => ("TF", "bg-fuchsia-700"),
+        _ => ("✝", "bg-gray-700"),
+    }
+}
+
+/// Plays a match back as a kill feed, with the scoreboard as it stood at each moment.
+///
+/// Only runs in the browser, the server renders it paused at the start of the match.
+#[component]
+pub fn KillFeedReplay(cx: Scope, record: MatchRecord) -> impl IntoView {
+    let replay = store_value(cx, Replay::from_record(&record));
+    let duration = replay.with_value(Replay::duration);
+    let elapsed = create_rw_signal(cx, 0.0_f64);
+    let second = create_memo(cx, move |_| elapsed.get() as u32);
+    let speed = create_rw_signal(cx, 1_u32);
+    let playing = create_rw_signal(cx, false);
+    let interval = store_value::<Option<IntervalHandle>>(cx, None);
+
+    let stop_interval = move || {
+        interval.update_value(|interval| {
+            if let Some(handle) = interval.take() {
+                handle.clear();
+            }
+        })
+    };
+    on_cleanup(cx, stop_interval);
+
+    let pause = move || {
+        stop_interval();
+        playing.set(false);
+    };
+
+    let play = move || {
+        if elapsed.get_untracked() >= duration as f64 {
+            elapsed.set(0.0);
+        }
+
+        let tick = move || {
+            let next = elapsed.get_untracked() + speed.get_untracked() as f64 * TICK.as_secs_f64();
+            if next >= duration as f64 {
+                elapsed.set(duration as f64);
+                pause();
+            } else {
+                elapsed.set(next);
+            }
+        };
+
+        if let Ok(handle) = set_interval_with_handle(tick, TICK) {
+            stop_interval();
+            interval.set_value(Some(handle));
+            playing.set(true);
+        }
+    };
+
+    let feed = move || {
+        replay.with_value(|replay| {
+            replay
+                .kills_until(second.get())
+                .iter()
+                .rev()
+                .map(|kill| {
+                    let (label, colour) = weapon_icon(&kill.means);
+                    let time = format_game_time(kill.time.saturating_sub(replay.start_time));
+
+                    view! { cx,
+                        <li class="flex flex-row gap-3 items-center px-4 py-2 rounded-lg bg-gray-900 text-sm">
+                            <span class="w-12 text-gray-400">{time}</span>
+                            <span class="font-medium"><ColouredText text=kill.killer.clone()/></span>
+                            <span class=format!("px-2 py-0.5 rounded text-xs font-bold text-white {colour}") title=kill.means.clone()>
+                                {label}
+                            </span>
+                            <span class="font-medium"><ColouredText text=kill.killed.clone()/></span>
+                        </li>
+                    }
+                })
+                .collect_view(cx)
+        })
+    };
+
+    let scoreboard = move || {
+        replay
+            .with_value(|replay| replay.standings_at(second.get()))
+            .into_iter()
+            .map(|ranking_position| view! { cx,
+                <tr class="border-b bg-gray-900 border-gray-700">
+                    <td class="px-4 py-2">{ranking_position.position}</td>
+                    <td class="px-4 py-2 font-medium"><ColouredText text=ranking_position.player/></td>
+                    <td class="px-4 py-2 text-right">{ranking_position.kills}</td>
+                </tr>
+            })
+            .collect_view(cx)
+    };
+
+    view! { cx,
+        <div class="flex flex-col gap-6">
+            <div class="flex flex-row flex-wrap gap-4 items-center justify-center">
+                <button
+                    type="button"
+                    class="w-20 px-3 py-1 rounded-lg bg-blue-600 hover:bg-blue-500 text-sm font-medium"
+                    on:click=move |_| if playing.get_untracked() { pause() } else { play() }
+                >
+                    {move || if playing.get() { "Pause" } else { "Play" }}
+                </button>
+
+                <div class="flex flex-row gap-1" role="group" aria-label="Replay speed">
+                    {SPEEDS.iter().map(|&option| view! { cx,
+                        <button
+                            type="button"
+                            class="px-2 py-1 rounded-lg text-sm"
+                            class:bg-gray-500=move || speed.get() == option
+                            class:bg-gray-700=move || speed.get() != option
+                            aria-pressed=move || (speed.get() == option).to_string()
+                            on:click=move |_| speed.set(option)
+                        >
+                            {format!("{option}x")}
+                        </button>
+                    }).collect_view(cx)}
+                </div>
+
+                <input
+                    type="range"
+                    min="0"
+                    max=duration
+                    step="1"
+                    aria-label="Match time"
+                    class="w-1/2"
+                    prop:value=move || second.get().to_string()
+                    on:input=move |event| {
+                        if let Ok(value) = event_target_value(&event).parse::<f64>() {
+                            elapsed.set(value);
+                        }
+                    }
+                />
+
+                <span class="text-sm text-gray-300">
+                    {move || format!("{} / {}", format_game_time(second.get()), format_game_time(duration))}
+                </span>
+            </div>
+
+            <div class="flex flex-row w-full gap-8">
+                <div class="w-2/3">
+                    <h3 class="text-xl font-medium text-center mb-2">"Kill feed"</h3>
+                    <ul class="flex flex-col gap-2 h-96 overflow-y-auto" aria-live="polite">{feed}</ul>
+                </div>
+
+                <div class="w-1/3">
+                    <h3 class="text-xl font-medium text-center mb-2">"Scoreboard"</h3>
+                    <table class="w-full text-sm text-left text-gray-400">
+                        <thead class="text-xs uppercase bg-gray-700 text-gray-400">
+                            <tr>
+                                <th scope="col" class="px-4 py-2">"#"</th>
+                                <th scope="col" class="px-4 py-2">"Player"</th>
+                                <th scope="col" class="px-4 py-2 text-right">"Kills"</th>
+                            </tr>
+                        </thead>
+                        <tbody>{scoreboard}</tbody>
+                    </table>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,76 @@
+use leptos::*;
+
+use crate::components::data_table::{Cell, Column, DataTable};
+use crate::links::{match_href, player_href};
+use crate::model::player::{kill_death_ratio, PlayerProfile};
+use crate::store::{match_number, StoredMatch};
+
+/// Every player's totals, in the order the profiles are given.
+#[component]
+pub fn LeaderboardTable(cx: Scope, profiles: Vec<PlayerProfile>) -> impl IntoView {
+    let columns = vec![
+        Column::text("Player"),
+        Column::number("Matches"),
+        Column::number("Wins"),
+        Column::number("Kills"),
+        Column::number("Deaths"),
+        Column::number("K/D"),
+    ];
+    let rows = profiles
+        .into_iter()
+        .map(|profile| {
+            let kills = profile.total_kills();
+            let deaths = profile.total_deaths();
+
+            vec![
+                Cell::link(&profile.name, player_href(cx, &profile.name)),
+                Cell::integer(profile.matches.len() as i64),
+                Cell::integer(profile.wins() as i64),
+                Cell::integer(kills),
+                Cell::integer(deaths),
+                Cell::Decimal {
+                    value: kill_death_ratio(kills, deaths),
+                    precision: 2,
+                },
+            ]
+        })
+        .collect::<Vec<Vec<Cell>>>();
+
+    view! { cx, <DataTable columns rows/> }
+}
+
+/// Stored matches with their map, winner and total kills, in the order they are given.
+#[component]
+pub fn MatchListTable(cx: Scope, matches: Vec<StoredMatch>) -> impl IntoView {
+    let columns = vec![
+        Column::text("Match"),
+        Column::text("Map"),
+        Column::text("Winner"),
+        Column::number("Total kills"),
+    ];
+    let rows = matches
+        .into_iter()
+        .map(|stored_match| {
+            let record = stored_match.record;
+            let winner = match record.ranking.first() {
+                Some(ranking_position) => Cell::link(
+                    &ranking_position.player,
+                    player_href(cx, &ranking_position.player),
+                ),
+                None => Cell::text("Nobody"),
+            };
+
+            vec![
+                Cell::link(
+                    format!("Match {}", match_number(&stored_match.match_key)),
+                    match_href(cx, &stored_match.id),
+                ),
+                Cell::text(record.metadata.map.unwrap_or_else(|| "Unknown".to_string())),
+                winner,
+                Cell::integer(record.total_kills),
+            ]
+        })
+        .collect::<Vec<Vec<Cell>>>();
+
+    view! { cx, <DataTable columns rows/> }
+}
@@ -0,0 +1,54 @@
+use leptos::*;
+
+use crate::components::data_table::{Cell, Column, DataTable, Sort};
+use crate::links::player_href;
+use crate::model::parser::MatchRecord;
+
+/// Players ranking of a match, player names linking to their profiles.
+#[component]
+pub fn RankingTable(cx: Scope, record: MatchRecord) -> impl IntoView {
+    let columns = vec![
+        Column::number("Ranking"),
+        Column::text("Player"),
+        Column::number("Kill Score"),
+    ];
+    let rows = record
+        .ranking
+        .into_iter()
+        .map(|ranking_position| {
+            vec![
+                Cell::integer(ranking_position.position),
+                Cell::link(
+                    &ranking_position.player,
+                    player_href(cx, &ranking_position.player),
+                ),
+                Cell::integer(ranking_position.kills),
+            ]
+        })
+        .collect::<Vec<Vec<Cell>>>();
+
+    view! { cx, <DataTable columns rows default_sort=Sort::ascending(0)/> }
+}
+
+/// Kills by means of death, most used first and ties ordered by name.
+#[component]
+pub fn MeansTable(cx: Scope, record: MatchRecord) -> impl IntoView {
+    if record.kills_by_means.is_empty() {
+        return view! { cx, <p class="text-center">"No valid means of kills registered"</p> }
+            .into_view(cx);
+    }
+
+    let mut kills_by_means = record
+        .kills_by_means
+        .into_iter()
+        .collect::<Vec<(String, i16)>>();
+    kills_by_means.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let columns = vec![Column::text("Damage source"), Column::number("Kills")];
+    let rows = kills_by_means
+        .into_iter()
+        .map(|(means, kills)| vec![Cell::text(means), Cell::integer(kills)])
+        .collect::<Vec<Vec<Cell>>>();
+
+    view! { cx, <DataTable columns rows default_sort=Sort::descending(1)/> }.into_view(cx)
+}
@@ -1 +1,10 @@
-
+pub mod coloured_text;
+pub mod data_table;
+pub mod drop_zone;
+pub mod export_links;
+pub mod highlights;
+pub mod kill_feed;
+pub mod leaderboard;
+pub mod match_tables;
+pub mod score_chart;
+pub mod upload_status;
@@ -0,0 +1,194 @@
+use leptos::*;
+
+use crate::components::coloured_text::ColouredText;
+use crate::model::names::strip_colour_codes;
+use crate::model::parser::{format_game_time, MatchRecord};
+use crate::model::timeline::ScoreTimeline;
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 320.0;
+const PADDING_LEFT: f64 = 40.0;
+const PADDING_RIGHT: f64 = 16.0;
+const PADDING_TOP: f64 = 16.0;
+const PADDING_BOTTOM: f64 = 32.0;
+
+/// Line colours, reused when a match has more players than colours.
+const COLOURS: [&str; 8] = [
+    "#60a5fa", "#f87171", "#4ade80", "#facc15", "#c084fc", "#fb923c", "#2dd4bf", "#f472b6",
+];
+
+/// Maps game times and scores to coordinates inside the plot area.
+struct Scale {
+    start_time: u32,
+    end_time: u32,
+    min_score: i16,
+    max_score: i16,
+}
+
+impl Scale {
+    fn x(&self, time: u32) -> f64 {
+        let span = self.end_time.saturating_sub(self.start_time).max(1) as f64;
+        PADDING_LEFT
+            + (time.saturating_sub(self.start_time) as f64 / span)
+                * (WIDTH - PADDING_LEFT - PADDING_RIGHT)
+    }
+
+    fn y(&self, score: i16) -> f64 {
+        let span = (self.max_score - self.min_score).max(1) as f64;
+        PADDING_TOP
+            + ((self.max_score - score) as f64 / span) * (HEIGHT - PADDING_TOP - PADDING_BOTTOM)
+    }
+
+    /// Up to six evenly spread integer scores for the horizontal grid lines.
+    fn score_ticks(&self) -> Vec<i16> {
+        let step = ((self.max_score - self.min_score) as f64 / 5.0)
+            .ceil()
+            .max(1.0) as i16;
+        (self.min_score..=self.max_score)
+            .step_by(step as usize)
+            .collect()
+    }
+}
+
+/// Cumulative kill score of each player along the match, with a marker wherever the lead changed.
+///
+/// Plain SVG, so it renders on the server like the rest of the page.
+#[component]
+pub fn ScoreChart(cx: Scope, record: MatchRecord) -> impl IntoView {
+    let timeline = ScoreTimeline::from_record(&record);
+
+    if timeline.series.is_empty() {
+        return view! { cx, <p class="text-center">"No players joined this match"</p> }
+            .into_view(cx);
+    }
+
+    let (min_score, max_score) = timeline.score_range();
+    let scale = Scale {
+        start_time: timeline.start_time,
+        end_time: timeline.end_time,
+        min_score,
+        max_score: max_score.max(min_score + 1),
+    };
+    let colour = |player: &str| {
+        let index = timeline
+            .series
+            .keys()
+            .position(|name| name == player)
+            .unwrap_or_default();
+        COLOURS[index % COLOURS.len()]
+    };
+
+    let grid = scale
+        .score_ticks()
+        .into_iter()
+        .map(|score| {
+            let y = scale.y(score);
+            view! { cx,
+                <line x1=PADDING_LEFT x2=WIDTH - PADDING_RIGHT y1=y y2=y stroke="#3c4450" stroke-width="1"/>
+                <text x=PADDING_LEFT - 8.0 y=y + 4.0 text-anchor="end" font-size="12" fill="#9ca3af">{score}</text>
+            }
+        })
+        .collect_view(cx);
+
+    let time_labels = [
+        (timeline.start_time, "start"),
+        (
+            timeline.start_time + (timeline.end_time - timeline.start_time) / 2,
+            "middle",
+        ),
+        (timeline.end_time, "end"),
+    ]
+    .into_iter()
+    .map(|(time, anchor)| {
+        view! { cx,
+            <text x=scale.x(time) y=HEIGHT - 8.0 text-anchor=anchor font-size="12" fill="#9ca3af">
+                {format_game_time(time)}
+            </text>
+        }
+    })
+    .collect_view(cx);
+
+    let lines = timeline
+        .series
+        .iter()
+        .map(|(player, points)| {
+            // Steps, since a score only changes at the moment of a kill
+            let mut coordinates = Vec::new();
+            let mut last_y = None;
+            for point in points.iter() {
+                let x = scale.x(point.time);
+                if let Some(last_y) = last_y {
+                    coordinates.push(format!("{x:.1},{last_y:.1}"));
+                }
+                let y = scale.y(point.score);
+                coordinates.push(format!("{x:.1},{y:.1}"));
+                last_y = Some(y);
+            }
+            if let Some(last_y) = last_y {
+                coordinates.push(format!("{:.1},{last_y:.1}", scale.x(timeline.end_time)));
+            }
+
+            view! { cx,
+                <polyline
+                    points=coordinates.join(" ")
+                    fill="none"
+                    stroke=colour(player)
+                    stroke-width="2"
+                    stroke-linejoin="round"
+                />
+            }
+        })
+        .collect_view(cx);
+
+    let markers = timeline
+        .lead_changes
+        .iter()
+        .map(|lead_change| view! { cx,
+            <circle
+                cx=scale.x(lead_change.time)
+                cy=scale.y(lead_change.score)
+                r="5"
+                fill=colour(&lead_change.leader)
+                stroke="#e8ecf0"
+                stroke-width="1.5"
+            >
+                <title>{format!("{} takes the lead at {}", strip_colour_codes(&lead_change.leader), format_game_time(lead_change.time))}</title>
+            </circle>
+        })
+        .collect_view(cx);
+
+    let legend = timeline
+        .series
+        .keys()
+        .map(|player| view! { cx,
+            <li class="flex flex-row gap-2 items-center">
+                <span class="inline-block w-3 h-3 rounded-full" style=format!("background-color: {}", colour(player))></span>
+                <ColouredText text=player.clone()/>
+            </li>
+        })
+        .collect_view(cx);
+
+    view! { cx,
+        <div class="flex flex-col gap-4 items-center">
+            <svg
+                viewBox=format!("0 0 {WIDTH} {HEIGHT}")
+                class="w-full"
+                role="img"
+                aria-label="Kill score of each player over the match"
+            >
+                {grid}
+                {time_labels}
+                {lines}
+                {markers}
+            </svg>
+            <ul class="flex flex-row flex-wrap gap-4 justify-center text-sm">
+                {legend}
+                <li class="flex flex-row gap-2 items-center text-gray-400">
+                    <span class="inline-block w-3 h-3 rounded-full border border-[#e8ecf0]"></span>
+                    "Lead change"
+                </li>
+            </ul>
+        </div>
+    }
+    .into_view(cx)
+}
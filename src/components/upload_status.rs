@@ -0,0 +1,98 @@
+use leptos::*;
+
+/// Where the log being parsed is at.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UploadStatus {
+    Idle,
+    Reading,
+    /// Bytes of the upload sent so far.
+    Uploading {
+        loaded: f64,
+        total: f64,
+    },
+    Parsing,
+    Cancelled,
+}
+
+impl UploadStatus {
+    /// Progress of the upload, between 0 and 100.
+    pub fn percent(&self) -> Option<f64> {
+        match self {
+            UploadStatus::Uploading { loaded, total } if *total > 0.0 => {
+                Some((loaded / total * 100.0).min(100.0))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// File size for people, in KB under a megabyte and in MB above.
+pub fn format_size(bytes: f64) -> String {
+    if bytes < 1024.0 * 1024.0 {
+        format!("{:.1} KB", bytes / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes / 1024.0 / 1024.0)
+    }
+}
+
+/// Progress bar, spinner and cancel button shown while a log is read, uploaded and parsed.
+#[component]
+pub fn UploadProgress<F>(
+    cx: Scope,
+    status: RwSignal<UploadStatus>,
+    pending: ReadSignal<bool>,
+    on_cancel: F,
+) -> impl IntoView
+where
+    F: Fn() + Copy + 'static,
+{
+    let message = move || match status.get() {
+        UploadStatus::Idle => String::new(),
+        UploadStatus::Reading => "Reading the file...".to_string(),
+        UploadStatus::Uploading { loaded, total } => format!(
+            "Uploading {} of {}",
+            format_size(loaded),
+            format_size(total)
+        ),
+        UploadStatus::Parsing => "Parsing the log...".to_string(),
+        UploadStatus::Cancelled => "Upload cancelled".to_string(),
+    };
+
+    view! { cx,
+        <Show
+            when=move || pending.get() || status.get() == UploadStatus::Cancelled
+            fallback=|_| ()
+        >
+            <div class="flex flex-col gap-2 my-8 items-center" role="status" aria-live="polite">
+                <div class="flex flex-row gap-3 items-center">
+                    <Show when=move || matches!(status.get(), UploadStatus::Reading | UploadStatus::Parsing) fallback=|_| ()>
+                        <span class="inline-block w-5 h-5 rounded-full border-2 border-gray-400 border-t-transparent animate-spin"></span>
+                    </Show>
+                    <span class="text-lg">{message}</span>
+                </div>
+
+                {move || status.get().percent().map(|percent| view! { cx,
+                    <div
+                        class="w-1/2 h-2.5 rounded-full bg-gray-700"
+                        role="progressbar"
+                        aria-valuemin="0"
+                        aria-valuemax="100"
+                        aria-valuenow=format!("{percent:.0}")
+                    >
+                        <div class="h-2.5 rounded-full bg-blue-600" style=format!("width: {percent:.1}%")></div>
+                    </div>
+                })}
+
+                <Show when=move || pending.get() fallback=|_| ()>
+                    <button
+                        type="button"
+                        class="px-3 py-1 rounded-lg bg-gray-700 hover:bg-gray-600 text-sm"
+                        on:click=move |_| on_cancel()
+                    >
+                        "Cancel"
+                    </button>
+                </Show>
+            </div>
+        </Show>
+    }
+}
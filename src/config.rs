@@ -0,0 +1,321 @@
+use cfg_if::cfg_if;
+
+use crate::model::parser::ParserOptions;
+
+/// Name of the `<meta>` tag carrying the public base URL from the server to the browser.
+pub const PUBLIC_BASE_URL_META: &str = "q3a-public-base-url";
+/// Name of the `<meta>` tag carrying the parser options, as JSON, so logs parsed in the browser
+/// get the same highlights as the ones parsed by the server.
+pub const PARSER_OPTIONS_META: &str = "q3a-parser-options";
+
+/// Settings the browser needs, provided as context while rendering on the server.
+#[derive(Clone, Debug, Default)]
+pub struct ClientSettings {
+    pub public_base_url: Option<String>,
+    pub parser_options: ParserOptions,
+}
+
+fn meta_content(name: &str) -> Option<String> {
+    leptos::document()
+        .query_selector(&format!("meta[name=\"{name}\"]"))
+        .ok()
+        .flatten()
+        .and_then(|meta| meta.get_attribute("content"))
+}
+
+/// Absolute URL of an API `path`, using the configured public base URL when the server rendered
+/// one, or the origin that served the page otherwise.
+pub fn api_url(path: &str) -> String {
+    let base_url = meta_content(PUBLIC_BASE_URL_META)
+        .unwrap_or_else(|| leptos::window().location().origin().unwrap());
+
+    format!("{}{}", base_url.trim_end_matches('/'), path)
+}
+
+/// Parser options the server rendered into the page, the defaults otherwise.
+pub fn client_parser_options() -> ParserOptions {
+    meta_content(PARSER_OPTIONS_META)
+        .and_then(|options| serde_json::from_str(&options).ok())
+        .unwrap_or_default()
+}
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::model::dialect::Dialect;
+use crate::model::parallel::ParallelParser;
+use crate::model::rating::RatingSettings;
+
+/// Configuration file read when `Q3A_CONFIG` doesn't point to another one.
+pub const DEFAULT_CONFIG_FILE: &str = "q3aparser.toml";
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Cannot read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Invalid configuration file {path}: {source}")]
+    Toml {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("Invalid value {value:?} for {name}")]
+    Env { name: String, value: String },
+    #[error("Invalid [ratings] settings: {0}")]
+    Ratings(String),
+}
+
+/// Server settings, loaded from a TOML file and overridden by `Q3A_*` environment variables.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Address the server listens on, defaults to the cargo-leptos `site-addr`.
+    pub bind_address: Option<SocketAddr>,
+    /// URL the browser uses to reach the API, when it differs from the one serving the pages.
+    pub public_base_url: Option<String>,
+    pub max_upload_bytes: usize,
+    /// Where uploaded logs are written while being parsed.
+    pub temp_dir: PathBuf,
+    /// Where parsed uploads are kept, so their match pages survive restarts.
+    pub data_dir: PathBuf,
+    /// TOML file of player aliases and clan tags, see `AliasMap`.
+    pub alias_file: Option<PathBuf>,
+    pub cors_allowed_origins: Vec<String>,
+    pub request_timeout_secs: u64,
+    /// `games.log` followed by the live page.
+    pub live_log: Option<PathBuf>,
+    pub live_poll_interval_ms: u64,
+    /// Frags at most this many seconds after the first one of a chain make a multi-kill.
+    pub multi_kill_window_secs: u32,
+    /// Game the logs come from, detected for each match when unset.
+    pub dialect: Option<Dialect>,
+    /// Threads parsing each upload, as many as the machine runs in parallel when unset.
+    pub parse_threads: Option<usize>,
+    /// Uploads parsed at the same time, the others waiting for their turn.
+    pub concurrent_parses: usize,
+    /// How the ratings leaderboard rates players, the `[ratings]` table.
+    pub ratings: RatingSettings,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind_address: None,
+            public_base_url: None,
+            max_upload_bytes: 512 * 1024 * 1024,
+            temp_dir: PathBuf::from("./tmp/"),
+            data_dir: PathBuf::from("./data/"),
+            alias_file: None,
+            cors_allowed_origins: Vec::new(),
+            request_timeout_secs: 300,
+            live_log: None,
+            live_poll_interval_ms: 500,
+            multi_kill_window_secs: ParserOptions::default().multi_kill_window_secs,
+            dialect: None,
+            parse_threads: None,
+            concurrent_parses: 2,
+            ratings: RatingSettings::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Reads the file pointed by `Q3A_CONFIG` (or `q3aparser.toml` when present), then applies
+    /// the environment overrides.
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config = match env::var("Q3A_CONFIG") {
+            Ok(path) => ServerConfig::from_file(Path::new(&path))?,
+            Err(_) if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                ServerConfig::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            Err(_) => ServerConfig::default(),
+        };
+
+        config.apply_env()?;
+        config.ratings.validate().map_err(ConfigError::Ratings)?;
+
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let content = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        toml::from_str(&content).map_err(|source| ConfigError::Toml {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout_secs)
+    }
+
+    pub fn live_poll_interval(&self) -> Duration {
+        Duration::from_millis(self.live_poll_interval_ms)
+    }
+
+    pub fn parser_options(&self) -> ParserOptions {
+        ParserOptions {
+            multi_kill_window_secs: self.multi_kill_window_secs,
+            dialect: self.dialect,
+        }
+    }
+
+    pub fn parallel_parser(&self) -> ParallelParser {
+        let parser = ParallelParser::new(self.parser_options());
+
+        match self.parse_threads {
+            Some(threads) => parser.with_threads(threads),
+            None => parser,
+        }
+    }
+
+    pub fn client_settings(&self) -> ClientSettings {
+        ClientSettings {
+            public_base_url: self.public_base_url.clone(),
+            parser_options: self.parser_options(),
+        }
+    }
+
+    fn apply_env(&mut self) -> Result<(), ConfigError> {
+        if let Some(bind_address) = ServerConfig::env_value("Q3A_BIND_ADDRESS")? {
+            self.bind_address = Some(bind_address);
+        }
+        if let Ok(public_base_url) = env::var("Q3A_PUBLIC_BASE_URL") {
+            self.public_base_url = Some(public_base_url);
+        }
+        if let Some(max_upload_bytes) = ServerConfig::env_value("Q3A_MAX_UPLOAD_BYTES")? {
+            self.max_upload_bytes = max_upload_bytes;
+        }
+        if let Ok(temp_dir) = env::var("Q3A_TEMP_DIR") {
+            self.temp_dir = PathBuf::from(temp_dir);
+        }
+        if let Ok(data_dir) = env::var("Q3A_DATA_DIR") {
+            self.data_dir = PathBuf::from(data_dir);
+        }
+        if let Ok(alias_file) = env::var("Q3A_ALIAS_FILE") {
+            self.alias_file = Some(PathBuf::from(alias_file));
+        }
+        if let Ok(origins) = env::var("Q3A_CORS_ALLOWED_ORIGINS") {
+            self.cors_allowed_origins = origins
+                .split(',')
+                .map(|origin| origin.trim().to_string())
+                .filter(|origin| !origin.is_empty())
+                .collect();
+        }
+        if let Some(request_timeout_secs) = ServerConfig::env_value("Q3A_REQUEST_TIMEOUT_SECS")? {
+            self.request_timeout_secs = request_timeout_secs;
+        }
+        if let Ok(live_log) = env::var("Q3A_LIVE_LOG") {
+            self.live_log = Some(PathBuf::from(live_log));
+        }
+        if let Some(live_poll_interval_ms) = ServerConfig::env_value("Q3A_LIVE_POLL_INTERVAL_MS")? {
+            self.live_poll_interval_ms = live_poll_interval_ms;
+        }
+        if let Some(multi_kill_window_secs) = ServerConfig::env_value("Q3A_MULTI_KILL_WINDOW_SECS")? {
+            self.multi_kill_window_secs = multi_kill_window_secs;
+        }
+        if let Some(dialect) = ServerConfig::env_value("Q3A_DIALECT")? {
+            self.dialect = Some(dialect);
+        }
+        if let Some(parse_threads) = ServerConfig::env_value("Q3A_PARSE_THREADS")? {
+            self.parse_threads = Some(parse_threads);
+        }
+        if let Some(concurrent_parses) = ServerConfig::env_value("Q3A_CONCURRENT_PARSES")? {
+            self.concurrent_parses = concurrent_parses;
+        }
+        if let Some(algorithm) = ServerConfig::env_value("Q3A_RATINGS_ALGORITHM")? {
+            self.ratings.algorithm = algorithm;
+        }
+        if let Some(input) = ServerConfig::env_value("Q3A_RATINGS_INPUT")? {
+            self.ratings.input = input;
+        }
+
+        Ok(())
+    }
+
+    fn env_value<T: std::str::FromStr>(name: &str) -> Result<Option<T>, ConfigError> {
+        match env::var(name) {
+            Ok(value) => value.trim().parse::<T>().map(Some).map_err(|_| ConfigError::Env {
+                name: name.to_string(),
+                value,
+            }),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::rating::{RatingAlgorithm, RatingInput};
+
+    /// Every variable is set and checked in one test, since the environment is shared by the
+    /// tests running in parallel.
+    #[test]
+    fn the_environment_overrides_the_file() {
+        let path = env::temp_dir().join(format!("q3a-config-{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            "data_dir = \"/srv/q3a/data\"\nconcurrent_parses = 4\ndialect = \"urbanterror\"\nrequest_timeout_secs = 60\n\n[ratings]\nalgorithm = \"elo\"\n",
+        )
+        .unwrap();
+        env::set_var("Q3A_CONFIG", &path);
+        env::set_var("Q3A_BIND_ADDRESS", "127.0.0.1:4000");
+        env::set_var("Q3A_CONCURRENT_PARSES", " 8 ");
+        env::set_var("Q3A_CORS_ALLOWED_ORIGINS", "https://a.example, ,https://b.example");
+        env::set_var("Q3A_DIALECT", "wolfet");
+        env::set_var("Q3A_RATINGS_INPUT", "duels");
+
+        let config = ServerConfig::load();
+
+        env::set_var("Q3A_PARSE_THREADS", "many");
+        let invalid = ServerConfig::load();
+        env::remove_var("Q3A_PARSE_THREADS");
+
+        fs::write(&path, "[ratings.glicko2]\ntau = 0.0\n").unwrap();
+        let invalid_ratings = ServerConfig::load();
+
+        for name in [
+            "Q3A_CONFIG",
+            "Q3A_BIND_ADDRESS",
+            "Q3A_CONCURRENT_PARSES",
+            "Q3A_CORS_ALLOWED_ORIGINS",
+            "Q3A_DIALECT",
+            "Q3A_RATINGS_INPUT",
+        ] {
+            env::remove_var(name);
+        }
+        fs::remove_file(&path).unwrap();
+
+        let config = config.unwrap();
+        assert_eq!(config.data_dir, PathBuf::from("/srv/q3a/data"));
+        assert_eq!(config.request_timeout(), Duration::from_secs(60));
+        assert_eq!(config.bind_address, Some("127.0.0.1:4000".parse().unwrap()));
+        assert_eq!(config.concurrent_parses, 8);
+        assert_eq!(config.cors_allowed_origins, ["https://a.example", "https://b.example"]);
+        assert_eq!(config.dialect, Some(Dialect::WolfEt));
+        assert_eq!(config.ratings.algorithm, RatingAlgorithm::Elo);
+        assert_eq!(config.ratings.input, RatingInput::Duels);
+        assert_eq!(config.temp_dir, ServerConfig::default().temp_dir);
+
+        assert!(matches!(
+            invalid,
+            Err(ConfigError::Env { name, value }) if name == "Q3A_PARSE_THREADS" && value == "many"
+        ));
+        assert!(matches!(invalid_ratings, Err(ConfigError::Ratings(_))));
+    }
+}
+}}
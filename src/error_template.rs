@@ -1,25 +1,172 @@
 use cfg_if::cfg_if;
 use http::status::StatusCode;
 use leptos::*;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[cfg(feature = "ssr")]
 use leptos_axum::ResponseOptions;
 
+use crate::model::parser::ParseError;
+
 #[derive(Clone, Debug, Error)]
 pub enum AppError {
     #[error("Not Found")]
     NotFound,
+    #[error("Invalid upload")]
+    InvalidUpload(String),
+    #[error("The upload must have a \"log\" file field")]
+    MissingLogField,
+    #[error("The log file is larger than the server accepts")]
+    PayloadTooLarge,
+    #[error("No match found in the log, is it a Quake 3 Arena games.log?")]
+    NoMatches,
+    #[error("Malformed line {line}")]
+    MalformedLine { line: u64, content: String },
+    #[error("Cannot store the log")]
+    Storage(String),
+    #[error("Cannot reach the server")]
+    Network(String),
+    #[error("Invalid export request")]
+    InvalidExport(String),
+    #[error("Cannot generate the export")]
+    Export(String),
+    #[error("Invalid ratings request")]
+    InvalidRatings(String),
+}
+
+/// Body of every error response sent by the API.
+///
+/// `code` is a stable identifier meant for programs, `message` is meant for people. `line` is
+/// the 1-based log line that caused the error, when there is one.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
 }
 
 impl AppError {
+    /// Storage failure, logged with its details while the client only gets a generic message, so
+    /// paths and OS errors are not leaked.
+    pub fn storage(err: impl std::fmt::Display) -> Self {
+        log::error!("Storage error: {err}");
+
+        AppError::Storage("The server could not read or write the log files".to_string())
+    }
+
     pub fn status_code(&self) -> StatusCode {
         match self {
             AppError::NotFound => StatusCode::NOT_FOUND,
+            AppError::InvalidUpload(_)
+            | AppError::MissingLogField
+            | AppError::InvalidExport(_)
+            | AppError::InvalidRatings(_) => StatusCode::BAD_REQUEST,
+            AppError::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::NoMatches | AppError::MalformedLine { .. } => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            AppError::Storage(_) | AppError::Export(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Network(_) => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound => "not_found",
+            AppError::InvalidUpload(_) => "invalid_upload",
+            AppError::MissingLogField => "missing_log_field",
+            AppError::PayloadTooLarge => "payload_too_large",
+            AppError::NoMatches => "no_matches",
+            AppError::MalformedLine { .. } => "malformed_line",
+            AppError::Storage(_) => "storage_error",
+            AppError::Network(_) => "network_error",
+            AppError::InvalidExport(_) => "invalid_export",
+            AppError::Export(_) => "export_error",
+            AppError::InvalidRatings(_) => "invalid_ratings",
+        }
+    }
+
+    pub fn line(&self) -> Option<u64> {
+        match self {
+            AppError::MalformedLine { line, .. } => Some(*line),
+            _ => None,
+        }
+    }
+
+    pub fn details(&self) -> Option<String> {
+        match self {
+            AppError::InvalidUpload(details)
+            | AppError::Storage(details)
+            | AppError::Network(details)
+            | AppError::InvalidExport(details)
+            | AppError::Export(details)
+            | AppError::InvalidRatings(details)
+            | AppError::MalformedLine {
+                content: details, ..
+            } => Some(details.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn to_body(&self) -> ErrorBody {
+        ErrorBody {
+            code: self.code().to_string(),
+            message: self.to_string(),
+            line: self.line(),
+            details: self.details(),
+        }
+    }
+}
+
+impl From<ErrorBody> for AppError {
+    fn from(body: ErrorBody) -> Self {
+        let details = body.details.unwrap_or_default();
+
+        match body.code.as_str() {
+            "not_found" => AppError::NotFound,
+            "invalid_upload" => AppError::InvalidUpload(details),
+            "missing_log_field" => AppError::MissingLogField,
+            "payload_too_large" => AppError::PayloadTooLarge,
+            "no_matches" => AppError::NoMatches,
+            "malformed_line" => AppError::MalformedLine {
+                line: body.line.unwrap_or_default(),
+                content: details,
+            },
+            "storage_error" => AppError::Storage(details),
+            "invalid_export" => AppError::InvalidExport(details),
+            "export_error" => AppError::Export(details),
+            "invalid_ratings" => AppError::InvalidRatings(details),
+            _ => AppError::Network(body.message),
         }
     }
 }
 
+impl From<ParseError> for AppError {
+    fn from(err: ParseError) -> Self {
+        match err {
+            ParseError::Io(err) => AppError::storage(err),
+            ParseError::NoMatches => AppError::NoMatches,
+            ParseError::MalformedLine { line, content } => {
+                AppError::MalformedLine { line, content }
+            }
+        }
+    }
+}
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+    use axum::response::{IntoResponse, Response};
+
+    impl IntoResponse for AppError {
+        fn into_response(self) -> Response {
+            (self.status_code(), axum::Json(self.to_body())).into_response()
+        }
+    }
+}}
+
 // A basic function to display errors served by the error boundaries.
 // Feel free to do more complicated things here than just displaying the error.
 #[component]
@@ -65,10 +212,12 @@ pub fn ErrorTemplate(
             view= move |cx, error| {
                 let error_string = error.1.to_string();
                 let error_code= error.1.status_code();
+                let error_details = error.1.details();
                 view! {
                     cx,
                     <h2>{error_code.to_string()}</h2>
                     <p>"Error: " {error_string}</p>
+                    {error_details.map(|details| view! { cx, <pre class="text-sm">{details}</pre> })}
                 }
             }
         />
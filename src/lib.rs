@@ -1,12 +1,18 @@
 use cfg_if::cfg_if;
 pub mod app;
+pub mod components;
+pub mod config;
 pub mod error_template;
 pub mod fileserv;
+pub mod links;
+pub mod live;
 
 pub mod model;
 
 pub mod api;
 pub mod pages;
+pub mod report;
+pub mod store;
 
 cfg_if! { if #[cfg(feature = "hydrate")] {
     use leptos::*;
@@ -0,0 +1,60 @@
+use leptos::*;
+
+use crate::model::identity::IdentityResolver;
+use crate::model::player::player_path;
+
+/// How pages link to each other, provided as context. Pages rendered without it link through
+/// the server routes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum LinkStyle {
+    #[default]
+    Server,
+    /// Files of a static report, `root` being the relative path from the page to the report root.
+    StaticFiles { root: String },
+}
+
+/// Page of a stored match.
+pub fn match_href(cx: Scope, id: &str) -> String {
+    match use_context::<LinkStyle>(cx).unwrap_or_default() {
+        LinkStyle::Server => format!("/matches/{id}"),
+        LinkStyle::StaticFiles { root } => format!("{root}matches/{id}.html"),
+    }
+}
+
+/// Profile page of a player, the identity they were merged into when an `IdentityResolver` is
+/// provided as context.
+pub fn player_href(cx: Scope, name: &str) -> String {
+    let name = match use_context::<IdentityResolver>(cx) {
+        Some(identities) => identities.resolve(name),
+        None => name.to_string(),
+    };
+
+    match use_context::<LinkStyle>(cx).unwrap_or_default() {
+        LinkStyle::Server => player_path(&name),
+        LinkStyle::StaticFiles { root } => {
+            format!("{root}players/{}.html", static_file_stem(&name))
+        }
+    }
+}
+
+/// File name safe version of a player name, made unique by a hash of the full name since
+/// different names can share the same letters.
+pub fn static_file_stem(name: &str) -> String {
+    let letters = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+
+    // FNV-1a, stable across builds unlike the standard library hasher
+    let hash = name.bytes().fold(0x811c9dc5_u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+
+    format!("{}-{hash:08x}", letters.trim_matches('-'))
+}
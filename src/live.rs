@@ -0,0 +1,371 @@
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+
+use crate::model::parser::{MatchRecord, RankingPosition};
+
+/// Kill shown in the live kill feed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FeedEntry {
+    pub killer: String,
+    pub killed: String,
+    pub means: String,
+}
+
+/// Messages pushed to the clients watching the live page.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    Snapshot {
+        match_id: Option<String>,
+        ranking: Vec<RankingPosition>,
+        total_kills: i16,
+        feed: Vec<FeedEntry>,
+    },
+    MatchStarted {
+        match_id: String,
+    },
+    Kill {
+        match_id: String,
+        entry: FeedEntry,
+        total_kills: i16,
+    },
+    RankingChanged {
+        match_id: String,
+        ranking: Vec<RankingPosition>,
+    },
+    MatchEnded {
+        match_id: String,
+        record: Box<MatchRecord>,
+    },
+}
+
+/// Amount of kills kept in the feed sent to newly connected clients.
+pub const FEED_SIZE: usize = 20;
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+
+use crate::model::parser::{ParserEvent, ParserOptions, ParserState};
+
+#[derive(Default)]
+struct LiveSnapshot {
+    match_id: Option<String>,
+    ranking: Vec<RankingPosition>,
+    total_kills: i16,
+    feed: VecDeque<FeedEntry>,
+}
+
+/// Fan-out point between the log follower and the connected clients.
+#[derive(Clone)]
+pub struct LiveHub {
+    sender: broadcast::Sender<LiveEvent>,
+    snapshot: Arc<RwLock<LiveSnapshot>>,
+}
+
+impl LiveHub {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(256);
+
+        LiveHub {
+            sender,
+            snapshot: Arc::new(RwLock::new(LiveSnapshot::default())),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveEvent> {
+        self.sender.subscribe()
+    }
+
+    /// State of the match being followed, sent first to every new subscriber.
+    pub fn snapshot(&self) -> LiveEvent {
+        let snapshot = self.snapshot.read().unwrap();
+
+        LiveEvent::Snapshot {
+            match_id: snapshot.match_id.clone(),
+            ranking: snapshot.ranking.clone(),
+            total_kills: snapshot.total_kills,
+            feed: snapshot.feed.iter().cloned().collect(),
+        }
+    }
+
+    fn publish(&self, event: LiveEvent) {
+        {
+            let mut snapshot = self.snapshot.write().unwrap();
+
+            match &event {
+                LiveEvent::MatchStarted { match_id } => {
+                    *snapshot = LiveSnapshot {
+                        match_id: Some(match_id.clone()),
+                        ..LiveSnapshot::default()
+                    };
+                }
+                LiveEvent::Kill { entry, total_kills, .. } => {
+                    snapshot.total_kills = *total_kills;
+                    snapshot.feed.push_front(entry.clone());
+                    snapshot.feed.truncate(FEED_SIZE);
+                }
+                LiveEvent::RankingChanged { ranking, .. } => {
+                    snapshot.ranking = ranking.clone();
+                }
+                LiveEvent::MatchEnded { record, .. } => {
+                    snapshot.ranking = record.ranking.clone();
+                    snapshot.total_kills = record.total_kills;
+                }
+                LiveEvent::Snapshot { .. } => {}
+            }
+        }
+
+        // Nobody listening is not an error, the snapshot keeps the state for later clients
+        let _ = self.sender.send(event);
+    }
+}
+
+impl Default for LiveHub {
+    fn default() -> Self {
+        LiveHub::new()
+    }
+}
+
+/// Follows a growing `games.log`, surviving truncation and rotation, and publishes parsed events.
+pub struct LogFollower {
+    path: PathBuf,
+    poll_interval: Duration,
+    file_id: Option<u64>,
+    /// Whether the log was opened yet, the matches it already holds are not replayed as live.
+    started: bool,
+    state: ParserState,
+}
+
+impl LogFollower {
+    pub fn new(path: PathBuf, poll_interval: Duration, options: ParserOptions) -> Self {
+        LogFollower {
+            path,
+            poll_interval,
+            file_id: None,
+            started: false,
+            state: ParserState::with_options(options).with_kill_events(),
+        }
+    }
+
+    /// Runs the follower on its own thread, since all of its work is blocking file I/O.
+    pub fn spawn(mut self, hub: LiveHub) -> thread::JoinHandle<()> {
+        thread::spawn(move || loop {
+            if let Err(err) = self.poll(&hub) {
+                log::warn!("Cannot follow {}: {}", self.path.display(), err);
+            }
+
+            thread::sleep(self.poll_interval);
+        })
+    }
+
+    fn poll(&mut self, hub: &LiveHub) -> io::Result<()> {
+        let metadata = fs::metadata(&self.path)?;
+        let file_id = LogFollower::file_id(&metadata);
+
+        if !self.started {
+            self.started = true;
+            self.file_id = file_id;
+            let start = LogFollower::start_offset(File::open(&self.path)?)?;
+            self.state.skip_to(start);
+        } else if file_id != self.file_id || metadata.len() < self.state.offset() {
+            // A new file under the same path means the log was rotated, a shorter one that it was truncated
+            self.file_id = file_id;
+            self.state.rewind();
+        }
+
+        if metadata.len() == self.state.offset() {
+            return Ok(());
+        }
+
+        let mut file = BufReader::new(File::open(&self.path)?);
+        // The offset counts the incomplete line the parser state keeps, so only the bytes written since are read
+        file.seek(SeekFrom::Start(self.state.offset()))?;
+
+        loop {
+            let chunk = file.fill_buf()?;
+            if chunk.is_empty() {
+                break;
+            }
+
+            self.state.push_chunk_with(chunk, |state, event| {
+                LogFollower::handle_event(state, event, hub)
+            });
+
+            let chunk_size = chunk.len();
+            file.consume(chunk_size);
+        }
+
+        Ok(())
+    }
+
+    /// Offset of the `InitGame` line of the match still being played when the follower starts, or
+    /// of the end of the log when none is.
+    fn start_offset(file: File) -> io::Result<u64> {
+        let mut reader = BufReader::new(file);
+        let mut line = Vec::new();
+        let mut offset = 0;
+        let mut match_start = None;
+
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)?;
+            // An incomplete last line is left for the parser, which reads it once it is complete
+            if read == 0 || !line.ends_with(b"\n") {
+                break;
+            }
+
+            let text = String::from_utf8_lossy(&line);
+            let (_, event) = ParserState::split_time(text.trim());
+            if event.starts_with("InitGame:") {
+                match_start = Some(offset);
+            } else if event.starts_with("ShutdownGame:") {
+                match_start = None;
+            }
+
+            offset += read as u64;
+        }
+
+        Ok(match_start.unwrap_or(offset))
+    }
+
+    #[cfg(unix)]
+    fn file_id(metadata: &fs::Metadata) -> Option<u64> {
+        use std::os::unix::fs::MetadataExt;
+
+        Some(metadata.ino())
+    }
+
+    #[cfg(not(unix))]
+    fn file_id(_metadata: &fs::Metadata) -> Option<u64> {
+        None
+    }
+
+    fn handle_event(state: &ParserState, event: ParserEvent, hub: &LiveHub) {
+        match event {
+            ParserEvent::MatchStarted { match_id } => {
+                hub.publish(LiveEvent::MatchStarted { match_id });
+            }
+            ParserEvent::Kill {
+                match_id,
+                killer,
+                killed,
+                means,
+            } => {
+                let match_record = match state.current_match() {
+                    Some((_, match_record)) => match_record,
+                    None => return,
+                };
+                let previous_ranking = hub.snapshot.read().unwrap().ranking.clone();
+                let ranking = match_record.standings();
+
+                hub.publish(LiveEvent::Kill {
+                    match_id: match_id.clone(),
+                    entry: FeedEntry {
+                        killer,
+                        killed,
+                        means,
+                    },
+                    total_kills: match_record.total_kills,
+                });
+
+                let ranking_changed = previous_ranking.len() != ranking.len()
+                    || previous_ranking
+                        .iter()
+                        .zip(ranking.iter())
+                        .any(|(a, b)| a.player != b.player || a.kills != b.kills);
+
+                if ranking_changed {
+                    hub.publish(LiveEvent::RankingChanged { match_id, ranking });
+                }
+            }
+            ParserEvent::MatchEnded { match_id, record } => {
+                hub.publish(LiveEvent::MatchEnded { match_id, record });
+            }
+            ParserEvent::MalformedLine { line, content } => {
+                log::warn!("Skipping malformed line {line}: {content}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn poll_reads_a_split_line_once() {
+        let path = std::env::temp_dir().join(format!("q3a-live-{}.log", std::process::id()));
+        fs::write(&path, "  0:00 InitGame: \\sv_hostname\\Test\\g_gametype\\0\\mapname\\q3dm17\n  0:10 Kill: 1022 2 22: <world> kil").unwrap();
+        let hub = LiveHub::new();
+        let mut follower = LogFollower::new(path.clone(), Duration::ZERO, ParserOptions::default());
+
+        follower.poll(&hub).unwrap();
+        // Nothing written since, the incomplete line must not be read again
+        follower.poll(&hub).unwrap();
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"led Isgalamido by MOD_TRIGGER_HURT\n")
+            .unwrap();
+        follower.poll(&hub).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        match hub.snapshot() {
+            LiveEvent::Snapshot { total_kills, feed, .. } => {
+                assert_eq!(total_kills, 1);
+                assert_eq!(feed.len(), 1);
+                assert_eq!(feed[0].killer, "<world>");
+                assert_eq!(feed[0].killed, "Isgalamido");
+            }
+            _ => panic!("hub snapshot is not a snapshot event"),
+        }
+    }
+
+    #[test]
+    fn matches_logged_before_starting_are_not_replayed() {
+        let path = std::env::temp_dir().join(format!("q3a-live-start-{}.log", std::process::id()));
+        fs::write(&path, "  0:00 InitGame: \\sv_hostname\\Test\\g_gametype\\0\\mapname\\q3dm17\n  0:10 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT\n  0:20 ShutdownGame:\n").unwrap();
+        let hub = LiveHub::new();
+        let mut follower = LogFollower::new(path.clone(), Duration::ZERO, ParserOptions::default());
+
+        follower.poll(&hub).unwrap();
+        match hub.snapshot() {
+            LiveEvent::Snapshot { match_id, total_kills, .. } => {
+                assert_eq!(match_id, None);
+                assert_eq!(total_kills, 0);
+            }
+            _ => panic!("hub snapshot is not a snapshot event"),
+        }
+
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"  0:00 InitGame: \\sv_hostname\\Test\\g_gametype\\0\\mapname\\q3dm6\n  0:05 Kill: 1022 3 22: <world> killed Zeh by MOD_FALLING\n")
+            .unwrap();
+        follower.poll(&hub).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        match hub.snapshot() {
+            LiveEvent::Snapshot { match_id, feed, .. } => {
+                assert!(match_id.is_some());
+                assert_eq!(feed.len(), 1);
+                assert_eq!(feed[0].killed, "Zeh");
+            }
+            _ => panic!("hub snapshot is not a snapshot event"),
+        }
+    }
+}
+}}
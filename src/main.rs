@@ -1,16 +1,83 @@
 #[cfg(feature = "ssr")]
 #[tokio::main]
 async fn main() {
-    use axum::{routing::post, Router};
+    use axum::{
+        body::Body,
+        extract::{DefaultBodyLimit, Path, RawQuery},
+        http::{HeaderMap, HeaderValue, Method, Request},
+        response::IntoResponse,
+        routing::{get, post},
+        Extension, Router,
+    };
     use leptos::*;
-    use leptos_axum::{generate_route_list, LeptosRoutes};
+    use leptos_axum::{generate_route_list, handle_server_fns_with_context, LeptosRoutes};
+    use std::sync::Arc;
+    use tower_http::{cors::CorsLayer, timeout::TimeoutLayer};
 
+    use q3aparser::api::export::*;
+    use q3aparser::api::live::*;
     use q3aparser::api::parse::*;
+    use q3aparser::api::ratings::*;
+    use q3aparser::api::validate::*;
     use q3aparser::app::*;
+    use q3aparser::config::ServerConfig;
     use q3aparser::fileserv::file_and_error_handler;
+    use q3aparser::live::{LiveHub, LogFollower};
+    use q3aparser::model::identity::AliasMap;
+    use q3aparser::store::MatchStore;
+
+    // Server functions need the same context as the pages rendering them
+    async fn server_fn_handler(
+        Extension(store): Extension<MatchStore>,
+        Extension(config): Extension<Arc<ServerConfig>>,
+        path: Path<String>,
+        headers: HeaderMap,
+        raw_query: RawQuery,
+        request: Request<Body>,
+    ) -> impl IntoResponse {
+        handle_server_fns_with_context(
+            path,
+            headers,
+            raw_query,
+            move |cx| {
+                provide_context(cx, store.clone());
+                provide_context(cx, config.ratings.clone());
+            },
+            request,
+        )
+        .await
+    }
+
+    // Settings errors name the file or variable at fault, which a panic would bury
+    fn exit_with(message: String) -> ! {
+        eprintln!("{message}");
+        std::process::exit(1)
+    }
 
     simple_logger::init_with_level(log::Level::Debug).expect("couldn't initialize logging");
 
+    let config = ServerConfig::load().unwrap_or_else(|err| exit_with(err.to_string()));
+    std::fs::create_dir_all(&config.temp_dir).unwrap_or_else(|err| {
+        exit_with(format!(
+            "Cannot create the temporary directory {}: {err}",
+            config.temp_dir.display()
+        ))
+    });
+    let aliases = match &config.alias_file {
+        Some(alias_file) => {
+            AliasMap::from_file(alias_file).unwrap_or_else(|err| exit_with(err.to_string()))
+        }
+        None => AliasMap::default(),
+    };
+    let store = MatchStore::open(config.data_dir.clone())
+        .unwrap_or_else(|err| {
+            exit_with(format!(
+                "Cannot open the data directory {}: {err}",
+                config.data_dir.display()
+            ))
+        })
+        .with_aliases(aliases);
+
     // Setting get_configuration(None) means we'll be using cargo-leptos's env values
     // For deployment these variables are:
     // <https://github.com/leptos-rs/start-axum#executing-a-server-on-a-remote-machine-without-the-toolchain>
@@ -18,17 +85,72 @@ async fn main() {
     // The file would need to be included with the executable when moved to deployment
     let conf = get_configuration(None).await.unwrap();
     let leptos_options = conf.leptos_options;
-    let addr = leptos_options.site_addr;
+    let addr = config.bind_address.unwrap_or(leptos_options.site_addr);
     let routes = generate_route_list(|cx| view! { cx, <App/> }).await;
 
+    // The live page follows the configured server log, when there is one
+    let live_hub = LiveHub::new();
+    if let Some(live_log) = &config.live_log {
+        log!("following {}", live_log.display());
+        LogFollower::new(
+            live_log.clone(),
+            config.live_poll_interval(),
+            config.parser_options(),
+        )
+        .spawn(live_hub.clone());
+    }
+
+    let client_settings = config.client_settings();
+    let page_store = store.clone();
+    let rating_settings = config.ratings.clone();
+
     // build our application with a route
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/api/parse_log_file", post(parse_log_file))
-        .route("/api/*fn_name", post(leptos_axum::handle_server_fns))
-        .leptos_routes(&leptos_options, routes, |cx| view! { cx, <App/> })
+        .route("/api/validate_log_file", post(validate_log_file))
+        .route("/api/live", get(live_events))
+        .route("/api/matches/:id/export", get(export_match))
+        .route("/api/uploads/:upload_id/export", get(export_upload))
+        .route("/api/ratings", get(ratings))
+        .route("/api/ratings/:player", get(player_ratings))
+        .route("/api/*fn_name", post(server_fn_handler))
+        .leptos_routes_with_context(
+            &leptos_options,
+            routes,
+            move |cx| {
+                provide_context(cx, client_settings.clone());
+                provide_context(cx, page_store.clone());
+                provide_context(cx, rating_settings.clone());
+            },
+            |cx| view! { cx, <App/> },
+        )
         .fallback(file_and_error_handler)
+        .layer(Extension(live_hub))
+        .layer(Extension(store))
+        .layer(Extension(ParseSlots::new(config.concurrent_parses)))
+        .layer(Extension(Arc::new(config.clone())))
+        .layer(DefaultBodyLimit::max(config.max_upload_bytes))
+        .layer(TimeoutLayer::new(config.request_timeout()))
         .with_state(leptos_options);
 
+    if !config.cors_allowed_origins.is_empty() {
+        let origins = config
+            .cors_allowed_origins
+            .iter()
+            .map(|origin| {
+                origin
+                    .parse::<HeaderValue>()
+                    .unwrap_or_else(|_| exit_with(format!("Invalid CORS origin {origin:?}")))
+            })
+            .collect::<Vec<HeaderValue>>();
+
+        app = app.layer(
+            CorsLayer::new()
+                .allow_origin(origins)
+                .allow_methods([Method::GET, Method::POST]),
+        );
+    }
+
     // run our app with hyper
     // `axum::Server` is a re-export of `hyper::Server`
     log!("listening on http://{}", &addr);
@@ -0,0 +1,472 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::dialect::{LogDialect, Quake3};
+
+/// Replaces the messages of redacted chat lines.
+pub const REDACTED: &str = "[redacted]";
+
+/// Userinfo keys whose values identify a client.
+const GUID_KEYS: [&str; 3] = ["cl_guid", "guid", "id"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct AnonymiseOptions {
+    /// Replaces what players said with `REDACTED`, keeping who said it.
+    pub redact_chat: bool,
+}
+
+/// Rewrites logs with a pseudonym for every player name, GUID and IP, leaving every other byte of
+/// the log as it was.
+///
+/// Pseudonyms are given in the order names first show up and are kept for the lifetime of the
+/// anonymiser, so logs anonymised one after the other share them. An anonymised log parses to the
+/// same statistics as the original, under the pseudonyms.
+#[derive(Clone, Debug, Default)]
+pub struct Anonymiser {
+    options: AnonymiseOptions,
+    names: HashMap<String, String>,
+    guids: HashMap<String, String>,
+    ips: HashMap<String, String>,
+}
+
+impl Anonymiser {
+    pub fn new(options: AnonymiseOptions) -> Self {
+        Anonymiser {
+            options,
+            ..Anonymiser::default()
+        }
+    }
+
+    /// Pseudonym given to each player name seen so far.
+    pub fn pseudonyms(&self) -> &HashMap<String, String> {
+        &self.names
+    }
+
+    /// Copies a log from `reader` to `writer`, line by line.
+    pub fn anonymise<R: BufRead, W: Write>(
+        &mut self,
+        mut reader: R,
+        mut writer: W,
+    ) -> io::Result<()> {
+        let mut raw_line = Vec::new();
+
+        while reader.read_until(b'\n', &mut raw_line)? > 0 {
+            let content_end = raw_line
+                .iter()
+                .rposition(|b| *b != b'\n' && *b != b'\r')
+                .map_or(0, |last| last + 1);
+            let (content, line_break) = raw_line.split_at(content_end);
+
+            let line = String::from_utf8_lossy(content);
+            let anonymised = self.anonymise_line(&line);
+            // Lines left alone keep any byte that isn't valid UTF-8
+            if anonymised == line {
+                writer.write_all(content)?;
+            } else {
+                writer.write_all(anonymised.as_bytes())?;
+            }
+            writer.write_all(line_break)?;
+
+            raw_line.clear();
+        }
+
+        writer.flush()
+    }
+
+    /// Anonymised copy of a whole log held in memory.
+    pub fn anonymise_bytes(&mut self, log: &[u8]) -> Vec<u8> {
+        let mut anonymised = Vec::with_capacity(log.len());
+        // Writing to a Vec can't fail
+        let _ = self.anonymise(log, &mut anonymised);
+        anonymised
+    }
+
+    /// Anonymised copy of a single line, without its line break.
+    pub fn anonymise_line(&mut self, line: &str) -> String {
+        let (time, event) = Anonymiser::split_time(line);
+
+        let event = if let Some(userinfo) = event.strip_prefix("ClientUserinfoChanged: ") {
+            format!(
+                "ClientUserinfoChanged: {}",
+                self.anonymise_userinfo(userinfo, "n")
+            )
+        } else if let Some(userinfo) = event.strip_prefix("ClientUserinfo: ") {
+            format!(
+                "ClientUserinfo: {}",
+                self.anonymise_userinfo(userinfo, "name")
+            )
+        } else if event.starts_with("Kill: ") {
+            self.anonymise_kill(event)
+        } else if event.starts_with("say: ") || event.starts_with("sayteam: ") {
+            self.anonymise_chat(event)
+        } else if event.starts_with("tell: ") {
+            self.anonymise_tell(event)
+        } else if let Some(score) = event.strip_prefix("score: ") {
+            self.anonymise_score(score)
+        } else if event.starts_with("InitGame:") {
+            // Server settings only
+            event.to_string()
+        } else {
+            self.anonymise_event(event)
+        };
+
+        format!("{time}{event}")
+    }
+
+    /// Splits the indentation and `m:ss` game time in front of a line from its event.
+    fn split_time(line: &str) -> (&str, &str) {
+        let indent = line.len() - line.trim_start().len();
+        let time_end = line[indent..]
+            .find(' ')
+            .filter(|end| {
+                let time = &line[indent..indent + end];
+                time.contains(':') && time.chars().all(|c| c.is_ascii_digit() || c == ':')
+            })
+            .map_or(indent, |end| indent + end);
+        let event_start = line.len() - line[time_end..].trim_start().len();
+
+        line.split_at(event_start)
+    }
+
+    fn pseudonym(&mut self, name: &str) -> String {
+        if name.is_empty() || name == "<world>" {
+            return name.to_string();
+        }
+
+        let next = self.names.len() + 1;
+        self.names
+            .entry(name.to_string())
+            .or_insert_with(|| format!("Player{next}"))
+            .clone()
+    }
+
+    fn guid_pseudonym(&mut self, guid: &str) -> String {
+        let next = self.guids.len() + 1;
+        self.guids
+            .entry(guid.to_string())
+            .or_insert_with(|| format!("{next:032X}"))
+            .clone()
+    }
+
+    /// IPs become addresses of the private 10.0.0.0/8 range, keeping the port.
+    fn ip_pseudonym(&mut self, address: &str) -> String {
+        let (ip, port) = match address.rsplit_once(':') {
+            Some((ip, port)) => (ip, Some(port)),
+            None => (address, None),
+        };
+        if ip.is_empty() || ip == "bot" || ip == "localhost" {
+            return address.to_string();
+        }
+
+        let next = self.ips.len() as u32 + 1;
+        let ip = self
+            .ips
+            .entry(ip.to_string())
+            .or_insert_with(|| {
+                format!(
+                    "10.{}.{}.{}",
+                    (next >> 16) & 255,
+                    (next >> 8) & 255,
+                    next & 255
+                )
+            })
+            .clone();
+
+        match port {
+            Some(port) => format!("{ip}:{port}"),
+            None => ip,
+        }
+    }
+
+    // 2 n\Isgalamido\t\0\model\xian/default...
+    // 2 \ip\10.0.0.7:27960\name\Isgalamido\cl_guid\4F2A...
+    fn anonymise_userinfo(&mut self, userinfo: &str, name_key: &str) -> String {
+        let mut fields = userinfo
+            .split('\\')
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        for index in 1..fields.len() {
+            let key = fields[index - 1].rsplit(' ').next().unwrap_or_default();
+            // The parser takes the name right after `n\`, whatever follows it
+            let value = if key == name_key && (name_key != "n" || index == 1) {
+                self.pseudonym(&fields[index])
+            } else if GUID_KEYS.contains(&key) && !fields[index].is_empty() {
+                self.guid_pseudonym(&fields[index])
+            } else if key == "ip" {
+                self.ip_pseudonym(&fields[index])
+            } else {
+                continue;
+            };
+            fields[index] = value;
+        }
+
+        fields.join("\\")
+    }
+
+    // Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT
+    fn anonymise_kill(&mut self, event: &str) -> String {
+        let header_end = event
+            .match_indices(": ")
+            .nth(1)
+            .map(|(index, separator)| index + separator.len());
+
+        // Read the way the parser does, so the pseudonyms replace exactly what it takes as names
+        match (header_end, Quake3.parse_kill(event)) {
+            (Some(header_end), Some((killer, killed, means))) => format!(
+                "{}{} killed {} by {means}",
+                &event[..header_end],
+                self.pseudonym(killer),
+                self.pseudonym(killed)
+            ),
+            _ => event.to_string(),
+        }
+    }
+
+    // say: Isgalamido: gg ez
+    fn anonymise_chat(&mut self, event: &str) -> String {
+        let (command, said) = event.split_once(": ").unwrap_or_default();
+        let (player, message) = match said.split_once(": ") {
+            Some(split) => split,
+            None => return event.to_string(),
+        };
+
+        let message = if self.options.redact_chat {
+            REDACTED.to_string()
+        } else {
+            self.replace_names(message)
+        };
+
+        format!("{command}: {}: {message}", self.pseudonym(player))
+    }
+
+    // tell: Isgalamido to Mocinha: gg
+    fn anonymise_tell(&mut self, event: &str) -> String {
+        let said = event.trim_start_matches("tell: ");
+        let (players, message) = match said.split_once(": ") {
+            Some(split) => split,
+            None => return event.to_string(),
+        };
+        let message = if self.options.redact_chat {
+            REDACTED.to_string()
+        } else {
+            self.replace_names(message)
+        };
+
+        format!("tell: {}: {message}", self.replace_names(players))
+    }
+
+    // score: 20  ping: 4  client: 0 Isgalamido
+    fn anonymise_score(&mut self, score: &str) -> String {
+        let name_start = score.find("client: ").and_then(|client| {
+            let after_client = client + "client: ".len();
+            score[after_client..]
+                .find(' ')
+                .map(|space| after_client + space + 1)
+        });
+
+        match name_start {
+            Some(name_start) => format!(
+                "score: {}{}",
+                &score[..name_start],
+                self.pseudonym(&score[name_start..])
+            ),
+            None => format!("score: {score}"),
+        }
+    }
+
+    /// Lines of the game dialects naming players in their text, e.g. `Hit: 1 0 2 21: A hit B in
+    /// the Head`, only get the names already seen replaced, leaving numbers and keywords alone.
+    fn anonymise_event(&mut self, event: &str) -> String {
+        match event.split_once(": ") {
+            Some((command, text)) if !command.contains(' ') => {
+                let text_start = text
+                    .match_indices(": ")
+                    .next()
+                    .filter(|(index, _)| {
+                        text[..*index]
+                            .chars()
+                            .all(|c| c.is_ascii_digit() || c == ' ')
+                    })
+                    .map_or(0, |(index, separator)| index + separator.len());
+                let (numbers, text) = text.split_at(text_start);
+
+                format!("{command}: {numbers}{}", self.replace_names(text))
+            }
+            _ => event.to_string(),
+        }
+    }
+
+    /// Replaces every known name found in `text` on word boundaries, longest names first.
+    fn replace_names(&self, text: &str) -> String {
+        let mut names = self.names.iter().collect::<Vec<(&String, &String)>>();
+        names.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(b.0)));
+
+        let is_boundary = |c: Option<char>| c.is_none_or(|c| !c.is_alphanumeric());
+        let mut replaced = String::with_capacity(text.len());
+        let mut rest = text;
+        let mut previous = None;
+
+        'scan: while let Some(c) = rest.chars().next() {
+            if is_boundary(previous) {
+                for (name, pseudonym) in names.iter() {
+                    if rest.starts_with(name.as_str())
+                        && is_boundary(rest[name.len()..].chars().next())
+                    {
+                        replaced.push_str(pseudonym);
+                        previous = name.chars().last();
+                        rest = &rest[name.len()..];
+                        continue 'scan;
+                    }
+                }
+            }
+
+            replaced.push(c);
+            previous = Some(c);
+            rest = &rest[c.len_utf8()..];
+        }
+
+        replaced
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::model::generator::{GeneratorOptions, LogGenerator};
+    use crate::model::parser::{MatchRecord, Parser};
+
+    /// Generated log whose players include names the kill and userinfo lines make hard to read.
+    fn generated_log() -> Vec<u8> {
+        let log = LogGenerator::new(GeneratorOptions {
+            seed: 5,
+            matches: 15,
+            ..GeneratorOptions::default()
+        })
+        .generate_bytes();
+
+        String::from_utf8(log)
+            .unwrap()
+            .replace("Mocinha", "Mo killed Cinha")
+            .replace("Hunter", "Hunt\\er")
+            .replace("Slash", "Sl\\ash\\")
+            .into_bytes()
+    }
+
+    /// Total kills, ranking, kills by means and kill feed of a match.
+    type Stats = (
+        i16,
+        Vec<(String, i16)>,
+        Vec<(String, i16)>,
+        Vec<(u32, String, String, String)>,
+    );
+
+    /// What a match tells about its players, named through `rename`.
+    fn stats(record: &MatchRecord, rename: &dyn Fn(&str) -> String) -> Stats {
+        let mut ranking = record
+            .ranking
+            .iter()
+            .map(|ranking_position| (rename(&ranking_position.player), ranking_position.kills))
+            .collect::<Vec<(String, i16)>>();
+        // Ties are ranked by name, which the pseudonyms change
+        ranking.sort();
+        let mut means = record
+            .kills_by_means
+            .clone()
+            .into_iter()
+            .collect::<Vec<(String, i16)>>();
+        means.sort();
+        let kills = record
+            .kill_events
+            .iter()
+            .map(|kill| {
+                (
+                    kill.time,
+                    rename(&kill.killer),
+                    rename(&kill.killed),
+                    kill.means.clone(),
+                )
+            })
+            .collect();
+
+        (record.total_kills, ranking, means, kills)
+    }
+
+    fn assert_same_stats(redact_chat: bool) {
+        let log = generated_log();
+        let mut anonymiser = Anonymiser::new(AnonymiseOptions { redact_chat });
+        let anonymised = anonymiser.anonymise_bytes(&log);
+        let pseudonyms = anonymiser.pseudonyms().clone();
+        let rename = |name: &str| {
+            pseudonyms
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| name.to_string())
+        };
+        let unchanged = |name: &str| name.to_string();
+
+        let original = Parser::parse_bytes(&log).unwrap();
+        let anonymised = Parser::parse_bytes(&anonymised).unwrap();
+        assert_eq!(
+            original.keys().collect::<BTreeSet<&String>>(),
+            anonymised.keys().collect::<BTreeSet<&String>>()
+        );
+        for name in ["Mo killed Cinha", "Hunt\\er", "Sl\\ash\\"] {
+            assert!(pseudonyms.contains_key(name), "{name} has no pseudonym");
+        }
+
+        for (match_key, record) in original.iter() {
+            let anonymised_record = &anonymised[match_key];
+            assert_eq!(
+                stats(record, &rename),
+                stats(anonymised_record, &unchanged),
+                "{match_key}"
+            );
+
+            let chat = |record: &MatchRecord, rename: &dyn Fn(&str) -> String| {
+                record
+                    .chat
+                    .iter()
+                    .map(|message| (message.time, rename(&message.player), message.team_only))
+                    .collect::<Vec<(u32, String, bool)>>()
+            };
+            assert_eq!(
+                chat(record, &rename),
+                chat(anonymised_record, &unchanged),
+                "{match_key}"
+            );
+            if redact_chat {
+                assert!(anonymised_record
+                    .chat
+                    .iter()
+                    .all(|message| message.message == REDACTED));
+            }
+        }
+    }
+
+    #[test]
+    fn anonymised_logs_parse_to_the_same_stats() {
+        assert_same_stats(false);
+    }
+
+    #[test]
+    fn redacted_logs_parse_to_the_same_stats() {
+        assert_same_stats(true);
+    }
+
+    #[test]
+    fn names_are_replaced_everywhere() {
+        let mut anonymiser = Anonymiser::new(AnonymiseOptions::default());
+        let anonymised = String::from_utf8(anonymiser.anonymise_bytes(&generated_log())).unwrap();
+
+        assert!(!anonymised.contains("Cinha"));
+        assert!(!anonymised.contains("Hunt"));
+        assert!(!anonymised.contains("Isgalamido"));
+        assert!(anonymised.contains("Player1"));
+    }
+}
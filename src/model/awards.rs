@@ -0,0 +1,570 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::parser::MatchRecord;
+use crate::model::timeline::ScoreTimeline;
+
+/// Award given by a rule to the players sharing the best value of a match.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Award {
+    /// Stable identifier, e.g. `most_railgun_frags` or `top_fragger_rocket`.
+    pub id: String,
+    pub title: String,
+    /// Every player tied for the award, sorted by name.
+    pub players: Vec<String>,
+    pub value: i32,
+    /// What the value means, e.g. "12 railgun frags".
+    pub detail: String,
+}
+
+impl Award {
+    /// Award for the players with the highest value, none when nobody got past zero.
+    pub fn highest<F>(
+        id: &str,
+        title: &str,
+        values: BTreeMap<String, i32>,
+        detail: F,
+    ) -> Option<Award>
+    where
+        F: Fn(i32) -> String,
+    {
+        let best = values.values().copied().max().filter(|best| *best > 0)?;
+        Some(Award::for_value(id, title, &values, best, detail))
+    }
+
+    /// Award for the players with the lowest value, none without any player.
+    pub fn lowest<F>(
+        id: &str,
+        title: &str,
+        values: BTreeMap<String, i32>,
+        detail: F,
+    ) -> Option<Award>
+    where
+        F: Fn(i32) -> String,
+    {
+        let best = values.values().copied().min()?;
+        Some(Award::for_value(id, title, &values, best, detail))
+    }
+
+    fn for_value<F>(
+        id: &str,
+        title: &str,
+        values: &BTreeMap<String, i32>,
+        value: i32,
+        detail: F,
+    ) -> Award
+    where
+        F: Fn(i32) -> String,
+    {
+        Award {
+            id: id.to_string(),
+            title: title.to_string(),
+            players: values
+                .iter()
+                .filter(|(_, player_value)| **player_value == value)
+                .map(|(player, _)| player.clone())
+                .collect(),
+            value,
+            detail: detail(value),
+        }
+    }
+}
+
+/// A rule giving awards out of a completed match.
+///
+/// Implement it and add it to an `AwardEngine` to give awards of your own.
+pub trait AwardRule: Send + Sync {
+    /// Awards for the match, an empty list when nobody qualifies.
+    fn evaluate(&self, record: &MatchRecord) -> Vec<Award>;
+}
+
+fn count(value: i32, singular: &str, plural: &str) -> String {
+    match value {
+        1 => format!("1 {singular}"),
+        value => format!("{value} {plural}"),
+    }
+}
+
+/// Frags each player made with the given means, suicides and `<world>` left out.
+fn frags_with<P>(record: &MatchRecord, means: P) -> BTreeMap<String, i32>
+where
+    P: Fn(&str) -> bool,
+{
+    let mut frags = BTreeMap::new();
+    for kill in record
+        .kill_events
+        .iter()
+        .filter(|kill| kill.killer != "<world>" && kill.killer != kill.killed && means(&kill.means))
+    {
+        *frags.entry(kill.killer.clone()).or_default() += 1;
+    }
+    frags
+}
+
+/// Most frags with one weapon, splash damage included.
+pub struct MostWeaponFrags {
+    pub id: &'static str,
+    pub title: &'static str,
+    /// Means of death without the `_SPLASH` suffix, e.g. `MOD_RAILGUN`.
+    pub weapon: &'static str,
+}
+
+impl AwardRule for MostWeaponFrags {
+    fn evaluate(&self, record: &MatchRecord) -> Vec<Award> {
+        let weapon = record
+            .metadata
+            .dialect
+            .weapon_name(self.weapon)
+            .to_lowercase();
+        let frags = frags_with(record, |means| {
+            means.trim_end_matches("_SPLASH") == self.weapon
+        });
+
+        Award::highest(self.id, self.title, frags, |value| {
+            count(value, &format!("{weapon} frag"), &format!("{weapon} frags"))
+        })
+        .into_iter()
+        .collect()
+    }
+}
+
+/// Most deaths to `<world>`: falls, lava, crushers and the like.
+pub struct MostWorldDeaths;
+
+impl AwardRule for MostWorldDeaths {
+    fn evaluate(&self, record: &MatchRecord) -> Vec<Award> {
+        let mut deaths = BTreeMap::new();
+        for kill in record
+            .kill_events
+            .iter()
+            .filter(|kill| kill.killer == "<world>")
+        {
+            *deaths.entry(kill.killed.clone()).or_default() += 1;
+        }
+
+        Award::highest(
+            "most_world_deaths",
+            "Gravity's favourite",
+            deaths,
+            |value| format!("{} to <world>", count(value, "death", "deaths")),
+        )
+        .into_iter()
+        .collect()
+    }
+}
+
+/// Fewest deaths of any cause, among the players of the ranking.
+pub struct FewestDeaths;
+
+impl AwardRule for FewestDeaths {
+    fn evaluate(&self, record: &MatchRecord) -> Vec<Award> {
+        // A lone player always has the fewest deaths
+        if record.ranking.len() < 2 {
+            return vec![];
+        }
+
+        let mut deaths: BTreeMap<String, i32> = record
+            .ranking
+            .iter()
+            .map(|ranking_position| (ranking_position.player.clone(), 0))
+            .collect();
+        for kill in record.kill_events.iter() {
+            if let Some(player_deaths) = deaths.get_mut(&kill.killed) {
+                *player_deaths += 1;
+            }
+        }
+
+        Award::lowest("fewest_deaths", "Survivor", deaths, |value| {
+            count(value, "death", "deaths")
+        })
+        .into_iter()
+        .collect()
+    }
+}
+
+/// Winner of the match who was the furthest behind the leader at some point.
+pub struct BiggestComeback;
+
+impl AwardRule for BiggestComeback {
+    fn evaluate(&self, record: &MatchRecord) -> Vec<Award> {
+        let winners = record
+            .ranking
+            .iter()
+            .filter(|ranking_position| ranking_position.position == 1)
+            .map(|ranking_position| ranking_position.player.as_str())
+            .collect::<Vec<&str>>();
+        if winners.is_empty() {
+            return vec![];
+        }
+
+        // Replays the scores, keeping how far behind the leader each winner fell
+        let timeline = ScoreTimeline::from_record(record);
+        let mut events = timeline
+            .series
+            .iter()
+            .flat_map(|(player, points)| {
+                points
+                    .iter()
+                    .map(move |point| (point.time, player.as_str(), point.score))
+            })
+            .collect::<Vec<(u32, &str, i16)>>();
+        events.sort_by_key(|(time, _, _)| *time);
+
+        let mut scores: BTreeMap<&str, i16> = BTreeMap::new();
+        let mut deficits: BTreeMap<String, i32> = winners
+            .iter()
+            .map(|winner| (winner.to_string(), 0))
+            .collect();
+        for (index, (time, player, score)) in events.iter().enumerate() {
+            scores.insert(player, *score);
+
+            // Only compare once every change made at the same time is applied
+            if events.get(index + 1).map(|(next_time, _, _)| next_time) == Some(time) {
+                continue;
+            }
+            let leader_score = scores.values().copied().max().unwrap_or_default();
+            for (winner, deficit) in deficits.iter_mut() {
+                let winner_score = scores.get(winner.as_str()).copied().unwrap_or_default();
+                *deficit = (*deficit).max(i32::from(leader_score) - i32::from(winner_score));
+            }
+        }
+
+        Award::highest("biggest_comeback", "Comeback kid", deficits, |value| {
+            format!("won after trailing by {value}")
+        })
+        .into_iter()
+        .collect()
+    }
+}
+
+/// Most frags with each weapon used in the match, one award per weapon.
+pub struct TopFraggerPerWeapon;
+
+impl AwardRule for TopFraggerPerWeapon {
+    fn evaluate(&self, record: &MatchRecord) -> Vec<Award> {
+        let mut by_weapon: BTreeMap<&str, BTreeMap<String, i32>> = BTreeMap::new();
+        for kill in record
+            .kill_events
+            .iter()
+            .filter(|kill| kill.killer != "<world>" && kill.killer != kill.killed)
+        {
+            *by_weapon
+                .entry(kill.means.trim_end_matches("_SPLASH"))
+                .or_default()
+                .entry(kill.killer.clone())
+                .or_default() += 1;
+        }
+
+        by_weapon
+            .into_iter()
+            .filter_map(|(weapon, frags)| {
+                let id = format!(
+                    "top_fragger_{}",
+                    weapon
+                        .trim_start_matches("UT_")
+                        .trim_start_matches("MOD_")
+                        .to_lowercase()
+                );
+                let title = format!(
+                    "Top fragger: {}",
+                    record.metadata.dialect.weapon_name(weapon)
+                );
+                Award::highest(&id, &title, frags, |value| count(value, "frag", "frags"))
+            })
+            .collect()
+    }
+}
+
+/// Rules evaluated on every completed match, the built-in set by default.
+#[derive(Clone)]
+pub struct AwardEngine {
+    rules: Vec<Arc<dyn AwardRule>>,
+}
+
+impl AwardEngine {
+    /// Engine without any rule.
+    pub fn empty() -> Self {
+        AwardEngine { rules: vec![] }
+    }
+
+    pub fn builtin() -> Self {
+        AwardEngine::empty()
+            .with_rule(MostWeaponFrags {
+                id: "most_railgun_frags",
+                title: "Railgun ace",
+                weapon: "MOD_RAILGUN",
+            })
+            .with_rule(MostWorldDeaths)
+            .with_rule(FewestDeaths)
+            .with_rule(BiggestComeback)
+            .with_rule(TopFraggerPerWeapon)
+    }
+
+    pub fn with_rule<R: AwardRule + 'static>(mut self, rule: R) -> Self {
+        self.rules.push(Arc::new(rule));
+        self
+    }
+
+    /// Awards of every rule, in the order the rules were added.
+    pub fn evaluate(&self, record: &MatchRecord) -> Vec<Award> {
+        self.rules
+            .iter()
+            .flat_map(|rule| rule.evaluate(record))
+            .collect()
+    }
+}
+
+impl Default for AwardEngine {
+    fn default() -> Self {
+        AwardEngine::builtin()
+    }
+}
+
+impl fmt::Debug for AwardEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AwardEngine")
+            .field("rules", &self.rules.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::parser::Parser;
+
+    const ISGALAMIDO: &str = r"  0:01 ClientUserinfoChanged: 2 n\Isgalamido\t\0\model\uriel/zael";
+    const ZEH: &str = r"  0:01 ClientUserinfoChanged: 3 n\Zeh\t\0\model\sarge/default";
+    const MOCINHA: &str = r"  0:01 ClientUserinfoChanged: 4 n\Mocinha\t\0\model\sarge";
+
+    /// Match of `players` made of the `kills` lines.
+    fn record(players: &[&str], kills: &[&str]) -> MatchRecord {
+        let mut log = vec![r"  0:00 InitGame: \sv_hostname\Test\g_gametype\0\mapname\q3dm17"];
+        log.extend(players);
+        log.extend(kills);
+        log.push("  5:00 ShutdownGame:");
+
+        Parser::parse_bytes(log.join("\n").as_bytes())
+            .unwrap()
+            .into_values()
+            .next()
+            .unwrap()
+    }
+
+    fn winners(awards: &[Award]) -> Vec<(&str, Vec<&str>, i32)> {
+        awards
+            .iter()
+            .map(|award| {
+                let players = award.players.iter().map(String::as_str).collect();
+                (award.id.as_str(), players, award.value)
+            })
+            .collect()
+    }
+
+    const RAILGUN: MostWeaponFrags = MostWeaponFrags {
+        id: "most_railgun_frags",
+        title: "Railgun ace",
+        weapon: "MOD_RAILGUN",
+    };
+
+    #[test]
+    fn most_weapon_frags_leaves_suicides_and_other_weapons_out() {
+        let record = record(
+            &[ISGALAMIDO, ZEH, MOCINHA],
+            &[
+                "  0:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+                "  0:20 Kill: 2 4 10: Isgalamido killed Mocinha by MOD_RAILGUN",
+                "  0:30 Kill: 3 2 10: Zeh killed Isgalamido by MOD_RAILGUN",
+                "  0:40 Kill: 3 3 10: Zeh killed Zeh by MOD_RAILGUN",
+                "  0:50 Kill: 4 3 7: Mocinha killed Zeh by MOD_ROCKET_SPLASH",
+            ],
+        );
+        let awards = RAILGUN.evaluate(&record);
+
+        assert_eq!(
+            winners(&awards),
+            [("most_railgun_frags", vec!["Isgalamido"], 2)]
+        );
+        assert_eq!(awards[0].detail, "2 railgun frags");
+    }
+
+    #[test]
+    fn most_weapon_frags_counts_splash_and_ties() {
+        let rocket = MostWeaponFrags {
+            id: "most_rocket_frags",
+            title: "Rocketeer",
+            weapon: "MOD_ROCKET",
+        };
+        let record = record(
+            &[ISGALAMIDO, ZEH, MOCINHA],
+            &[
+                "  0:10 Kill: 2 3 6: Isgalamido killed Zeh by MOD_ROCKET",
+                "  0:20 Kill: 4 2 7: Mocinha killed Isgalamido by MOD_ROCKET_SPLASH",
+            ],
+        );
+        let awards = rocket.evaluate(&record);
+
+        assert_eq!(
+            winners(&awards),
+            [("most_rocket_frags", vec!["Isgalamido", "Mocinha"], 1)]
+        );
+        assert_eq!(awards[0].detail, "1 rocket launcher frag");
+        assert!(RAILGUN.evaluate(&record).is_empty());
+    }
+
+    #[test]
+    fn most_world_deaths() {
+        let record = record(
+            &[ISGALAMIDO, ZEH, MOCINHA],
+            &[
+                "  0:10 Kill: 1022 3 22: <world> killed Zeh by MOD_TRIGGER_HURT",
+                "  0:20 Kill: 1022 3 19: <world> killed Zeh by MOD_FALLING",
+                "  0:30 Kill: 1022 4 22: <world> killed Mocinha by MOD_TRIGGER_HURT",
+                "  0:40 Kill: 1022 4 22: <world> killed Mocinha by MOD_TRIGGER_HURT",
+                "  0:50 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+            ],
+        );
+        let awards = MostWorldDeaths.evaluate(&record);
+
+        assert_eq!(
+            winners(&awards),
+            [("most_world_deaths", vec!["Mocinha", "Zeh"], 2)]
+        );
+        assert_eq!(awards[0].detail, "2 deaths to <world>");
+    }
+
+    #[test]
+    fn no_world_deaths_no_award() {
+        let record = record(
+            &[ISGALAMIDO, ZEH],
+            &["  0:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN"],
+        );
+
+        assert!(MostWorldDeaths.evaluate(&record).is_empty());
+    }
+
+    #[test]
+    fn fewest_deaths_counts_every_cause() {
+        let record = record(
+            &[ISGALAMIDO, ZEH, MOCINHA],
+            &[
+                "  0:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+                "  0:20 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT",
+                "  0:30 Kill: 4 4 6: Mocinha killed Mocinha by MOD_ROCKET_SPLASH",
+                "  0:40 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+            ],
+        );
+        let awards = FewestDeaths.evaluate(&record);
+
+        assert_eq!(
+            winners(&awards),
+            [("fewest_deaths", vec!["Isgalamido", "Mocinha"], 1)]
+        );
+        assert_eq!(awards[0].detail, "1 death");
+    }
+
+    #[test]
+    fn fewest_deaths_needs_two_players() {
+        let record = record(
+            &[ISGALAMIDO],
+            &["  0:10 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT"],
+        );
+
+        assert_eq!(record.ranking.len(), 1);
+        assert!(FewestDeaths.evaluate(&record).is_empty());
+    }
+
+    #[test]
+    fn biggest_comeback_goes_to_a_winner_who_trailed() {
+        let record = record(
+            &[ISGALAMIDO, ZEH],
+            &[
+                "  0:10 Kill: 3 2 10: Zeh killed Isgalamido by MOD_RAILGUN",
+                "  0:20 Kill: 3 2 10: Zeh killed Isgalamido by MOD_RAILGUN",
+                "  0:30 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+                "  0:40 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+                "  0:50 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+            ],
+        );
+        let awards = BiggestComeback.evaluate(&record);
+
+        assert_eq!(
+            winners(&awards),
+            [("biggest_comeback", vec!["Isgalamido"], 2)]
+        );
+        assert_eq!(awards[0].detail, "won after trailing by 2");
+    }
+
+    #[test]
+    fn a_winner_leading_throughout_gets_no_comeback() {
+        let record = record(
+            &[ISGALAMIDO, ZEH],
+            &[
+                "  0:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+                // Scored on the same second, Zeh never led
+                "  0:20 Kill: 3 2 10: Zeh killed Isgalamido by MOD_RAILGUN",
+                "  0:20 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+                "  0:30 Kill: 3 2 10: Zeh killed Isgalamido by MOD_RAILGUN",
+                "  0:40 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+            ],
+        );
+
+        assert_eq!(record.ranking[0].player, "Isgalamido");
+        assert!(BiggestComeback.evaluate(&record).is_empty());
+    }
+
+    #[test]
+    fn top_fragger_per_weapon_gives_one_award_per_weapon() {
+        let record = record(
+            &[ISGALAMIDO, ZEH, MOCINHA],
+            &[
+                "  0:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+                "  0:20 Kill: 3 4 10: Zeh killed Mocinha by MOD_RAILGUN",
+                "  0:30 Kill: 4 2 6: Mocinha killed Isgalamido by MOD_ROCKET",
+                "  0:40 Kill: 4 3 7: Mocinha killed Zeh by MOD_ROCKET_SPLASH",
+                "  0:50 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT",
+                "  1:00 Kill: 3 3 1: Zeh killed Zeh by MOD_SHOTGUN",
+            ],
+        );
+        let awards = TopFraggerPerWeapon.evaluate(&record);
+
+        assert_eq!(
+            winners(&awards),
+            [
+                ("top_fragger_railgun", vec!["Isgalamido", "Zeh"], 1),
+                ("top_fragger_rocket", vec!["Mocinha"], 2),
+            ]
+        );
+        assert_eq!(awards[1].title, "Top fragger: Rocket launcher");
+    }
+
+    #[test]
+    fn the_engine_evaluates_rules_in_order() {
+        let record = record(
+            &[ISGALAMIDO, ZEH],
+            &[
+                "  0:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+                "  0:20 Kill: 1022 3 22: <world> killed Zeh by MOD_TRIGGER_HURT",
+            ],
+        );
+        let ids = AwardEngine::builtin()
+            .evaluate(&record)
+            .into_iter()
+            .map(|award| award.id)
+            .collect::<Vec<String>>();
+
+        assert_eq!(
+            ids,
+            [
+                "most_railgun_frags",
+                "most_world_deaths",
+                "fewest_deaths",
+                "top_fragger_railgun"
+            ]
+        );
+        assert!(AwardEngine::empty().evaluate(&record).is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::player::PlayerProfile;
+use crate::store::StoredMatch;
+
+/// A match every compared player took part in.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SharedMatch {
+    pub match_id: String,
+    pub match_key: String,
+    pub map: Option<String>,
+    pub players: usize,
+    /// Final position of each compared player, in the order of `PlayerComparison::profiles`.
+    pub positions: Vec<Option<u16>>,
+}
+
+/// Two or more players side by side, over every stored match.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlayerComparison {
+    /// Profiles in the order the players were picked, players who never played left out.
+    pub profiles: Vec<PlayerProfile>,
+    /// `duels[i][j]` is how many times `profiles[i]` killed `profiles[j]`.
+    pub duels: Vec<Vec<u32>>,
+    /// In upload order.
+    pub shared_matches: Vec<SharedMatch>,
+}
+
+impl PlayerComparison {
+    pub fn build(names: &[String], stored_matches: &[StoredMatch]) -> PlayerComparison {
+        let mut profiles: Vec<PlayerProfile> = Vec::new();
+        for name in names {
+            if profiles.iter().any(|profile| &profile.name == name) {
+                continue;
+            }
+            profiles.extend(PlayerProfile::build(name, stored_matches));
+        }
+
+        let mut duels = vec![vec![0; profiles.len()]; profiles.len()];
+        for stored_match in stored_matches {
+            let kill_matrix = stored_match.record.kill_matrix();
+            for (killer_index, killer) in profiles.iter().enumerate() {
+                let Some(victims) = kill_matrix.get(&killer.name) else {
+                    continue;
+                };
+                for (victim_index, victim) in profiles.iter().enumerate() {
+                    if killer_index != victim_index {
+                        duels[killer_index][victim_index] +=
+                            victims.get(&victim.name).copied().unwrap_or_default() as u32;
+                    }
+                }
+            }
+        }
+
+        let shared_matches = match profiles.split_first() {
+            Some((first, others)) => first
+                .matches
+                .iter()
+                .filter(|player_match| {
+                    others.iter().all(|profile| {
+                        profile
+                            .matches
+                            .iter()
+                            .any(|other_match| other_match.match_id == player_match.match_id)
+                    })
+                })
+                .map(|player_match| SharedMatch {
+                    match_id: player_match.match_id.clone(),
+                    match_key: player_match.match_key.clone(),
+                    map: player_match.map.clone(),
+                    players: player_match.players,
+                    positions: profiles
+                        .iter()
+                        .map(|profile| {
+                            profile
+                                .matches
+                                .iter()
+                                .find(|other_match| other_match.match_id == player_match.match_id)
+                                .and_then(|other_match| other_match.position)
+                        })
+                        .collect(),
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        PlayerComparison {
+            profiles,
+            duels,
+            shared_matches,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::parser::Parser;
+
+    fn stored_match(id: &str, lines: &[&str]) -> StoredMatch {
+        let mut log = vec![
+            r"  0:00 InitGame: \sv_hostname\Test\g_gametype\0\mapname\q3dm17",
+            r"  0:01 ClientUserinfoChanged: 2 n\Isgalamido\t\0\model\uriel/zael",
+            r"  0:01 ClientUserinfoChanged: 3 n\Zeh\t\0\model\sarge/default",
+        ];
+        log.extend(lines);
+        log.push("  5:00 ShutdownGame:");
+
+        StoredMatch {
+            id: id.to_string(),
+            upload_id: "upload".to_string(),
+            match_key: format!("game_{}", id.trim_start_matches("upload-")),
+            uploaded_at: 0,
+            record: Parser::parse_bytes(log.join("\n").as_bytes())
+                .unwrap()
+                .into_values()
+                .next()
+                .unwrap(),
+        }
+    }
+
+    fn stored_matches() -> Vec<StoredMatch> {
+        vec![
+            stored_match(
+                "upload-0",
+                &[
+                    "  0:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+                    "  0:20 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+                    "  0:30 Kill: 3 2 6: Zeh killed Isgalamido by MOD_ROCKET",
+                    "  0:40 Kill: 3 3 7: Zeh killed Zeh by MOD_ROCKET_SPLASH",
+                ],
+            ),
+            stored_match(
+                "upload-1",
+                &[
+                    r"  0:01 ClientUserinfoChanged: 4 n\Mocinha\t\0\model\sarge",
+                    "  0:10 Kill: 3 2 6: Zeh killed Isgalamido by MOD_ROCKET",
+                    "  0:20 Kill: 3 4 6: Zeh killed Mocinha by MOD_ROCKET",
+                    "  0:30 Kill: 4 3 6: Mocinha killed Zeh by MOD_ROCKET",
+                ],
+            ),
+            stored_match(
+                "upload-2",
+                &[r"  0:01 ClientUserinfoChanged: 4 n\Mocinha\t\0\model\sarge"],
+            ),
+        ]
+    }
+
+    #[test]
+    fn counts_duels_between_the_players_picked() {
+        let names = ["Isgalamido", "Zeh", "Mocinha"].map(String::from);
+        let comparison = PlayerComparison::build(&names, &stored_matches());
+
+        // Suicides are not duels
+        assert_eq!(comparison.duels, [[0, 2, 0], [2, 0, 1], [0, 1, 0]]);
+    }
+
+    #[test]
+    fn keeps_the_order_picked_and_leaves_unknown_and_repeated_players_out() {
+        let names = ["Zeh", "Dono da Bola", "Isgalamido", "Zeh"].map(String::from);
+        let comparison = PlayerComparison::build(&names, &stored_matches());
+
+        let profiles = comparison
+            .profiles
+            .iter()
+            .map(|profile| profile.name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(profiles, ["Zeh", "Isgalamido"]);
+        assert_eq!(comparison.duels, [[0, 2], [2, 0]]);
+    }
+
+    #[test]
+    fn lists_the_matches_every_player_took_part_in() {
+        let names = ["Isgalamido", "Mocinha"].map(String::from);
+        let comparison = PlayerComparison::build(&names, &stored_matches());
+
+        let shared_matches = comparison
+            .shared_matches
+            .iter()
+            .map(|shared_match| {
+                (
+                    shared_match.match_id.as_str(),
+                    shared_match.players,
+                    shared_match.positions.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            shared_matches,
+            [
+                ("upload-1", 3, vec![Some(3), Some(2)]),
+                ("upload-2", 3, vec![Some(1), Some(2)])
+            ]
+        );
+    }
+
+    #[test]
+    fn nobody_picked_compares_nothing() {
+        let comparison = PlayerComparison::build(&[], &stored_matches());
+
+        assert!(comparison.profiles.is_empty());
+        assert!(comparison.duels.is_empty());
+        assert!(comparison.shared_matches.is_empty());
+    }
+}
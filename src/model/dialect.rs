@@ -0,0 +1,896 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// What a flag event did to the flag.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagAction {
+    Taken,
+    Dropped,
+    Returned,
+    Captured,
+}
+
+/// Game-specific events besides kills and chat, players given by the name they had then.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GameEvent {
+    Flag {
+        time: u32,
+        player: String,
+        action: FlagAction,
+        /// Team owning the flag, when the log says.
+        flag: Option<String>,
+    },
+    /// Bombs and, in Wolf:ET, dynamite on objectives.
+    BombPlanted {
+        time: u32,
+        player: String,
+    },
+    BombDefused {
+        time: u32,
+        player: String,
+    },
+    Hit {
+        time: u32,
+        attacker: String,
+        victim: String,
+        location: String,
+    },
+    Revive {
+        time: u32,
+        medic: String,
+        revived: String,
+    },
+}
+
+impl GameEvent {
+    /// Label of the event kind, e.g. "Flags captured".
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameEvent::Flag {
+                action: FlagAction::Taken,
+                ..
+            } => "Flags taken",
+            GameEvent::Flag {
+                action: FlagAction::Dropped,
+                ..
+            } => "Flags dropped",
+            GameEvent::Flag {
+                action: FlagAction::Returned,
+                ..
+            } => "Flags returned",
+            GameEvent::Flag {
+                action: FlagAction::Captured,
+                ..
+            } => "Flags captured",
+            GameEvent::BombPlanted { .. } => "Bombs planted",
+            GameEvent::BombDefused { .. } => "Bombs defused",
+            GameEvent::Hit { .. } => "Hits",
+            GameEvent::Revive { .. } => "Revives",
+        }
+    }
+
+    /// Player credited with the event.
+    pub fn player(&self) -> &str {
+        match self {
+            GameEvent::Flag { player, .. }
+            | GameEvent::BombPlanted { player, .. }
+            | GameEvent::BombDefused { player, .. } => player,
+            GameEvent::Hit { attacker, .. } => attacker,
+            GameEvent::Revive { medic, .. } => medic,
+        }
+    }
+
+    pub fn rename_players<F>(&mut self, rename: F)
+    where
+        F: Fn(&str) -> String,
+    {
+        match self {
+            GameEvent::Flag { player, .. }
+            | GameEvent::BombPlanted { player, .. }
+            | GameEvent::BombDefused { player, .. } => {
+                *player = rename(player);
+            }
+            GameEvent::Hit {
+                attacker, victim, ..
+            } => {
+                *attacker = rename(attacker);
+                *victim = rename(victim);
+            }
+            GameEvent::Revive { medic, revived, .. } => {
+                *medic = rename(medic);
+                *revived = rename(revived);
+            }
+        }
+    }
+}
+
+/// How one Quake-engine game writes its log.
+///
+/// The provided methods follow the baseq3 `games.log`, dialects only override what their game
+/// does differently.
+pub trait LogDialect: Send + Sync {
+    /// Whether the `InitGame` cvars were written by this game.
+    fn detect(&self, cvars: &BTreeMap<String, String>) -> bool;
+
+    /// Killer, victim and means of death of a `Kill:` event.
+    // Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT
+    fn parse_kill<'a>(&self, event: &'a str) -> Option<(&'a str, &'a str, &'a str)> {
+        let split_line = event.splitn(3, ": ").nth(2)?;
+        // Names can hold " by ", which the means of death never does
+        let (killer, rest) = split_line.split_once(" killed ")?;
+        let (killed, means) = rest.rsplit_once(" by ")?;
+
+        Some((killer.trim(), killed.trim(), means.trim()))
+    }
+
+    /// Name of a `g_gametype` value.
+    fn game_type_name(&self, game_type: &str) -> Option<&'static str> {
+        quake3_game_type_name(game_type)
+    }
+
+    /// Name of the team of the `t` userinfo value, `None` for free-for-all players.
+    fn team_name(&self, team: &str) -> Option<&'static str> {
+        match team {
+            "1" => Some("Red"),
+            "2" => Some("Blue"),
+            "3" => Some("Spectator"),
+            _ => None,
+        }
+    }
+
+    /// Readable name of a means of death, splash damage already left out.
+    fn weapon_name(&self, means: &str) -> Option<&'static str> {
+        quake3_weapon_name(means)
+    }
+
+    /// Event of a line the parser doesn't know, `client_name` giving the name of a client slot.
+    fn parse_event(
+        &self,
+        _time: u32,
+        _event: &str,
+        _client_name: &dyn Fn(&str) -> Option<String>,
+    ) -> Option<GameEvent> {
+        None
+    }
+}
+
+fn quake3_game_type_name(game_type: &str) -> Option<&'static str> {
+    let name = match game_type {
+        "0" => "Free for all",
+        "1" => "Tournament",
+        "2" => "Single player",
+        "3" => "Team deathmatch",
+        "4" => "Capture the flag",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+fn quake3_weapon_name(means: &str) -> Option<&'static str> {
+    let name = match means {
+        "MOD_GAUNTLET" => "Gauntlet",
+        "MOD_MACHINEGUN" => "Machinegun",
+        "MOD_SHOTGUN" => "Shotgun",
+        "MOD_GRENADE" => "Grenade launcher",
+        "MOD_ROCKET" => "Rocket launcher",
+        "MOD_PLASMA" => "Plasma gun",
+        "MOD_RAILGUN" => "Railgun",
+        "MOD_LIGHTNING" => "Lightning gun",
+        "MOD_BFG" => "BFG",
+        "MOD_NAIL" => "Nailgun",
+        "MOD_CHAINGUN" => "Chaingun",
+        "MOD_PROXIMITY_MINE" => "Proximity launcher",
+        "MOD_KAMIKAZE" => "Kamikaze",
+        "MOD_JUICED" => "Juiced",
+        "MOD_GRAPPLE" => "Grapple",
+        "MOD_TELEFRAG" => "Telefrag",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+/// Slot and rest of an event of the form `Name: <slot> ...`.
+fn client_event<'a>(event: &'a str, prefix: &str) -> Option<(&'a str, &'a str)> {
+    let rest = event.strip_prefix(prefix)?.trim_start();
+    Some(rest.split_once(char::is_whitespace).unwrap_or((rest, "")))
+}
+
+/// The baseq3 `games.log`, the format used when no other dialect matches.
+pub struct Quake3;
+
+impl LogDialect for Quake3 {
+    fn detect(&self, _cvars: &BTreeMap<String, String>) -> bool {
+        true
+    }
+}
+
+/// OpenArena, with the Team Arena weapons and game types and its `CTF:` flag lines.
+pub struct OpenArena;
+
+impl LogDialect for OpenArena {
+    fn detect(&self, cvars: &BTreeMap<String, String>) -> bool {
+        cvars
+            .get("gamename")
+            .is_some_and(|gamename| gamename == "baseoa")
+            || cvars
+                .get("version")
+                .is_some_and(|version| version.contains("+oa") || version.starts_with("OA"))
+    }
+
+    fn game_type_name(&self, game_type: &str) -> Option<&'static str> {
+        let name = match game_type {
+            "5" => "One flag CTF",
+            "6" => "Overload",
+            "7" => "Harvester",
+            "8" => "Elimination",
+            "9" => "CTF Elimination",
+            "10" => "Last man standing",
+            "11" => "Double domination",
+            "12" => "Domination",
+            game_type => return quake3_game_type_name(game_type),
+        };
+
+        Some(name)
+    }
+
+    // CTF: 2 1 0: Sarge got the RED flag!
+    fn parse_event(
+        &self,
+        time: u32,
+        event: &str,
+        client_name: &dyn Fn(&str) -> Option<String>,
+    ) -> Option<GameEvent> {
+        let (client, rest) = client_event(event, "CTF:")?;
+        let mut fields = rest.split(':').next()?.split_whitespace();
+        let flag = self.team_name(fields.next()?).map(String::from);
+        let action = match fields.next()? {
+            "0" => FlagAction::Taken,
+            "1" => FlagAction::Captured,
+            "2" => FlagAction::Returned,
+            _ => return None,
+        };
+
+        Some(GameEvent::Flag {
+            time,
+            player: client_name(client)?,
+            action,
+            flag,
+        })
+    }
+}
+
+/// Quake Live, whose extra game types and weapons sit on top of the baseq3 format.
+pub struct QuakeLive;
+
+impl LogDialect for QuakeLive {
+    fn detect(&self, cvars: &BTreeMap<String, String>) -> bool {
+        cvars
+            .get("gamename")
+            .is_some_and(|gamename| gamename == "baseqz")
+            || cvars.contains_key("g_factory")
+            || cvars.get("version").is_some_and(|version| {
+                version.to_lowercase().contains("quake live")
+                    || version.to_lowercase().contains("quakelive")
+            })
+    }
+
+    fn game_type_name(&self, game_type: &str) -> Option<&'static str> {
+        let name = match game_type {
+            "0" => "Free for all",
+            "1" => "Duel",
+            "2" => "Race",
+            "3" => "Team deathmatch",
+            "4" => "Clan arena",
+            "5" => "Capture the flag",
+            "6" => "One flag CTF",
+            "8" => "Harvester",
+            "9" => "Freeze tag",
+            "10" => "Domination",
+            "11" => "Attack and defend",
+            "12" => "Red rover",
+            _ => return None,
+        };
+
+        Some(name)
+    }
+
+    fn weapon_name(&self, means: &str) -> Option<&'static str> {
+        match means {
+            "MOD_HMG" => Some("Heavy machinegun"),
+            means => quake3_weapon_name(means),
+        }
+    }
+}
+
+/// Urban Terror, with its `UT_MOD_*` weapons and its hit, flag and bomb lines.
+pub struct UrbanTerror;
+
+impl LogDialect for UrbanTerror {
+    fn detect(&self, cvars: &BTreeMap<String, String>) -> bool {
+        cvars
+            .get("gamename")
+            .is_some_and(|gamename| gamename.starts_with("q3ut") || gamename.starts_with("q3urt"))
+    }
+
+    fn game_type_name(&self, game_type: &str) -> Option<&'static str> {
+        let name = match game_type {
+            "0" => "Free for all",
+            "1" => "Last man standing",
+            "3" => "Team deathmatch",
+            "4" => "Team survivor",
+            "5" => "Follow the leader",
+            "6" => "Capture and hold",
+            "7" => "Capture the flag",
+            "8" => "Bomb mode",
+            "9" => "Jump",
+            "10" => "Freeze tag",
+            "11" => "Gun game",
+            _ => return None,
+        };
+
+        Some(name)
+    }
+
+    fn weapon_name(&self, means: &str) -> Option<&'static str> {
+        let name = match means {
+            "UT_MOD_KNIFE" => "Knife",
+            "UT_MOD_KNIFE_THROWN" => "Thrown knife",
+            "UT_MOD_BERETTA" => "Beretta",
+            "UT_MOD_DEAGLE" => "Desert Eagle",
+            "UT_MOD_SPAS" => "SPAS-12",
+            "UT_MOD_UMP45" => "UMP45",
+            "UT_MOD_MP5K" => "MP5K",
+            "UT_MOD_LR300" => "LR-300",
+            "UT_MOD_G36" => "G36",
+            "UT_MOD_PSG1" => "PSG-1",
+            "UT_MOD_HK69" => "HK69",
+            "UT_MOD_BLED" => "Bleeding",
+            "UT_MOD_KICKED" => "Boot",
+            "UT_MOD_HEGRENADE" => "HE grenade",
+            "UT_MOD_SR8" => "SR-8",
+            "UT_MOD_AK103" => "AK-103",
+            "UT_MOD_NEGEV" => "Negev",
+            "UT_MOD_M4" => "M4",
+            "UT_MOD_GLOCK" => "Glock",
+            "UT_MOD_COLT1911" => "Colt 1911",
+            "UT_MOD_MAC11" => "MAC-11",
+            "UT_MOD_FRF1" => "FR-F1",
+            "UT_MOD_BENELLI" => "Benelli",
+            "UT_MOD_P90" => "P90",
+            "UT_MOD_MAGNUM" => ".44 Magnum",
+            "UT_MOD_TOD50" => "TOD-50",
+            "UT_MOD_GOOMBA" => "Goomba stomp",
+            "UT_MOD_BOMBED" => "Bomb",
+            _ => return None,
+        };
+
+        Some(name)
+    }
+
+    fn parse_event(
+        &self,
+        time: u32,
+        event: &str,
+        client_name: &dyn Fn(&str) -> Option<String>,
+    ) -> Option<GameEvent> {
+        // Hit: 1 0 2 21: Player1 hit Player2 in the Head
+        if event.starts_with("Hit:") {
+            let text = event.splitn(3, ": ").nth(2)?;
+            let (attacker, rest) = text.split_once(" hit ")?;
+            let (victim, location) = rest.rsplit_once(" in the ")?;
+
+            return Some(GameEvent::Hit {
+                time,
+                attacker: attacker.to_string(),
+                victim: victim.to_string(),
+                location: location.trim().to_string(),
+            });
+        }
+
+        // Flag: 0 2: team_CTF_redflag
+        if let Some((client, rest)) = client_event(event, "Flag:") {
+            let (action, flag) = rest.split_once(':')?;
+            let action = match action.trim() {
+                "0" => FlagAction::Dropped,
+                "1" => FlagAction::Returned,
+                "2" => FlagAction::Captured,
+                _ => return None,
+            };
+            let flag = match flag.trim() {
+                "team_CTF_redflag" => Some("Red".to_string()),
+                "team_CTF_blueflag" => Some("Blue".to_string()),
+                _ => None,
+            };
+
+            return Some(GameEvent::Flag {
+                time,
+                player: client_name(client)?,
+                action,
+                flag,
+            });
+        }
+
+        // Bomb was planted by 2
+        if let Some(client) = event.strip_prefix("Bomb was planted by ") {
+            return Some(GameEvent::BombPlanted {
+                time,
+                player: client_name(client.trim_end_matches('!').trim())?,
+            });
+        }
+        if let Some(client) = event.strip_prefix("Bomb was defused by ") {
+            return Some(GameEvent::BombDefused {
+                time,
+                player: client_name(client.trim_end_matches('!').trim())?,
+            });
+        }
+
+        None
+    }
+}
+
+/// Wolfenstein: Enemy Territory and its mods, with Axis and Allies teams.
+pub struct WolfEt;
+
+impl LogDialect for WolfEt {
+    fn detect(&self, cvars: &BTreeMap<String, String>) -> bool {
+        let gamename = cvars.get("gamename").map(String::as_str);
+        matches!(
+            gamename,
+            Some(
+                "etmain"
+                    | "etpro"
+                    | "legacy"
+                    | "etlegacy"
+                    | "jaymod"
+                    | "nq"
+                    | "noquarter"
+                    | "silent"
+            )
+        ) || cvars
+            .get("version")
+            .is_some_and(|version| version.starts_with("ET "))
+    }
+
+    fn game_type_name(&self, game_type: &str) -> Option<&'static str> {
+        let name = match game_type {
+            "2" => "Objective",
+            "3" => "Stopwatch",
+            "4" => "Campaign",
+            "5" => "Last man standing",
+            "6" => "Map voting",
+            _ => return None,
+        };
+
+        Some(name)
+    }
+
+    fn team_name(&self, team: &str) -> Option<&'static str> {
+        match team {
+            "1" => Some("Axis"),
+            "2" => Some("Allies"),
+            "3" => Some("Spectator"),
+            _ => None,
+        }
+    }
+
+    fn weapon_name(&self, means: &str) -> Option<&'static str> {
+        let name = match means {
+            "MOD_KNIFE" => "Knife",
+            "MOD_LUGER" => "Luger",
+            "MOD_COLT" => "Colt",
+            "MOD_MP40" => "MP40",
+            "MOD_THOMPSON" => "Thompson",
+            "MOD_STEN" => "Sten",
+            "MOD_GARAND" => "Garand",
+            "MOD_K43" => "K43",
+            "MOD_FG42" => "FG42",
+            "MOD_PANZERFAUST" => "Panzerfaust",
+            "MOD_FLAMETHROWER" => "Flamethrower",
+            "MOD_MORTAR" => "Mortar",
+            "MOD_GRENADE_LAUNCHER" | "MOD_GRENADE_PINEAPPLE" => "Grenade",
+            "MOD_DYNAMITE" => "Dynamite",
+            "MOD_AIRSTRIKE" => "Airstrike",
+            "MOD_ARTY" => "Artillery",
+            "MOD_SATCHEL" => "Satchel charge",
+            "MOD_LANDMINE" => "Landmine",
+            "MOD_MOBILE_MG42" => "Mobile MG42",
+            "MOD_MACHINEGUN" => "Mounted MG",
+            _ => return None,
+        };
+
+        Some(name)
+    }
+
+    // Medic_Revive: 3 5
+    // Dynamite_Plant: 4
+    fn parse_event(
+        &self,
+        time: u32,
+        event: &str,
+        client_name: &dyn Fn(&str) -> Option<String>,
+    ) -> Option<GameEvent> {
+        if let Some((medic, revived)) = client_event(event, "Medic_Revive:") {
+            return Some(GameEvent::Revive {
+                time,
+                medic: client_name(medic)?,
+                revived: client_name(revived.split_whitespace().next()?)?,
+            });
+        }
+        if let Some((client, _)) = client_event(event, "Dynamite_Plant:") {
+            return Some(GameEvent::BombPlanted {
+                time,
+                player: client_name(client)?,
+            });
+        }
+        if let Some((client, _)) = client_event(event, "Dynamite_Diffuse:") {
+            return Some(GameEvent::BombDefused {
+                time,
+                player: client_name(client)?,
+            });
+        }
+
+        None
+    }
+}
+
+/// Games whose logs can be parsed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Dialect {
+    #[default]
+    Quake3,
+    OpenArena,
+    QuakeLive,
+    UrbanTerror,
+    WolfEt,
+}
+
+impl Dialect {
+    /// Baseq3 last, since it takes whatever the others don't.
+    pub const ALL: [Dialect; 5] = [
+        Dialect::OpenArena,
+        Dialect::QuakeLive,
+        Dialect::UrbanTerror,
+        Dialect::WolfEt,
+        Dialect::Quake3,
+    ];
+
+    /// Name used in settings.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Dialect::Quake3 => "quake3",
+            Dialect::OpenArena => "openarena",
+            Dialect::QuakeLive => "quakelive",
+            Dialect::UrbanTerror => "urbanterror",
+            Dialect::WolfEt => "wolfet",
+        }
+    }
+
+    pub fn rules(&self) -> &'static dyn LogDialect {
+        match self {
+            Dialect::Quake3 => &Quake3,
+            Dialect::OpenArena => &OpenArena,
+            Dialect::QuakeLive => &QuakeLive,
+            Dialect::UrbanTerror => &UrbanTerror,
+            Dialect::WolfEt => &WolfEt,
+        }
+    }
+
+    /// Game that wrote an `InitGame` line, from its `gamename` and `version` cvars.
+    pub fn detect(cvars: &BTreeMap<String, String>) -> Dialect {
+        Dialect::ALL
+            .into_iter()
+            .find(|dialect| dialect.rules().detect(cvars))
+            .unwrap_or_default()
+    }
+
+    /// Readable name of a means of death, made up from the means itself when the game has none.
+    pub fn weapon_name(&self, means: &str) -> String {
+        let means = means.trim_end_matches("_SPLASH");
+        match self.rules().weapon_name(means) {
+            Some(name) => name.to_string(),
+            None => {
+                let name = means
+                    .trim_start_matches("UT_")
+                    .trim_start_matches("MOD_")
+                    .replace('_', " ")
+                    .to_lowercase();
+                let mut chars = name.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or(name)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Dialect::Quake3 => "Quake III Arena",
+            Dialect::OpenArena => "OpenArena",
+            Dialect::QuakeLive => "Quake Live",
+            Dialect::UrbanTerror => "Urban Terror",
+            Dialect::WolfEt => "Wolfenstein: Enemy Territory",
+        })
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Dialect::ALL
+            .into_iter()
+            .find(|dialect| dialect.as_str() == value.to_lowercase())
+            .ok_or_else(|| {
+                format!("Unknown log dialect {value:?}, expected quake3, openarena, quakelive, urbanterror or wolfet")
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cvars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// Names of the client slots of the sample lines.
+    fn client_name(client: &str) -> Option<String> {
+        let name = match client {
+            "0" => "Sarge",
+            "2" => "Isgalamido",
+            "3" => "Zeh",
+            "4" => "Dono da Bola",
+            "5" => "Mocinha",
+            _ => return None,
+        };
+
+        Some(name.to_string())
+    }
+
+    fn parse_event(dialect: Dialect, event: &str) -> Option<GameEvent> {
+        dialect.rules().parse_event(65, event, &client_name)
+    }
+
+    #[test]
+    fn detects_the_game_from_its_cvars() {
+        let cases = [
+            (vec![], Dialect::Quake3),
+            (
+                vec![
+                    ("gamename", "baseq3"),
+                    ("version", "ioq3 1.36_GIT_f2c61c14-2020-02-11 linux-x86_64"),
+                ],
+                Dialect::Quake3,
+            ),
+            (vec![("gamename", "baseoa")], Dialect::OpenArena),
+            (
+                vec![("version", "ioq3+oa 1.36_SVN1910M linux-i386 Jan 23 2011")],
+                Dialect::OpenArena,
+            ),
+            (
+                vec![("version", "OA 0.8.8 linux-x86_64")],
+                Dialect::OpenArena,
+            ),
+            (vec![("gamename", "baseqz")], Dialect::QuakeLive),
+            (
+                vec![("gamename", "baseq3"), ("g_factory", "ffa")],
+                Dialect::QuakeLive,
+            ),
+            (
+                vec![("version", "Quake Live 1069 linux-x64 Apr 14 2016")],
+                Dialect::QuakeLive,
+            ),
+            (
+                vec![
+                    ("gamename", "q3ut4"),
+                    ("version", "ioq3 1.35 urt 4.1.1 linux-i386"),
+                ],
+                Dialect::UrbanTerror,
+            ),
+            (vec![("gamename", "q3urt43")], Dialect::UrbanTerror),
+            (vec![("gamename", "etpro")], Dialect::WolfEt),
+            (
+                vec![
+                    ("gamename", "legacy"),
+                    ("version", "ET Legacy v2.81.1 linux-x86_64"),
+                ],
+                Dialect::WolfEt,
+            ),
+            (
+                vec![("version", "ET 2.60b linux-i386 May  8 2006")],
+                Dialect::WolfEt,
+            ),
+        ];
+
+        for (pairs, dialect) in cases {
+            assert_eq!(Dialect::detect(&cvars(&pairs)), dialect, "{pairs:?}");
+        }
+    }
+
+    #[test]
+    fn parses_kills() {
+        let kill = |event| Quake3.parse_kill(event);
+
+        assert_eq!(
+            kill("Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT"),
+            Some(("<world>", "Isgalamido", "MOD_TRIGGER_HURT"))
+        );
+        assert_eq!(
+            kill("Kill: 3 4 7: Zeh killed Dono da Bola by MOD_ROCKET_SPLASH"),
+            Some(("Zeh", "Dono da Bola", "MOD_ROCKET_SPLASH"))
+        );
+        assert_eq!(
+            kill("Kill: 3 2 10: Ze by h killed Isgalamido by MOD_RAILGUN"),
+            Some(("Ze by h", "Isgalamido", "MOD_RAILGUN"))
+        );
+        assert_eq!(
+            kill("Kill: 2 3 10: Isgalamido killed Ze by h by MOD_RAILGUN"),
+            Some(("Isgalamido", "Ze by h", "MOD_RAILGUN"))
+        );
+        assert_eq!(
+            kill("Kill: 2 3 10: Isgalamido killed Killer: by MOD_RAILGUN"),
+            Some(("Isgalamido", "Killer:", "MOD_RAILGUN"))
+        );
+        assert_eq!(kill("Kill: 2 3 10: Isgalamido killed Zeh"), None);
+        assert_eq!(kill("Kill: 2 3"), None);
+    }
+
+    #[test]
+    fn parses_openarena_flags() {
+        let flag = |event| parse_event(Dialect::OpenArena, event);
+
+        assert_eq!(
+            flag("CTF: 0 1 0: Sarge got the RED flag!"),
+            Some(GameEvent::Flag {
+                time: 65,
+                player: "Sarge".to_string(),
+                action: FlagAction::Taken,
+                flag: Some("Red".to_string()),
+            })
+        );
+        assert_eq!(
+            flag("CTF: 2 2 1: Isgalamido captured the BLUE flag!"),
+            Some(GameEvent::Flag {
+                time: 65,
+                player: "Isgalamido".to_string(),
+                action: FlagAction::Captured,
+                flag: Some("Blue".to_string()),
+            })
+        );
+        assert_eq!(
+            flag("CTF: 3 1 2: Zeh returned the RED flag!"),
+            Some(GameEvent::Flag {
+                time: 65,
+                player: "Zeh".to_string(),
+                action: FlagAction::Returned,
+                flag: Some("Red".to_string()),
+            })
+        );
+        // Fragging the carrier is not a flag action of its own
+        assert_eq!(flag("CTF: 3 2 3: Zeh fragged BLUE's flag carrier!"), None);
+        assert_eq!(flag("CTF: 9 1 0: Nobody got the RED flag!"), None);
+    }
+
+    #[test]
+    fn parses_urban_terror_events() {
+        let event = |event| parse_event(Dialect::UrbanTerror, event);
+
+        assert_eq!(
+            event("Hit: 3 2 1 19: Zeh hit Isgalamido in the Head"),
+            Some(GameEvent::Hit {
+                time: 65,
+                attacker: "Zeh".to_string(),
+                victim: "Isgalamido".to_string(),
+                location: "Head".to_string(),
+            })
+        );
+        assert_eq!(
+            event("Hit: 2 4 5 14: Isgalamido hit Dono da Bola in the Left Arm"),
+            Some(GameEvent::Hit {
+                time: 65,
+                attacker: "Isgalamido".to_string(),
+                victim: "Dono da Bola".to_string(),
+                location: "Left Arm".to_string(),
+            })
+        );
+        assert_eq!(
+            event("Flag: 2 2: team_CTF_redflag"),
+            Some(GameEvent::Flag {
+                time: 65,
+                player: "Isgalamido".to_string(),
+                action: FlagAction::Captured,
+                flag: Some("Red".to_string()),
+            })
+        );
+        assert_eq!(
+            event("Flag: 3 0: team_CTF_blueflag"),
+            Some(GameEvent::Flag {
+                time: 65,
+                player: "Zeh".to_string(),
+                action: FlagAction::Dropped,
+                flag: Some("Blue".to_string()),
+            })
+        );
+        assert_eq!(
+            event("Flag: 5 1: team_CTF_redflag"),
+            Some(GameEvent::Flag {
+                time: 65,
+                player: "Mocinha".to_string(),
+                action: FlagAction::Returned,
+                flag: Some("Red".to_string()),
+            })
+        );
+        assert_eq!(
+            event("Bomb was planted by 2!"),
+            Some(GameEvent::BombPlanted {
+                time: 65,
+                player: "Isgalamido".to_string(),
+            })
+        );
+        assert_eq!(
+            event("Bomb was defused by 3!"),
+            Some(GameEvent::BombDefused {
+                time: 65,
+                player: "Zeh".to_string(),
+            })
+        );
+        assert_eq!(event("Flag: 2 7: team_CTF_redflag"), None);
+        assert_eq!(event("Bomb was tossed by 2"), None);
+    }
+
+    #[test]
+    fn parses_enemy_territory_events() {
+        let event = |event| parse_event(Dialect::WolfEt, event);
+
+        assert_eq!(
+            event("Medic_Revive: 3 5"),
+            Some(GameEvent::Revive {
+                time: 65,
+                medic: "Zeh".to_string(),
+                revived: "Mocinha".to_string(),
+            })
+        );
+        assert_eq!(
+            event("Dynamite_Plant: 4"),
+            Some(GameEvent::BombPlanted {
+                time: 65,
+                player: "Dono da Bola".to_string(),
+            })
+        );
+        assert_eq!(
+            event("Dynamite_Diffuse: 2"),
+            Some(GameEvent::BombDefused {
+                time: 65,
+                player: "Isgalamido".to_string(),
+            })
+        );
+        assert_eq!(event("Medic_Revive: 3"), None);
+        assert_eq!(event("Dynamite_Plant: 9"), None);
+    }
+
+    #[test]
+    fn quake3_and_quake_live_log_no_other_events() {
+        for dialect in [Dialect::Quake3, Dialect::QuakeLive] {
+            assert_eq!(
+                parse_event(dialect, "CTF: 0 1 0: Sarge got the RED flag!"),
+                None
+            );
+            assert_eq!(parse_event(dialect, "Dynamite_Plant: 4"), None);
+        }
+    }
+}
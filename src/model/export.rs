@@ -0,0 +1,312 @@
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+
+use crate::model::names::strip_colour_codes;
+use crate::model::parser::{format_game_time, MatchRecord};
+use crate::store::match_number;
+
+/// Formats a parsed report can be downloaded in.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+    Markdown,
+    Xlsx,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Xlsx => "xlsx",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+            ExportFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Text(String),
+    Number(f64),
+}
+
+impl Value {
+    fn text(&self) -> String {
+        match self {
+            Value::Text(text) => text.clone(),
+            Value::Number(number) => number.to_string(),
+        }
+    }
+}
+
+/// One of the tables shown on a match card.
+#[derive(Clone, Debug)]
+pub struct Table {
+    /// Identifier used by `?table=` and the long CSV format.
+    pub name: &'static str,
+    pub title: &'static str,
+    pub headers: Vec<&'static str>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// Names of the tables returned by `match_tables`, in order.
+pub const TABLE_NAMES: [&str; 3] = ["ranking", "means", "kills"];
+
+/// Ranking, kills by means (most used first) and the kills of a match in the order they happened.
+pub fn match_tables(record: &MatchRecord) -> Vec<Table> {
+    let ranking = record
+        .ranking
+        .iter()
+        .map(|ranking_position| {
+            vec![
+                Value::Number(ranking_position.position as f64),
+                Value::Text(strip_colour_codes(&ranking_position.player)),
+                Value::Number(ranking_position.kills as f64),
+            ]
+        })
+        .collect();
+
+    let mut kills_by_means = record
+        .kills_by_means
+        .iter()
+        .collect::<Vec<(&String, &i16)>>();
+    kills_by_means.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    let means = kills_by_means
+        .into_iter()
+        .map(|(means, kills)| vec![Value::Text(means.clone()), Value::Number(*kills as f64)])
+        .collect();
+
+    let kills = record
+        .kill_events
+        .iter()
+        .map(|kill| {
+            vec![
+                Value::Text(format_game_time(kill.time)),
+                Value::Text(strip_colour_codes(&kill.killer)),
+                Value::Text(strip_colour_codes(&kill.killed)),
+                Value::Text(kill.means.clone()),
+            ]
+        })
+        .collect();
+
+    vec![
+        Table {
+            name: "ranking",
+            title: "Players ranking",
+            headers: vec!["Ranking", "Player", "Kill Score"],
+            rows: ranking,
+        },
+        Table {
+            name: "means",
+            title: "Means of kills",
+            headers: vec!["Damage source", "Kills"],
+            rows: means,
+        },
+        Table {
+            name: "kills",
+            title: "Kills",
+            headers: vec!["Time", "Killer", "Victim", "Means"],
+            rows: kills,
+        },
+    ]
+}
+
+/// Matches of a report ordered by their number.
+pub fn ordered_matches<'a, I>(matches: I) -> Vec<(String, MatchRecord)>
+where
+    I: IntoIterator<Item = (&'a String, &'a MatchRecord)>,
+{
+    let mut ordered = matches
+        .into_iter()
+        .map(|(match_key, record)| (match_key.clone(), record.clone()))
+        .collect::<Vec<(String, MatchRecord)>>();
+    ordered.sort_by_key(|(match_key, _)| match_number(match_key));
+    ordered
+}
+
+/// Text starting like a formula is prefixed with `'`, so spreadsheets opening the export show
+/// player names and chat as typed instead of evaluating them. Numbers are left as they are.
+fn csv_field(value: &Value) -> String {
+    let text = match value {
+        Value::Number(number) => return number.to_string(),
+        Value::Text(text) => text,
+    };
+
+    if text.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("\"'{}\"", text.replace('"', "\"\""))
+    } else if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.clone()
+    }
+}
+
+fn csv_line(fields: impl IntoIterator<Item = Value>) -> String {
+    let mut line = fields
+        .into_iter()
+        .map(|field| csv_field(&field))
+        .collect::<Vec<String>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+/// A single table as CSV, with a header line.
+pub fn table_csv(table: &Table) -> String {
+    let mut csv = csv_line(
+        table
+            .headers
+            .iter()
+            .map(|header| Value::Text(header.to_string())),
+    );
+    for row in table.rows.iter() {
+        csv.push_str(&csv_line(row.iter().cloned()));
+    }
+    csv
+}
+
+/// Every table of every match in a single long-format CSV, one line per cell.
+pub fn long_csv(matches: &[(String, MatchRecord)]) -> String {
+    let mut csv = csv_line(
+        ["match", "table", "row", "column", "value"].map(|header| Value::Text(header.to_string())),
+    );
+
+    for (match_key, record) in matches.iter() {
+        for table in match_tables(record) {
+            for (row_number, row) in table.rows.iter().enumerate() {
+                for (header, value) in table.headers.iter().zip(row.iter()) {
+                    csv.push_str(&csv_line([
+                        Value::Text(match_key.clone()),
+                        Value::Text(table.name.to_string()),
+                        Value::Number((row_number + 1) as f64),
+                        Value::Text(header.to_string()),
+                        value.clone(),
+                    ]));
+                }
+            }
+        }
+    }
+
+    csv
+}
+
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Ranking and means tables of each match, ready to be pasted in a forum post.
+pub fn markdown(matches: &[(String, MatchRecord)]) -> String {
+    let mut markdown = String::new();
+
+    for (match_key, record) in matches.iter() {
+        markdown.push_str(&format!("## Match {}\n\n", match_number(match_key)));
+
+        for table in match_tables(record)
+            .into_iter()
+            .filter(|table| table.name != "kills")
+        {
+            markdown.push_str(&format!("### {}\n\n", table.title));
+            markdown.push_str(&format!("| {} |\n", table.headers.join(" | ")));
+            markdown.push_str(&format!("|{}\n", " --- |".repeat(table.headers.len())));
+            for row in table.rows.iter() {
+                let cells = row
+                    .iter()
+                    .map(|value| markdown_cell(&value.text()))
+                    .collect::<Vec<String>>();
+                markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+            }
+            markdown.push('\n');
+        }
+
+        markdown.push_str(&format!("Total match kills: {}\n\n", record.total_kills));
+    }
+
+    markdown
+}
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+
+/// Workbook with one sheet per match, its tables laid out one below the other.
+pub fn xlsx(matches: &[(String, MatchRecord)]) -> Result<Vec<u8>, XlsxError> {
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+
+    for (match_key, record) in matches.iter() {
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(format!("Match {}", match_number(match_key)))?;
+        worksheet.set_column_width(0, 24)?;
+        worksheet.set_column_width(1, 24)?;
+        worksheet.set_column_width(2, 24)?;
+        worksheet.set_column_width(3, 24)?;
+
+        let mut row_number = 0;
+        for table in match_tables(record) {
+            worksheet.write_string_with_format(row_number, 0, table.title, &bold)?;
+            row_number += 1;
+
+            for (column, header) in table.headers.iter().enumerate() {
+                worksheet.write_string_with_format(row_number, column as u16, *header, &bold)?;
+            }
+            row_number += 1;
+
+            for row in table.rows.iter() {
+                for (column, value) in row.iter().enumerate() {
+                    match value {
+                        Value::Text(text) => worksheet.write_string(row_number, column as u16, text)?,
+                        Value::Number(number) => worksheet.write_number(row_number, column as u16, *number)?,
+                    };
+                }
+                row_number += 1;
+            }
+
+            // Blank line between tables
+            row_number += 1;
+        }
+    }
+
+    workbook.save_to_buffer()
+}
+}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_starting_like_formulas_are_escaped() {
+        for formula in ["=1+1", "+1", "-1", "@SUM(A1)", "\tx", "\rx"] {
+            assert_eq!(
+                csv_field(&Value::Text(formula.to_string())),
+                format!("\"'{formula}\"")
+            );
+        }
+        assert_eq!(
+            csv_field(&Value::Text("=HYPERLINK(\"http://x\")".to_string())),
+            "\"'=HYPERLINK(\"\"http://x\"\")\""
+        );
+        assert_eq!(
+            csv_field(&Value::Text("Isgalamido".to_string())),
+            "Isgalamido"
+        );
+        assert_eq!(
+            csv_field(&Value::Text("Dono, da Bola".to_string())),
+            "\"Dono, da Bola\""
+        );
+        assert_eq!(csv_field(&Value::Number(-5.0)), "-5");
+    }
+}
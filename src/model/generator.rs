@@ -0,0 +1,494 @@
+use std::io::{self, Write};
+
+/// Settings of a generated log. The same settings and seed always give the same log.
+#[derive(Clone, Debug)]
+pub struct GeneratorOptions {
+    pub seed: u64,
+    /// Matches to generate, unless `target_bytes` is set.
+    pub matches: usize,
+    /// Keeps generating matches until the log is at least this big.
+    pub target_bytes: Option<u64>,
+    /// Players joining each match, a random count between the two.
+    pub min_players: usize,
+    pub max_players: usize,
+    /// Most kills of a match, `<world>` deaths included.
+    pub max_kills: u32,
+    /// Share of the matches ending without their `ShutdownGame` line, in percent.
+    pub missing_shutdown_percent: u32,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            seed: 0,
+            matches: 10,
+            target_bytes: None,
+            min_players: 2,
+            max_players: 8,
+            max_kills: 80,
+            missing_shutdown_percent: 10,
+        }
+    }
+}
+
+/// What went into a generated log.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GeneratedLog {
+    pub matches: usize,
+    pub lines: u64,
+    pub bytes: u64,
+}
+
+const PLAYER_NAMES: [&str; 20] = [
+    "Isgalamido",
+    "Dono da Bola",
+    "Mocinha",
+    "Zeh",
+    "Assasinu Credi",
+    "Oootsimo",
+    "Mal",
+    "Chessus",
+    "^1Sarge^7",
+    "^4Blue^7Bird",
+    "Fragmaster",
+    "xXx_Sniper_xXx",
+    "[LoL]Doom",
+    "=FK=Orbb",
+    "Anarki",
+    "^3Klesk",
+    "Hunter",
+    "Slash",
+    "UnnamedPlayer",
+    "Visor",
+];
+
+const MAPS: [&str; 10] = [
+    "q3dm17",
+    "q3dm6",
+    "q3dm7",
+    "q3dm13",
+    "q3tourney2",
+    "q3tourney4",
+    "q3ctf1",
+    "q3ctf4",
+    "pro-q3dm6",
+    "ztn3dm1",
+];
+
+/// `g_gametype` values, free for all the most often.
+const GAME_TYPES: [&str; 6] = ["0", "0", "0", "1", "3", "4"];
+
+/// Means of death with their index in the `Kill:` line, the common weapons listed more than once.
+const WEAPONS: [(&str, u32); 19] = [
+    ("MOD_RAILGUN", 10),
+    ("MOD_RAILGUN", 10),
+    ("MOD_RAILGUN", 10),
+    ("MOD_ROCKET", 6),
+    ("MOD_ROCKET", 6),
+    ("MOD_ROCKET_SPLASH", 7),
+    ("MOD_ROCKET_SPLASH", 7),
+    ("MOD_ROCKET_SPLASH", 7),
+    ("MOD_SHOTGUN", 1),
+    ("MOD_MACHINEGUN", 3),
+    ("MOD_MACHINEGUN", 3),
+    ("MOD_LIGHTNING", 11),
+    ("MOD_LIGHTNING", 11),
+    ("MOD_PLASMA", 8),
+    ("MOD_PLASMA_SPLASH", 9),
+    ("MOD_GAUNTLET", 2),
+    ("MOD_GRENADE_SPLASH", 5),
+    ("MOD_BFG", 12),
+    ("MOD_TELEFRAG", 18),
+];
+
+const WORLD_DEATHS: [(&str, u32); 5] = [
+    ("MOD_TRIGGER_HURT", 22),
+    ("MOD_TRIGGER_HURT", 22),
+    ("MOD_FALLING", 19),
+    ("MOD_LAVA", 16),
+    ("MOD_CRUSH", 17),
+];
+
+const ITEMS: [&str; 8] = [
+    "weapon_rocketlauncher",
+    "weapon_railgun",
+    "weapon_lightning",
+    "ammo_rockets",
+    "ammo_slugs",
+    "item_armor_body",
+    "item_health_large",
+    "item_quad",
+];
+
+const CHAT: [&str; 10] = [
+    "gg",
+    "nice shot",
+    "lag!",
+    "rematch?",
+    "camper...",
+    "lol",
+    "who has the rail?",
+    "brb",
+    "ez",
+    "wp",
+];
+
+/// Client slot the logs give to `<world>` kills.
+const WORLD_ID: u32 = 1022;
+
+/// SplitMix64, small and stable across versions so a seed always gives the same log.
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Between `min` and `max`, both included.
+    fn range(&mut self, min: u64, max: u64) -> u64 {
+        min + self.next() % (max.saturating_sub(min) + 1)
+    }
+
+    fn chance(&mut self, percent: u32) -> bool {
+        self.next() % 100 < u64::from(percent)
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.next() as usize % items.len()]
+    }
+}
+
+/// A player connected to the match being generated.
+struct Client {
+    id: u32,
+    name: String,
+    team: u32,
+    /// Index in `PLAYER_NAMES`, for a GUID and IP that stay the same across matches.
+    player: usize,
+    /// Time of the last death, nobody dying twice in the same second.
+    died_at: Option<u32>,
+}
+
+/// Writes realistic `games.log` content: matches of random players, renames, team changes, kills
+/// with varied weapons, `<world>` deaths, item pickups and chat, some matches missing their
+/// `ShutdownGame` line.
+pub struct LogGenerator {
+    options: GeneratorOptions,
+    random: Random,
+    /// Server uptime in seconds, the time in front of every line.
+    time: u32,
+    summary: GeneratedLog,
+}
+
+impl LogGenerator {
+    pub fn new(options: GeneratorOptions) -> Self {
+        LogGenerator {
+            random: Random(options.seed),
+            options,
+            time: 0,
+            summary: GeneratedLog::default(),
+        }
+    }
+
+    /// Writes the whole log, returning what went into it.
+    pub fn generate<W: Write>(mut self, mut writer: W) -> io::Result<GeneratedLog> {
+        loop {
+            let done = match self.options.target_bytes {
+                Some(target_bytes) => self.summary.bytes >= target_bytes,
+                None => self.summary.matches >= self.options.matches,
+            };
+            if done {
+                break;
+            }
+
+            self.generate_match(&mut writer)?;
+        }
+
+        writer.flush()?;
+        Ok(self.summary)
+    }
+
+    /// The whole log held in memory, for small logs.
+    pub fn generate_bytes(self) -> Vec<u8> {
+        let mut log = Vec::new();
+        // Writing to a Vec can't fail
+        let _ = self.generate(&mut log);
+        log
+    }
+
+    fn line<W: Write>(&mut self, writer: &mut W, event: &str) -> io::Result<()> {
+        let line = format!("{:>3}:{:02} {event}\n", self.time / 60, self.time % 60);
+        writer.write_all(line.as_bytes())?;
+
+        self.summary.lines += 1;
+        self.summary.bytes += line.len() as u64;
+        Ok(())
+    }
+
+    fn generate_match<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        let game_type = *self.random.pick(&GAME_TYPES);
+        let team_game = game_type == "3" || game_type == "4";
+        let map = *self.random.pick(&MAPS);
+        let fraglimit = self.random.range(2, 6) * 5;
+
+        self.line(writer, &"-".repeat(60))?;
+        self.line(
+            writer,
+            &format!(
+                "InitGame: \\sv_floodProtect\\1\\sv_maxPing\\0\\sv_hostname\\Generated Server\\g_gametype\\{game_type}\\sv_maxclients\\16\\fraglimit\\{fraglimit}\\timelimit\\15\\capturelimit\\8\\version\\ioq3 1.36 linux-x86_64 Apr 12 2009\\protocol\\68\\mapname\\{map}\\gamename\\baseq3\\g_needpass\\0"
+            ),
+        )?;
+
+        let max_players = self.options.max_players.clamp(1, PLAYER_NAMES.len());
+        // Tournaments are duels
+        let max_players = if game_type == "1" {
+            max_players.min(2)
+        } else {
+            max_players
+        };
+        let player_count = self.random.range(
+            self.options.min_players.clamp(1, max_players) as u64,
+            max_players as u64,
+        ) as usize;
+        let mut clients: Vec<Client> = Vec::new();
+        for _ in 0..player_count {
+            self.join(writer, &mut clients, team_game)?;
+        }
+
+        let kills = self.random.range(0, u64::from(self.options.max_kills)) as u32;
+        let mut logged_kills = 0;
+        while logged_kills < kills {
+            self.time += self.random.range(0, 6) as u32;
+
+            match self.random.range(0, 99) {
+                0..=69 => {
+                    if let Some(victim) = self.kill(writer, &clients)? {
+                        clients[victim].died_at = Some(self.time);
+                        logged_kills += 1;
+                    }
+                }
+                70..=79 => {
+                    let alive = self.alive(&clients);
+                    if !alive.is_empty() {
+                        let victim = *self.random.pick(&alive);
+                        let (means, index) = *self.random.pick(&WORLD_DEATHS);
+                        let event = format!(
+                            "Kill: {WORLD_ID} {} {index}: <world> killed {} by {means}",
+                            clients[victim].id, clients[victim].name
+                        );
+                        self.line(writer, &event)?;
+                        clients[victim].died_at = Some(self.time);
+                        logged_kills += 1;
+                    }
+                }
+                80..=89 => {
+                    let client = self.random.pick(&clients);
+                    let event = format!("Item: {} {}", client.id, self.random.pick(&ITEMS));
+                    self.line(writer, &event)?;
+                }
+                90..=95 => {
+                    let client = self.random.pick(&clients);
+                    let command = if team_game && self.random.chance(30) {
+                        "sayteam"
+                    } else {
+                        "say"
+                    };
+                    let event = format!("{command}: {}: {}", client.name, self.random.pick(&CHAT));
+                    self.line(writer, &event)?;
+                }
+                96..=97 => {
+                    // Renames keep the slot, the parser records the old name as an alias
+                    let index = self.random.range(0, clients.len() as u64 - 1) as usize;
+                    let suffix = *self.random.pick(&["_", "^1!", "2", " (afk)"]);
+                    let name = format!("{}{suffix}", PLAYER_NAMES[clients[index].player]);
+                    clients[index].name = name;
+                    self.userinfo_changed(writer, &clients[index])?;
+                }
+                _ if team_game => {
+                    let index = self.random.range(0, clients.len() as u64 - 1) as usize;
+                    clients[index].team = 3 - clients[index].team;
+                    self.userinfo_changed(writer, &clients[index])?;
+                }
+                _ if clients.len() < max_players => self.join(writer, &mut clients, team_game)?,
+                _ => {}
+            }
+        }
+
+        self.time += self.random.range(1, 10) as u32;
+        let exit = if self.random.chance(50) {
+            "Fraglimit hit."
+        } else {
+            "Timelimit hit."
+        };
+        self.line(writer, &format!("Exit: {exit}"))?;
+        for client in clients.iter() {
+            let event = format!(
+                "score: {}  ping: {}  client: {} {}",
+                self.random.range(0, fraglimit),
+                self.random.range(0, 120),
+                client.id,
+                client.name
+            );
+            self.line(writer, &event)?;
+        }
+
+        self.summary.matches += 1;
+        if self.random.chance(self.options.missing_shutdown_percent) {
+            // The next InitGame has to close this match
+            return Ok(());
+        }
+
+        self.time += self.random.range(0, 5) as u32;
+        self.line(writer, "ShutdownGame:")?;
+        self.line(writer, &"-".repeat(60))
+    }
+
+    fn join<W: Write>(
+        &mut self,
+        writer: &mut W,
+        clients: &mut Vec<Client>,
+        team_game: bool,
+    ) -> io::Result<()> {
+        let free_players = (0..PLAYER_NAMES.len())
+            .filter(|player| clients.iter().all(|client| client.player != *player))
+            .collect::<Vec<usize>>();
+        if free_players.is_empty() {
+            return Ok(());
+        }
+
+        let player = *self.random.pick(&free_players);
+        let client = Client {
+            id: clients
+                .iter()
+                .map(|client| client.id + 1)
+                .max()
+                .unwrap_or(2),
+            name: PLAYER_NAMES[player].to_string(),
+            team: if team_game {
+                1 + clients.len() as u32 % 2
+            } else {
+                0
+            },
+            player,
+            died_at: None,
+        };
+
+        self.line(writer, &format!("ClientConnect: {}", client.id))?;
+        let seed = self.options.seed;
+        let event = format!(
+            "ClientUserinfo: {} \\ip\\10.{}.{}.{}:27960\\name\\{}\\rate\\25000\\cl_guid\\{:032X}",
+            client.id,
+            seed % 256,
+            player / 256,
+            player % 256 + 1,
+            client.name,
+            seed.wrapping_mul(31).wrapping_add(player as u64 + 1)
+        );
+        self.line(writer, &event)?;
+        self.userinfo_changed(writer, &client)?;
+        self.line(writer, &format!("ClientBegin: {}", client.id))?;
+
+        clients.push(client);
+        Ok(())
+    }
+
+    fn userinfo_changed<W: Write>(&mut self, writer: &mut W, client: &Client) -> io::Result<()> {
+        let event = format!(
+            "ClientUserinfoChanged: {} n\\{}\\t\\{}\\model\\sarge\\hmodel\\sarge\\g_redteam\\\\g_blueteam\\\\c1\\4\\c2\\5\\hc\\100\\w\\0\\l\\0\\tt\\0\\tl\\0",
+            client.id, client.name, client.team
+        );
+        self.line(writer, &event)
+    }
+
+    /// Indexes of the clients who didn't die during the current second.
+    fn alive(&self, clients: &[Client]) -> Vec<usize> {
+        (0..clients.len())
+            .filter(|index| clients[*index].died_at != Some(self.time))
+            .collect()
+    }
+
+    /// Writes a kill, returning the index of its victim, if anyone could die.
+    fn kill<W: Write>(&mut self, writer: &mut W, clients: &[Client]) -> io::Result<Option<usize>> {
+        let killer = self.random.range(0, clients.len() as u64 - 1) as usize;
+        let (means, index) = *self.random.pick(&WEAPONS);
+
+        // Some splash damage hits the shooter
+        let suicide = means.ends_with("_SPLASH") && self.random.chance(10);
+        let victims = self
+            .alive(clients)
+            .into_iter()
+            .filter(|victim| *victim != killer)
+            .collect::<Vec<usize>>();
+        let victim = if suicide || victims.is_empty() {
+            killer
+        } else {
+            *self.random.pick(&victims)
+        };
+        if clients[victim].died_at == Some(self.time) {
+            return Ok(None);
+        }
+
+        let event = format!(
+            "Kill: {} {} {index}: {} killed {} by {means}",
+            clients[killer].id, clients[victim].id, clients[killer].name, clients[victim].name
+        );
+        self.line(writer, &event)?;
+        Ok(Some(victim))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::parser::Parser;
+
+    fn options(seed: u64) -> GeneratorOptions {
+        GeneratorOptions {
+            seed,
+            matches: 25,
+            ..GeneratorOptions::default()
+        }
+    }
+
+    #[test]
+    fn a_seed_always_gives_the_same_log() {
+        assert_eq!(
+            LogGenerator::new(options(42)).generate_bytes(),
+            LogGenerator::new(options(42)).generate_bytes()
+        );
+        assert_ne!(
+            LogGenerator::new(options(42)).generate_bytes(),
+            LogGenerator::new(options(43)).generate_bytes()
+        );
+    }
+
+    #[test]
+    fn the_summary_tells_what_the_parser_reads() {
+        let mut log = Vec::new();
+        let summary = LogGenerator::new(options(1)).generate(&mut log).unwrap();
+        let matches = Parser::parse_bytes(&log).unwrap();
+
+        assert_eq!(summary.bytes, log.len() as u64);
+        assert_eq!(
+            summary.lines,
+            log.iter().filter(|b| **b == b'\n').count() as u64
+        );
+        assert_eq!(summary.matches, 25);
+        assert_eq!(matches.len(), summary.matches);
+    }
+
+    #[test]
+    fn sizes_are_reached() {
+        let log = LogGenerator::new(GeneratorOptions {
+            target_bytes: Some(100_000),
+            ..options(3)
+        })
+        .generate_bytes();
+
+        assert!(log.len() >= 100_000);
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+
+use crate::model::names::strip_colour_codes;
+use crate::model::player::player_names;
+use crate::store::StoredMatch;
+
+/// Name as shown and compared across matches: no colour codes, no trailing backslashes left by
+/// the log's userinfo separators, no surrounding spaces.
+pub fn clean_name(name: &str) -> String {
+    strip_colour_codes(name)
+        .trim_end_matches('\\')
+        .trim()
+        .to_string()
+}
+
+/// User-supplied aliases, read from a TOML file:
+///
+/// ```toml
+/// clan_tags = ["[LoL]", "=FK="]
+///
+/// [players]
+/// Isgalamido = ["Isga", "isgalamido_"]
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct AliasMap {
+    /// Removed from the start and end of names.
+    pub clan_tags: Vec<String>,
+    /// Every name listed is counted as the player it's listed under.
+    pub players: BTreeMap<String, Vec<String>>,
+    /// Also merges the names of clients without a GUID logged with the same IP. Off by default,
+    /// since players sharing a LAN, a NAT or a recycled dynamic IP would become one.
+    pub merge_by_ip: bool,
+}
+
+impl AliasMap {
+    /// Name a raw name is grouped under before looking at GUIDs and IPs.
+    pub fn key(&self, raw_name: &str) -> String {
+        let mut name = clean_name(raw_name);
+        for tag in self.clan_tags.iter().filter(|tag| !tag.is_empty()) {
+            if let Some(untagged) = name
+                .strip_prefix(tag.as_str())
+                .or_else(|| name.strip_suffix(tag.as_str()))
+            {
+                name = untagged.trim().to_string();
+            }
+        }
+
+        if name.is_empty() {
+            // Nothing left but colour codes or a tag
+            return raw_name.trim().to_string();
+        }
+
+        self.players
+            .iter()
+            .find(|(player, aliases)| {
+                *player == &name || aliases.iter().any(|alias| clean_name(alias) == name)
+            })
+            .map(|(player, _)| player.clone())
+            .unwrap_or(name)
+    }
+}
+
+/// Union-find over name keys, GUIDs and IPs.
+#[derive(Default)]
+struct Groups {
+    parents: HashMap<String, String>,
+}
+
+impl Groups {
+    fn find(&mut self, node: &str) -> String {
+        let mut root = node.to_string();
+        while let Some(parent) = self.parents.get(&root).filter(|parent| **parent != root) {
+            root = parent.clone();
+        }
+        self.parents.insert(node.to_string(), root.clone());
+        root
+    }
+
+    fn union(&mut self, a: &str, b: &str) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parents.insert(b, a);
+        }
+    }
+}
+
+/// Resolves the names players went by across matches to one identity each.
+///
+/// Names are first cleaned and mapped through the `AliasMap`, then names logged with the same
+/// client GUID are merged, as well as the ones logged with the same IP for clients without a GUID
+/// when `AliasMap::merge_by_ip` is set. Each identity is named after
+/// the alias map entry it matches, or else the name it played the most matches with.
+#[derive(Clone, Debug, Default)]
+pub struct IdentityResolver {
+    aliases: AliasMap,
+    by_raw_name: HashMap<String, String>,
+    by_key: HashMap<String, String>,
+}
+
+impl IdentityResolver {
+    pub fn build(stored_matches: &[StoredMatch], aliases: &AliasMap) -> IdentityResolver {
+        let mut raw_names = player_names(stored_matches);
+        for record in stored_matches
+            .iter()
+            .map(|stored_match| &stored_match.record)
+        {
+            raw_names.extend(record.client_identities.keys().cloned());
+        }
+
+        let mut groups = Groups::default();
+        let keys = raw_names
+            .iter()
+            .map(|raw_name| (raw_name.clone(), aliases.key(raw_name)))
+            .collect::<HashMap<String, String>>();
+        for key in keys.values() {
+            groups.find(&format!("name:{key}"));
+        }
+
+        let mut matches_played: HashMap<&str, usize> = HashMap::new();
+        for record in stored_matches
+            .iter()
+            .map(|stored_match| &stored_match.record)
+        {
+            for (raw_name, identity) in record.client_identities.iter() {
+                let node = match (&identity.guid, &identity.ip) {
+                    (Some(guid), _) => format!("guid:{guid}"),
+                    (None, Some(ip)) if aliases.merge_by_ip => format!("ip:{ip}"),
+                    _ => continue,
+                };
+                groups.union(&format!("name:{}", keys[raw_name]), &node);
+            }
+
+            // Names left out of the players, like `<world>`, count for nobody
+            let played = record
+                .ranking
+                .iter()
+                .filter_map(|ranking_position| keys.get(&ranking_position.player))
+                .map(String::as_str)
+                .collect::<BTreeSet<&str>>();
+            for key in played {
+                *matches_played.entry(key).or_default() += 1;
+            }
+        }
+
+        let mut members: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+        for key in keys.values() {
+            members
+                .entry(groups.find(&format!("name:{key}")))
+                .or_default()
+                .insert(key);
+        }
+
+        let mut by_key = HashMap::new();
+        for group in members.values() {
+            let listed = group.iter().find(|key| aliases.players.contains_key(**key));
+            let most_played = group.iter().max_by(|a, b| {
+                let (a_played, b_played) = (matches_played.get(**a), matches_played.get(**b));
+                // Ties go to the first name
+                a_played.cmp(&b_played).then_with(|| b.cmp(a))
+            });
+
+            if let Some(name) = listed.or(most_played) {
+                by_key.extend(group.iter().map(|key| (key.to_string(), name.to_string())));
+            }
+        }
+
+        let by_raw_name = keys
+            .into_iter()
+            .map(|(raw_name, key)| {
+                let name = by_key.get(&key).cloned().unwrap_or(key);
+                (raw_name, name)
+            })
+            .collect();
+
+        IdentityResolver {
+            aliases: aliases.clone(),
+            by_raw_name,
+            by_key,
+        }
+    }
+
+    /// Identity of a name, which can be one never seen in the matches the resolver was built from.
+    pub fn resolve(&self, raw_name: &str) -> String {
+        if raw_name == "<world>" {
+            return raw_name.to_string();
+        }
+
+        self.by_raw_name.get(raw_name).cloned().unwrap_or_else(|| {
+            let key = self.aliases.key(raw_name);
+            self.by_key.get(&key).cloned().unwrap_or(key)
+        })
+    }
+
+    /// Matches with every player renamed to their identity, for stats across matches.
+    pub fn resolve_matches(&self, stored_matches: &[StoredMatch]) -> Vec<StoredMatch> {
+        stored_matches
+            .iter()
+            .map(|stored_match| StoredMatch {
+                record: stored_match
+                    .record
+                    .with_players_renamed(|name| self.resolve(name)),
+                ..stored_match.clone()
+            })
+            .collect()
+    }
+}
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AliasMapError {
+    #[error("Cannot read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Invalid alias file {path}: {source}")]
+    Toml {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+}
+
+impl AliasMap {
+    pub fn from_file(path: &Path) -> Result<Self, AliasMapError> {
+        let content = fs::read_to_string(path).map_err(|source| AliasMapError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        toml::from_str(&content).map_err(|source| AliasMapError::Toml {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+}}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::parser::{Parser, RankingPosition};
+
+    fn stored_match(index: usize, log: &str) -> StoredMatch {
+        StoredMatch {
+            id: format!("upload-{index}"),
+            upload_id: "upload".to_string(),
+            match_key: format!("game_{index}"),
+            uploaded_at: 0,
+            record: Parser::parse_bytes(log.as_bytes())
+                .unwrap()
+                .into_values()
+                .next()
+                .unwrap(),
+        }
+    }
+
+    /// A match where `name` joins with `userinfo` and kills Zeh.
+    fn match_of(index: usize, name: &str, userinfo: &str) -> StoredMatch {
+        stored_match(
+            index,
+            &format!(
+                "  0:00 InitGame: \\sv_hostname\\Test\n  0:01 ClientUserinfo: 2 {userinfo}\\name\\{name}\n  0:01 ClientUserinfoChanged: 2 n\\{name}\\t\\0\n  0:05 Kill: 2 3 10: {name} killed Zeh by MOD_RAILGUN\n  0:09 ShutdownGame:\n"
+            ),
+        )
+    }
+
+    #[test]
+    fn build_skips_names_left_out_of_the_players() {
+        let mut stored_match = stored_match(
+            0,
+            "  0:00 InitGame: \\sv_hostname\\Test\n  0:10 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT\n  0:20 ShutdownGame:\n",
+        );
+        stored_match.record.ranking.push(RankingPosition {
+            player: "<world>".to_string(),
+            kills: 1,
+            position: 1,
+        });
+
+        let resolver = IdentityResolver::build(&[stored_match], &AliasMap::default());
+
+        assert_eq!(resolver.resolve("Isgalamido"), "Isgalamido");
+        assert_eq!(resolver.resolve("<world>"), "<world>");
+    }
+
+    #[test]
+    fn names_sharing_an_ip_stay_apart_unless_asked() {
+        let stored_matches = [
+            match_of(0, "Alice", "\\ip\\192.168.1.10:27960"),
+            match_of(1, "Bob", "\\ip\\192.168.1.10:27961"),
+            match_of(2, "Bob", "\\ip\\192.168.1.10:27961"),
+        ];
+
+        let resolver = IdentityResolver::build(&stored_matches, &AliasMap::default());
+        assert_eq!(resolver.resolve("Alice"), "Alice");
+        assert_eq!(resolver.resolve("Bob"), "Bob");
+
+        let aliases = AliasMap {
+            merge_by_ip: true,
+            ..AliasMap::default()
+        };
+        let resolver = IdentityResolver::build(&stored_matches, &aliases);
+        assert_eq!(resolver.resolve("Alice"), "Bob");
+        assert_eq!(resolver.resolve("Bob"), "Bob");
+    }
+
+    #[test]
+    fn names_sharing_a_guid_are_merged() {
+        let stored_matches = [
+            match_of(0, "Alice", "\\ip\\10.0.0.1:27960\\cl_guid\\4F2A"),
+            match_of(1, "Alicia", "\\ip\\10.0.0.2:27960\\cl_guid\\4F2A"),
+            match_of(2, "Alicia", "\\ip\\10.0.0.2:27960\\cl_guid\\4F2A"),
+        ];
+
+        let resolver = IdentityResolver::build(&stored_matches, &AliasMap::default());
+
+        assert_eq!(resolver.resolve("Alice"), "Alicia");
+        assert_eq!(resolver.resolve("Zeh"), "Zeh");
+    }
+}
@@ -1 +1,15 @@
+pub mod anonymise;
+pub mod awards;
+pub mod comparison;
+pub mod dialect;
+pub mod export;
+pub mod generator;
+pub mod identity;
+pub mod names;
+pub mod parallel;
 pub mod parser;
+pub mod player;
+pub mod rating;
+pub mod replay;
+pub mod timeline;
+pub mod validator;
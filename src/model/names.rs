@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+
+/// Quake 3 colour table, indexed by `colour_index`.
+pub const PALETTE: [&str; 8] = [
+    "#000000", "#ff0000", "#00ff00", "#ffff00", "#0000ff", "#00ffff", "#ff00ff", "#ffffff",
+];
+
+/// Palette index of the character following a `^`, using the game's own rule: the low three
+/// bits of its distance to `0`, so `^8` is black again and letters pick a colour too.
+pub fn colour_index(code: char) -> usize {
+    (code as u32).wrapping_sub('0' as u32) as usize & 7
+}
+
+/// Run of text printed in the same colour, `colour` being `None` until the first code.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ColourSegment {
+    pub colour: Option<usize>,
+    pub text: String,
+}
+
+/// Splits a name or chat message on its colour codes, `^` followed by any character but another
+/// `^`. Codes without any text after them are dropped.
+pub fn colour_segments(text: &str) -> Vec<ColourSegment> {
+    let mut segments = Vec::new();
+    let mut current = ColourSegment {
+        colour: None,
+        text: String::new(),
+    };
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match chars.peek().filter(|_| c == '^').copied() {
+            Some(code) if code != '^' => {
+                chars.next();
+                let colour = Some(colour_index(code));
+                if current.text.is_empty() {
+                    current.colour = colour;
+                } else {
+                    segments.push(std::mem::replace(
+                        &mut current,
+                        ColourSegment {
+                            colour,
+                            text: String::new(),
+                        },
+                    ));
+                }
+            }
+            _ => current.text.push(c),
+        }
+    }
+
+    if !current.text.is_empty() {
+        segments.push(current);
+    }
+    segments
+}
+
+/// Plain text form without the colour codes, used to sort, search and export names.
+pub fn strip_colour_codes(text: &str) -> String {
+    colour_segments(text)
+        .into_iter()
+        .map(|segment| segment.text)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(text: &str) -> Vec<(Option<usize>, String)> {
+        colour_segments(text)
+            .into_iter()
+            .map(|segment| (segment.colour, segment.text))
+            .collect()
+    }
+
+    #[test]
+    fn colour_index_follows_the_game() {
+        assert_eq!(colour_index('0'), 0);
+        assert_eq!(colour_index('1'), 1);
+        assert_eq!(colour_index('7'), 7);
+        assert_eq!(colour_index('8'), 0);
+        assert_eq!(colour_index('9'), 1);
+        assert_eq!(colour_index('a'), 1);
+        assert_eq!(colour_index('#'), 3);
+    }
+
+    #[test]
+    fn splits_names_on_their_colour_codes() {
+        assert_eq!(
+            segments("^1Isga^7lamido"),
+            [
+                (Some(1), "Isga".to_string()),
+                (Some(7), "lamido".to_string())
+            ]
+        );
+        assert_eq!(
+            segments("Dono ^4da Bola"),
+            [
+                (None, "Dono ".to_string()),
+                (Some(4), "da Bola".to_string())
+            ]
+        );
+        assert_eq!(segments("Zeh"), [(None, "Zeh".to_string())]);
+    }
+
+    #[test]
+    fn codes_without_text_are_dropped() {
+        assert_eq!(segments("^1^2Zeh"), [(Some(2), "Zeh".to_string())]);
+        assert_eq!(segments("Zeh^3"), [(None, "Zeh".to_string())]);
+        assert_eq!(segments("^1"), []);
+        assert_eq!(segments(""), []);
+    }
+
+    #[test]
+    fn a_caret_before_a_caret_or_at_the_end_is_text() {
+        assert_eq!(
+            segments("^^1Zeh"),
+            [(None, "^".to_string()), (Some(1), "Zeh".to_string())]
+        );
+        assert_eq!(segments("Zeh^"), [(None, "Zeh^".to_string())]);
+    }
+
+    #[test]
+    fn strips_colour_codes() {
+        assert_eq!(strip_colour_codes("^1Is^2ga^7lamido"), "Isgalamido");
+        assert_eq!(strip_colour_codes("^^2Mocinha^"), "^Mocinha^");
+        assert_eq!(strip_colour_codes("Zeh"), "Zeh");
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::num::NonZeroUsize;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use crate::model::parser::{
+    MatchRecord, ParseError, ParsedSegment, Parser, ParserOptions, ParserState,
+};
+
+/// Bytes of log each thread is handed at a time, stretched to the next match boundary.
+pub const SEGMENT_BYTES: usize = 8 * 1024 * 1024;
+
+/// Parses large logs on several threads.
+///
+/// The log is cut in segments on the `InitGame` lines starting its matches, each segment is parsed
+/// on its own and the matches are put back in the order of the log, with the same keys, times and
+/// line numbers a sequential parse gives them. Files are read a segment at a time, so only the
+/// segments being parsed are held in memory besides the matches.
+#[derive(Clone, Copy, Debug)]
+pub struct ParallelParser {
+    options: ParserOptions,
+    threads: usize,
+    segment_bytes: usize,
+}
+
+/// Segments read from a file, waiting for a worker, with their index in the file.
+type SegmentReceiver = Arc<Mutex<mpsc::Receiver<(usize, Segment<Vec<u8>>)>>>;
+
+/// Slice of a log starting on a match boundary, along with the times around it.
+struct Segment<B> {
+    bytes: B,
+    last_time: Option<u32>,
+    next_time: Option<u32>,
+}
+
+impl<B: AsRef<[u8]>> Segment<B> {
+    fn parse(&self, options: ParserOptions) -> ParsedSegment {
+        Parser::parse_segment(self.bytes.as_ref(), options, self.last_time, self.next_time)
+    }
+}
+
+impl ParallelParser {
+    /// Uses as many threads as the machine runs in parallel.
+    pub fn new(options: ParserOptions) -> Self {
+        ParallelParser {
+            options,
+            threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            segment_bytes: SEGMENT_BYTES,
+        }
+    }
+
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn with_segment_bytes(mut self, segment_bytes: usize) -> Self {
+        self.segment_bytes = segment_bytes.max(1);
+        self
+    }
+
+    pub fn parse(&self, file_path: String) -> Result<HashMap<String, MatchRecord>, ParseError> {
+        let mut file = File::open(&file_path)?;
+        if self.threads == 1 || file.metadata()?.len() <= self.segment_bytes as u64 {
+            return Parser::parse_with_options(file_path, self.options);
+        }
+
+        let (sender, receiver) = mpsc::sync_channel(self.threads);
+        // Shared by the workers alone, so sending fails rather than blocks once they are all gone
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let (read, segments) = thread::scope(|scope| {
+            let workers = (0..self.threads)
+                .map(|_| {
+                    let receiver = Arc::clone(&receiver);
+                    scope.spawn(move || self.parse_received(receiver))
+                })
+                .collect::<Vec<_>>();
+            drop(receiver);
+
+            // Dropping the sender once the file is read lets the workers stop
+            let read = self.read_segments(&mut file, |index, segment| {
+                sender.send((index, segment)).is_ok()
+            });
+            drop(sender);
+
+            (read, ParallelParser::join(workers))
+        });
+        read?;
+
+        Parser::merge_segments(segments)
+    }
+
+    /// Parses a whole log already loaded in memory.
+    pub fn parse_bytes(&self, bytes: &[u8]) -> Result<HashMap<String, MatchRecord>, ParseError> {
+        if self.threads == 1 || bytes.len() <= self.segment_bytes {
+            return Parser::parse_bytes_with_options(bytes, self.options);
+        }
+
+        let mut segments = Vec::new();
+        let mut start = 0;
+        let mut last_time = None;
+        while let Some(cut) = next_match_start(bytes, start + self.segment_bytes) {
+            let (segment_last_time, next_time) =
+                times_around(&bytes[start..], cut - start, &mut last_time);
+            segments.push(Segment {
+                bytes: &bytes[start..cut],
+                last_time: segment_last_time,
+                next_time,
+            });
+            start = cut;
+        }
+        segments.push(Segment {
+            bytes: &bytes[start..],
+            last_time,
+            next_time: None,
+        });
+
+        let next_segment = Mutex::new(segments.iter().enumerate());
+        let parsed = thread::scope(|scope| {
+            let workers = (0..self.threads.min(segments.len()))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut parsed = Vec::new();
+                        while let Some((index, segment)) = next_segment
+                            .lock()
+                            .ok()
+                            .and_then(|mut segments| segments.next())
+                        {
+                            parsed.push((index, segment.parse(self.options)));
+                        }
+                        parsed
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            ParallelParser::join(workers)
+        });
+
+        Parser::merge_segments(parsed)
+    }
+
+    /// Hands `send` the segments of `file` in order, until it returns false or the file ends.
+    fn read_segments<F>(&self, file: &mut File, mut send: F) -> Result<(), ParseError>
+    where
+        F: FnMut(usize, Segment<Vec<u8>>) -> bool,
+    {
+        let mut buffer = Vec::with_capacity(self.segment_bytes * 2);
+        let mut searched = self.segment_bytes;
+        let mut last_time = None;
+        let mut index = 0;
+
+        loop {
+            let read = file
+                .by_ref()
+                .take(self.segment_bytes as u64)
+                .read_to_end(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            if buffer.len() < searched {
+                continue;
+            }
+
+            // A match longer than a segment keeps the buffer growing until it ends
+            let cut = match next_match_start(&buffer, searched) {
+                Some(cut) => cut,
+                None => {
+                    searched = searched.max(
+                        buffer
+                            .iter()
+                            .rposition(|b| *b == b'\n')
+                            .map_or(0, |end| end + 1),
+                    );
+                    continue;
+                }
+            };
+
+            let (segment_last_time, next_time) = times_around(&buffer, cut, &mut last_time);
+            let rest = buffer.split_off(cut);
+            let segment = Segment {
+                bytes: buffer,
+                last_time: segment_last_time,
+                next_time,
+            };
+            if !send(index, segment) {
+                return Ok(());
+            }
+            index += 1;
+            buffer = rest;
+            buffer.reserve(self.segment_bytes);
+            searched = self.segment_bytes;
+        }
+
+        if !buffer.is_empty() {
+            send(
+                index,
+                Segment {
+                    bytes: buffer,
+                    last_time,
+                    next_time: None,
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    fn parse_received(&self, receiver: SegmentReceiver) -> Vec<(usize, ParsedSegment)> {
+        let mut parsed = Vec::new();
+
+        // Holding the lock while waiting is fine, the other workers would be waiting too
+        while let Some((index, segment)) = receiver
+            .lock()
+            .ok()
+            .and_then(|receiver| receiver.recv().ok())
+        {
+            parsed.push((index, segment.parse(self.options)));
+        }
+
+        parsed
+    }
+
+    /// Segments parsed by every worker, in the order of the log.
+    fn join(
+        workers: Vec<thread::ScopedJoinHandle<Vec<(usize, ParsedSegment)>>>,
+    ) -> Vec<ParsedSegment> {
+        let mut parsed = workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect::<Vec<(usize, ParsedSegment)>>();
+        parsed.sort_by_key(|(index, _)| *index);
+
+        parsed.into_iter().map(|(_, segment)| segment).collect()
+    }
+}
+
+/// Times around `cut`, the start of a match in `bytes`: the one logged last before the segment
+/// ending there and the one of its `InitGame` line. `last_time` is the time logged last before
+/// `bytes` and moves on to the last one before `cut`.
+fn times_around(
+    bytes: &[u8],
+    cut: usize,
+    last_time: &mut Option<u32>,
+) -> (Option<u32>, Option<u32>) {
+    let (before, after) = bytes.split_at(cut);
+    let segment_last_time = *last_time;
+    let next_time = after
+        .split(|b| *b == b'\n')
+        .next()
+        .and_then(|raw_line| read_line(raw_line).0);
+
+    if let Some(time) = before
+        .rsplit(|b| *b == b'\n')
+        .find_map(|raw_line| read_line(raw_line).0)
+    {
+        *last_time = Some(time);
+    }
+
+    (segment_last_time, next_time)
+}
+
+/// Start of the first complete line starting a match at or after `from`, the first line of
+/// `bytes` left out.
+fn next_match_start(bytes: &[u8], from: usize) -> Option<usize> {
+    let mut line_start = from.max(1);
+    line_start += bytes
+        .get(line_start - 1..)?
+        .iter()
+        .position(|b| *b == b'\n')?;
+
+    while let Some(line_end) = bytes[line_start..].iter().position(|b| *b == b'\n') {
+        if read_line(&bytes[line_start..line_start + line_end]).1 {
+            return Some(line_start);
+        }
+        line_start += line_end + 1;
+    }
+
+    None
+}
+
+/// Game time of a line and whether it starts a match, read the way the parser reads them.
+fn read_line(raw_line: &[u8]) -> (Option<u32>, bool) {
+    let line = String::from_utf8_lossy(raw_line);
+    let (time, event) = ParserState::split_time(line.trim());
+
+    (time, event.starts_with("InitGame:"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::model::generator::{GeneratorOptions, LogGenerator};
+    use crate::model::parser::comparable_matches;
+
+    const SEGMENT_BYTES: usize = 2048;
+
+    fn parallel_parser() -> ParallelParser {
+        ParallelParser::new(ParserOptions::default())
+            .with_threads(3)
+            .with_segment_bytes(SEGMENT_BYTES)
+    }
+
+    /// Generated log with matches missing their `ShutdownGame` line and a malformed kill line
+    /// every 50 lines, logged at the time of the line before it.
+    fn generated_log() -> Vec<u8> {
+        let log = LogGenerator::new(GeneratorOptions {
+            seed: 11,
+            matches: 40,
+            missing_shutdown_percent: 30,
+            ..GeneratorOptions::default()
+        })
+        .generate_bytes();
+
+        let mut with_malformed_lines = Vec::new();
+        for (index, line) in log.split_inclusive(|b| *b == b'\n').enumerate() {
+            with_malformed_lines.extend_from_slice(line);
+            if index % 50 == 49 {
+                with_malformed_lines.extend_from_slice(&line[..7]);
+                with_malformed_lines.extend_from_slice(b"Kill: 3 2\n");
+            }
+        }
+        with_malformed_lines
+    }
+
+    fn count(log: &[u8], event: &str) -> usize {
+        String::from_utf8_lossy(log).matches(event).count()
+    }
+
+    #[test]
+    fn parses_like_the_sequential_parser() {
+        let log = generated_log();
+        assert!(log.len() > 20 * SEGMENT_BYTES);
+        assert!(count(&log, "ShutdownGame:") < count(&log, "InitGame:"));
+        let expected = Parser::parse_bytes(&log).unwrap();
+
+        assert_eq!(expected.len(), 40);
+        assert_eq!(
+            comparable_matches(&parallel_parser().parse_bytes(&log).unwrap()),
+            comparable_matches(&expected)
+        );
+
+        let path = std::env::temp_dir().join(format!("q3a-parallel-{}.log", std::process::id()));
+        fs::write(&path, &log).unwrap();
+        let parsed = parallel_parser().parse(path.to_string_lossy().into_owned());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            comparable_matches(&parsed.unwrap()),
+            comparable_matches(&expected)
+        );
+    }
+
+    #[test]
+    fn malformed_lines_keep_their_number() {
+        let separator = "  0:01 ------------------------------------------------------------\n";
+        let mut log = separator.repeat(200).into_bytes();
+        log.extend_from_slice(b"  0:02 Kill: 3 2\n");
+
+        let expected = match Parser::parse_bytes(&log) {
+            Err(ParseError::MalformedLine { line, .. }) => line,
+            _ => panic!("the log has no match and a malformed line"),
+        };
+        assert_eq!(expected, 201);
+        match parallel_parser().parse_bytes(&log) {
+            Err(ParseError::MalformedLine { line, .. }) => assert_eq!(line, expected),
+            _ => panic!("the log has no match and a malformed line"),
+        }
+
+        // Segments count their lines from 1, merging them counts on from the earlier ones
+        let (first, second) = log.split_at(100 * separator.len());
+        let segments = vec![
+            Parser::parse_segment(first, ParserOptions::default(), None, None),
+            Parser::parse_segment(second, ParserOptions::default(), Some(1), None),
+        ];
+        match Parser::merge_segments(segments) {
+            Err(ParseError::MalformedLine { line, .. }) => assert_eq!(line, expected),
+            _ => panic!("the log has no match and a malformed line"),
+        }
+    }
+}
@@ -1,8 +1,12 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
 
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::model::awards::{Award, AwardEngine};
+use crate::model::dialect::{Dialect, GameEvent};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct RankingPosition {
@@ -11,136 +15,996 @@ pub struct RankingPosition {
     pub position: u16,
 }
 
+/// Match settings taken from the `InitGame` line, times in seconds since the server started.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MatchMetadata {
+    pub map: Option<String>,
+    pub game_type: Option<String>,
+    pub server_name: Option<String>,
+    pub start_time: Option<u32>,
+    pub end_time: Option<u32>,
+    pub cvars: BTreeMap<String, String>,
+    /// Game that wrote the log, detected from the cvars unless set in the parser options.
+    #[serde(default)]
+    pub dialect: Dialect,
+}
+
+impl MatchMetadata {
+    pub fn game_type_name(&self) -> Option<&'static str> {
+        self.dialect
+            .rules()
+            .game_type_name(self.game_type.as_deref()?)
+    }
+
+    pub fn duration(&self) -> Option<u32> {
+        Some(self.end_time?.saturating_sub(self.start_time?))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct KillEvent {
+    pub time: u32,
+    /// Line of the kill counted from the `InitGame` of its match, ordering events of one second.
+    #[serde(default)]
+    pub line: u32,
+    pub killer: String,
+    pub killed: String,
+    pub means: String,
+}
+
+impl KillEvent {
+    /// Player whose score the kill changed and by how much: a kill by `<world>` costs the victim
+    /// one point and suicides don't count.
+    pub fn score_change(&self) -> Option<(&str, i16)> {
+        if self.killer == "<world>" {
+            Some((&self.killed, -1))
+        } else if self.killer != self.killed {
+            Some((&self.killer, 1))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChatMessage {
+    pub time: u32,
+    /// Line of the message counted from the `InitGame` of its match.
+    #[serde(default)]
+    pub line: u32,
+    pub player: String,
+    pub message: String,
+    pub team_only: bool,
+}
+
+/// What identifies a client beyond its name, when the server logs it.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ClientIdentity {
+    pub guid: Option<String>,
+    /// Without the port.
+    pub ip: Option<String>,
+}
+
+impl ClientIdentity {
+    /// Takes the values found in a userinfo string, keeping the known ones it lacks.
+    fn update(&mut self, userinfo: &str) {
+        let guid = ["cl_guid", "guid", "id"]
+            .iter()
+            .find_map(|key| ParserState::userinfo_value(userinfo, key));
+        if let Some(guid) = guid {
+            self.guid = Some(guid.to_string());
+        }
+
+        let ip = ParserState::userinfo_value(userinfo, "ip")
+            .map(|address| address.rsplit_once(':').map_or(address, |(ip, _)| ip))
+            .filter(|ip| *ip != "bot" && *ip != "localhost");
+        if let Some(ip) = ip {
+            self.ip = Some(ip.to_string());
+        }
+    }
+
+    fn is_known(&self) -> bool {
+        self.guid.is_some() || self.ip.is_some()
+    }
+}
+
+/// Notable moments of a match.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Highlight {
+    /// First frag of the match, `<world>` and suicides left out.
+    FirstBlood {
+        time: u32,
+        killer: String,
+        victim: String,
+    },
+    /// `kills` frags within the multi-kill window, `time` being the first of them.
+    MultiKill {
+        time: u32,
+        player: String,
+        kills: u16,
+    },
+    /// A streak of at least `STREAK_HIGHLIGHT_MIN` frags ended by the player's death.
+    StreakEnded {
+        time: u32,
+        player: String,
+        streak: u16,
+        ended_by: String,
+    },
+}
+
+/// Streaks shorter than this don't make a highlight when they end.
+pub const STREAK_HIGHLIGHT_MIN: u16 = 3;
+
+/// Best of each player in a match.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct MatchAwards {
+    pub first_blood: Option<String>,
+    /// Most frags each player made without dying.
+    pub longest_streaks: BTreeMap<String, u16>,
+    /// Most frags each player chained within the multi-kill window, players without any left out.
+    pub best_multi_kills: BTreeMap<String, u16>,
+    /// Given by the award rules once the match ended.
+    #[serde(default)]
+    pub titles: Vec<Award>,
+}
+
+impl MatchAwards {
+    /// Longest streak of the match, ties going to the first name.
+    pub fn top_streak(&self) -> Option<(&str, u16)> {
+        MatchAwards::top(&self.longest_streaks)
+    }
+
+    pub fn top_multi_kill(&self) -> Option<(&str, u16)> {
+        MatchAwards::top(&self.best_multi_kills)
+    }
+
+    fn top(counts: &BTreeMap<String, u16>) -> Option<(&str, u16)> {
+        counts.iter().filter(|(_, count)| **count > 0).fold(
+            None,
+            |top: Option<(&str, u16)>, (player, count)| match top {
+                Some((_, top_count)) if top_count >= *count => top,
+                _ => Some((player.as_str(), *count)),
+            },
+        )
+    }
+}
+
+/// Frags a player is chaining into a multi-kill.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct MultiKillChain {
+    start_time: u32,
+    kills: u16,
+    /// Index of its highlight, once it made one.
+    highlight: Option<usize>,
+}
+
+/// Settings changing what the parser derives from the log.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct ParserOptions {
+    /// Frags at most this many seconds after the first one of a chain make a multi-kill.
+    pub multi_kill_window_secs: u32,
+    /// Game the logs come from, `None` detecting it for each match.
+    pub dialect: Option<Dialect>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            multi_kill_window_secs: 3,
+            dialect: None,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct MatchRecord {
+    /// Set once the match is stored by the server, so it can be linked to.
+    #[serde(default)]
+    pub id: Option<String>,
     pub total_kills: i16,
     players: HashSet<String>,
     kills: HashMap<String, i16>,
     pub kills_by_means: HashMap<String, i16>,
     pub ranking: Vec<RankingPosition>,
+    #[serde(default)]
+    pub metadata: MatchMetadata,
+    /// Kills in the order they happened.
+    #[serde(default)]
+    pub kill_events: Vec<KillEvent>,
+    #[serde(default)]
+    pub chat: Vec<ChatMessage>,
+    /// Other names each player went by in this match, taken from clients renaming themselves.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, BTreeSet<String>>,
+    /// Name currently used by each client slot.
+    #[serde(default)]
+    client_names: HashMap<String, String>,
+    /// GUID and IP each player connected with, for the logs that have them.
+    #[serde(default)]
+    pub client_identities: BTreeMap<String, ClientIdentity>,
+    /// Identity of each client slot, which can be logged before its name.
+    #[serde(default)]
+    slot_identities: HashMap<String, ClientIdentity>,
+    /// Team each player was last on, free-for-all players left out.
+    #[serde(default)]
+    pub teams: BTreeMap<String, String>,
+    /// Flag, bomb, hit and other events of the games logging them, in the order they happened.
+    #[serde(default)]
+    pub game_events: Vec<GameEvent>,
+    /// In the order they happened.
+    #[serde(default)]
+    pub highlights: Vec<Highlight>,
+    #[serde(default)]
+    pub awards: MatchAwards,
+    /// Frags each player made since they last died.
+    #[serde(default)]
+    current_streaks: HashMap<String, u16>,
+    #[serde(default)]
+    multi_kill_chains: HashMap<String, MultiKillChain>,
 }
 
-pub struct Parser {}
+/// Matches as JSON with their players sorted, so parses can be compared whatever order the sets
+/// iterate in.
+#[cfg(test)]
+pub(crate) fn comparable_matches(matches: &HashMap<String, MatchRecord>) -> serde_json::Value {
+    let mut value = serde_json::to_value(matches).unwrap();
+    for record in value.as_object_mut().unwrap().values_mut() {
+        if let Some(serde_json::Value::Array(players)) = record.get_mut("players") {
+            players.sort_by_key(|player| player.to_string());
+        }
+    }
 
-impl Parser {
-    pub fn parse(file_path: String) -> HashMap<String, MatchRecord> {
-        let file_buffer = Parser::load_file(file_path.to_string());
-
-        let mut matches: HashMap<String, MatchRecord> = HashMap::new();
-        let mut current_match = String::new();
-
-        for raw_line in file_buffer.lines() {
-            let line = match raw_line {
-                Ok(line) => line.trim().to_string(),
-                Err(e) => {
-                    println!("Error: {}", e);
-                    continue;
+    value
+}
+
+/// Formats seconds as the `m:ss` game time used by the logs.
+pub fn format_game_time(seconds: u32) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Notable events observed while feeding lines to the parser.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum ParserEvent {
+    MatchStarted {
+        match_id: String,
+    },
+    /// Only handed over by states made `with_kill_events`.
+    Kill {
+        match_id: String,
+        killer: String,
+        killed: String,
+        means: String,
+    },
+    MatchEnded {
+        match_id: String,
+        record: Box<MatchRecord>,
+    },
+    /// A recognised event line whose content couldn't be understood, `line` counting from 1.
+    MalformedLine {
+        line: u64,
+        content: String,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum ParseError {
+    #[error("Cannot read the log: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("No match found in the log")]
+    NoMatches,
+    #[error("Malformed line {line}: {content}")]
+    MalformedLine { line: u64, content: String },
+}
+
+impl MatchRecord {
+    /// Amount of times each killer fragged each victim, `<world>` included as a killer.
+    pub fn kill_matrix(&self) -> BTreeMap<String, BTreeMap<String, u16>> {
+        let mut matrix: BTreeMap<String, BTreeMap<String, u16>> = BTreeMap::new();
+
+        for kill in self.kill_events.iter() {
+            *matrix
+                .entry(kill.killer.clone())
+                .or_default()
+                .entry(kill.killed.clone())
+                .or_default() += 1;
+        }
+
+        matrix
+    }
+
+    /// Kills made with each means of death by each player, suicides and `<world>` left out.
+    pub fn weapon_breakdown(&self) -> BTreeMap<String, BTreeMap<String, u16>> {
+        let mut breakdown: BTreeMap<String, BTreeMap<String, u16>> = BTreeMap::new();
+
+        for kill in self
+            .kill_events
+            .iter()
+            .filter(|kill| kill.killer != "<world>" && kill.killer != kill.killed)
+        {
+            *breakdown
+                .entry(kill.killer.clone())
+                .or_default()
+                .entry(kill.means.clone())
+                .or_default() += 1;
+        }
+
+        breakdown
+    }
+
+    /// Game events credited to each player, counted by kind.
+    pub fn game_event_counts(&self) -> BTreeMap<String, BTreeMap<&'static str, u16>> {
+        let mut counts: BTreeMap<String, BTreeMap<&'static str, u16>> = BTreeMap::new();
+
+        for event in self.game_events.iter() {
+            *counts
+                .entry(event.player().to_string())
+                .or_default()
+                .entry(event.label())
+                .or_default() += 1;
+        }
+
+        counts
+    }
+
+    /// Copy of the record with every player renamed, players getting the same new name merged.
+    /// Renamed players keep their logged names as aliases, highlights and awards are left as is.
+    pub fn with_players_renamed<F>(&self, rename: F) -> MatchRecord
+    where
+        F: Fn(&str) -> String,
+    {
+        let mut record = self.clone();
+
+        record.players = self.players.iter().map(|player| rename(player)).collect();
+        record.kills = HashMap::new();
+        for (player, kills) in self.kills.iter() {
+            *record.kills.entry(rename(player)).or_default() += kills;
+        }
+        for kill in record.kill_events.iter_mut() {
+            kill.killer = rename(&kill.killer);
+            kill.killed = rename(&kill.killed);
+        }
+        for message in record.chat.iter_mut() {
+            message.player = rename(&message.player);
+        }
+
+        record.aliases = BTreeMap::new();
+        for (player, aliases) in self.aliases.iter() {
+            record
+                .aliases
+                .entry(rename(player))
+                .or_default()
+                .extend(aliases.iter().cloned());
+        }
+        for player in self.players.iter() {
+            let renamed = rename(player);
+            if &renamed != player {
+                record
+                    .aliases
+                    .entry(renamed)
+                    .or_default()
+                    .insert(player.clone());
+            }
+        }
+        for (player, aliases) in record.aliases.iter_mut() {
+            aliases.remove(player);
+        }
+        record.aliases.retain(|_, aliases| !aliases.is_empty());
+
+        record.client_identities = self
+            .client_identities
+            .iter()
+            .map(|(player, identity)| (rename(player), identity.clone()))
+            .collect();
+        record.teams = self
+            .teams
+            .iter()
+            .map(|(player, team)| (rename(player), team.clone()))
+            .collect();
+        for event in record.game_events.iter_mut() {
+            event.rename_players(&rename);
+        }
+        record.ranking = record.standings();
+
+        record
+    }
+
+    /// Ranking built from the kill scores registered so far, ties ordered by player name.
+    pub fn standings(&self) -> Vec<RankingPosition> {
+        let mut raw_ranking = self.kills.iter().collect::<Vec<(&String, &i16)>>();
+        raw_ranking.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+        raw_ranking
+            .iter()
+            .enumerate()
+            .map(|(i, (player, kills))| RankingPosition {
+                player: player.to_string(),
+                kills: **kills,
+                position: (i + 1) as u16,
+            })
+            .collect::<Vec<RankingPosition>>()
+    }
+}
+
+/// Incremental parser that can be fed a log one line or chunk at a time.
+///
+/// Only the match being played is kept, completed matches are handed back through
+/// `ParserEvent::MatchEnded`. The state is serialisable, so it can be stored alongside
+/// `offset()` and used to resume parsing the same file after a restart.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ParserState {
+    started_matches: usize,
+    current_match: Option<(String, MatchRecord)>,
+    offset: u64,
+    lines: u64,
+    /// Lines since the current match started, unlike `lines` the same whichever segment or
+    /// rewound file they came from.
+    #[serde(default)]
+    match_lines: u32,
+    last_time: Option<u32>,
+    pending: Vec<u8>,
+    #[serde(default)]
+    options: ParserOptions,
+    /// Whether each kill is handed over as a `ParserEvent::Kill`, which only live followers need.
+    #[serde(default)]
+    kill_events: bool,
+    /// Rules aren't serialisable, a resumed state goes back to the built-in ones.
+    #[serde(skip)]
+    award_engine: AwardEngine,
+}
+
+impl ParserState {
+    pub fn new() -> Self {
+        ParserState::default()
+    }
+
+    pub fn with_options(options: ParserOptions) -> Self {
+        ParserState {
+            options,
+            ..ParserState::default()
+        }
+    }
+
+    /// Hands every kill over as a `ParserEvent::Kill` as well, not only the match events.
+    pub fn with_kill_events(mut self) -> Self {
+        self.kill_events = true;
+        self
+    }
+
+    /// Replaces the rules giving awards to the matches completed from now on.
+    pub fn with_award_engine(mut self, award_engine: AwardEngine) -> Self {
+        self.award_engine = award_engine;
+        self
+    }
+
+    /// Bytes received through `push_chunk`, an incomplete last line included, so reading on
+    /// from there never hands a byte over twice.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Match currently being played, if any.
+    pub fn current_match(&self) -> Option<(&String, &MatchRecord)> {
+        self.current_match
+            .as_ref()
+            .map(|(match_id, match_record)| (match_id, match_record))
+    }
+
+    /// Forgets any incomplete line and starts counting bytes and lines from zero again, keeping the
+    /// match in progress. Used when the underlying file was truncated or rotated.
+    pub fn rewind(&mut self) {
+        self.offset = 0;
+        self.lines = 0;
+        self.pending.clear();
+    }
+
+    /// Like `rewind`, but goes on from `offset` of the file, for when its earlier lines are not
+    /// meant to be parsed.
+    pub fn skip_to(&mut self, offset: u64) {
+        self.rewind();
+        self.offset = offset;
+    }
+
+    /// Feeds raw bytes, which may end in the middle of a line. The incomplete line is kept
+    /// until the rest of it arrives.
+    pub fn push_chunk(&mut self, chunk: &[u8]) -> Vec<ParserEvent> {
+        let mut events = Vec::new();
+        self.push_chunk_with(chunk, |_, event| events.push(event));
+
+        events
+    }
+
+    /// Same as `push_chunk`, but hands each event over as soon as its line is parsed, along
+    /// with the state right after that line.
+    pub fn push_chunk_with<F>(&mut self, chunk: &[u8], mut on_event: F)
+    where
+        F: FnMut(&ParserState, ParserEvent),
+    {
+        let mut consumed = 0;
+        self.offset += chunk.len() as u64;
+
+        // Only a line split across chunks is copied, the others are read in place
+        if !self.pending.is_empty() {
+            let line_end = match chunk.iter().position(|b| *b == b'\n') {
+                Some(line_end) => line_end,
+                None => {
+                    self.pending.extend_from_slice(chunk);
+                    return;
                 }
             };
 
-            Parser::parse_line(line, &mut matches, &mut current_match)
+            let mut raw_line = std::mem::take(&mut self.pending);
+            raw_line.extend_from_slice(&chunk[..line_end]);
+            consumed = line_end + 1;
+            self.push_raw_line(&raw_line, &mut on_event);
+        }
+
+        while let Some(line_end) = chunk[consumed..].iter().position(|b| *b == b'\n') {
+            let raw_line = &chunk[consumed..consumed + line_end];
+            consumed += line_end + 1;
+            self.push_raw_line(raw_line, &mut on_event);
         }
 
-        matches
+        self.pending.extend_from_slice(&chunk[consumed..]);
     }
 
-    fn load_file(file_path: String) -> BufReader<File> {
-        let reader = BufReader::new(File::open(file_path).expect("Cannot open {file_path}"));
+    fn push_raw_line<F>(&mut self, raw_line: &[u8], on_event: &mut F)
+    where
+        F: FnMut(&ParserState, ParserEvent),
+    {
+        let line = String::from_utf8_lossy(raw_line);
 
-        reader
+        for event in self.push_line(&line) {
+            on_event(self, event);
+        }
     }
 
-    fn parse_line(
-        line: String,
-        matches: &mut HashMap<String, MatchRecord>,
-        current_match: &mut String,
-    ) {
-        let new_match_started = line.contains("InitGame: ");
-        let user_joined = line.contains("ClientUserinfoChanged: ");
-        let user_got_killed = line.contains("Kill: ");
+    /// Feeds a single complete line.
+    pub fn push_line(&mut self, line: &str) -> Vec<ParserEvent> {
+        let line = line.trim();
+        self.lines += 1;
+        self.match_lines = self.match_lines.saturating_add(1);
+        let mut events = Vec::new();
 
-        let match_has_ended = line.contains("ShutdownGame: ");
-        let match_break =
-            line.contains("------------------------------------------------------------");
+        let (time, event) = ParserState::split_time(line);
+        if time.is_some() {
+            self.last_time = time;
+        }
 
-        let ranking_is_empty = match matches.get(current_match) {
-            Some(match_record) => match_record.ranking.is_empty(),
-            None => false,
-        };
+        let new_match_started = event.starts_with("InitGame:");
+        let user_joined = event.starts_with("ClientUserinfoChanged: ");
+        let user_info = event.starts_with("ClientUserinfo: ");
+        let user_got_killed = event.starts_with("Kill: ");
+        let user_said = event.starts_with("say: ") || event.starts_with("sayteam: ");
+
+        let match_has_ended = event.starts_with("ShutdownGame:");
+        let match_break =
+            event.starts_with("------------------------------------------------------------");
 
         if new_match_started {
-            Parser::handle_match_start(matches, current_match);
+            // A match interrupted without any closing line still has to be reported
+            events.extend(self.close_current_match());
+            events.push(self.handle_match_start(event));
+        } else if user_said {
+            self.handle_chat_message(event);
         } else if user_joined {
-            Parser::handle_user_joined_match(line, matches, &current_match);
+            events.extend(self.handle_user_joined_match(line));
+        } else if user_info {
+            self.handle_client_userinfo(event);
         } else if user_got_killed {
-            Parser::handle_user_kills(line, matches, &current_match);
+            events.extend(self.handle_user_kills(line));
 
         // This validation is necessary because a match doesn't have a ShutdownGame event, although it has all the others events
-        } else if match_has_ended || (match_break && !current_match.is_empty() && ranking_is_empty)
-        {
-            Parser::generate_ranking(matches, &current_match)
+        } else if match_has_ended || match_break {
+            events.extend(self.close_current_match());
+        } else {
+            self.handle_game_event(event);
+        }
+
+        events
+    }
+
+    /// Parses the trailing line left without a line break and closes the match in progress.
+    /// Called at the end of the input, since the last match of a log may not have any closing line.
+    pub fn finish(&mut self) -> Vec<ParserEvent> {
+        let mut events = Vec::new();
+
+        if !self.pending.is_empty() {
+            let raw_line = std::mem::take(&mut self.pending);
+            self.push_raw_line(&raw_line, &mut |_, event| events.push(event));
+        }
+        events.extend(self.close_current_match());
+
+        events
+    }
+
+    /// Splits the `m:ss` game time in front of a line from the event it announces.
+    pub(crate) fn split_time(line: &str) -> (Option<u32>, &str) {
+        let (time, event) = match line.split_once(' ') {
+            Some(parts) => parts,
+            None => return (None, line),
+        };
+        let (minutes, seconds) = match time.split_once(':') {
+            Some(parts) => parts,
+            None => return (None, line),
+        };
+
+        let time = match (minutes.parse::<u32>(), seconds.parse::<u32>()) {
+            (Ok(minutes), Ok(seconds)) => minutes
+                .checked_mul(60)
+                .and_then(|minutes| minutes.checked_add(seconds)),
+            _ => None,
+        };
+        match time {
+            Some(time) => (Some(time), event.trim_start()),
+            None => (None, line),
         }
     }
 
-    fn handle_match_start(matches: &mut HashMap<String, MatchRecord>, current_match: &mut String) {
-        let current_match_number = matches.keys().len();
-        *current_match = format!("game_{current_match_number}").to_string();
+    fn close_current_match(&mut self) -> Option<ParserEvent> {
+        let (match_id, mut match_record) = self.current_match.take()?;
+        match_record.metadata.end_time = self.last_time;
+        ParserState::generate_ranking(&mut match_record);
+        match_record.awards.titles = self.award_engine.evaluate(&match_record);
+
+        Some(ParserEvent::MatchEnded {
+            match_id,
+            record: Box::new(match_record),
+        })
+    }
+
+    fn handle_match_start(&mut self, event: &str) -> ParserEvent {
+        let match_id = format!("game_{}", self.started_matches);
+        self.started_matches += 1;
+        self.match_lines = 0;
+
+        // InitGame: \sv_hostname\Code Miner Server\g_gametype\0\mapname\q3dm17...
+        let mut cvars = BTreeMap::new();
+        let mut settings = event
+            .trim_start_matches("InitGame:")
+            .trim()
+            .split('\\')
+            .skip(1);
+        while let (Some(key), Some(value)) = (settings.next(), settings.next()) {
+            cvars.insert(key.to_string(), value.to_string());
+        }
+
+        let dialect = self
+            .options
+            .dialect
+            .unwrap_or_else(|| Dialect::detect(&cvars));
+        let metadata = MatchMetadata {
+            map: cvars.get("mapname").cloned(),
+            game_type: cvars.get("g_gametype").cloned(),
+            server_name: cvars.get("sv_hostname").cloned(),
+            start_time: self.last_time,
+            end_time: None,
+            cvars,
+            dialect,
+        };
 
-        matches.insert(
-            current_match.clone(),
+        self.current_match = Some((
+            match_id.clone(),
             MatchRecord {
+                id: None,
                 total_kills: 0,
                 players: HashSet::new(),
                 kills: HashMap::new(),
                 kills_by_means: HashMap::new(),
                 ranking: Vec::new(),
+                metadata,
+                kill_events: Vec::new(),
+                chat: Vec::new(),
+                aliases: BTreeMap::new(),
+                client_names: HashMap::new(),
+                client_identities: BTreeMap::new(),
+                slot_identities: HashMap::new(),
+                teams: BTreeMap::new(),
+                game_events: Vec::new(),
+                highlights: Vec::new(),
+                awards: MatchAwards::default(),
+                current_streaks: HashMap::new(),
+                multi_kill_chains: HashMap::new(),
             },
-        );
+        ));
+
+        ParserEvent::MatchStarted { match_id }
     }
 
-    fn handle_user_joined_match(
-        line: String,
-        matches: &mut HashMap<String, MatchRecord>,
-        current_match: &String,
-    ) {
-        let mut split_line = line.split("\\");
-        let username = split_line.nth(1).unwrap();
+    fn handle_chat_message(&mut self, event: &str) {
+        let team_only = event.starts_with("sayteam: ");
+        let (_, said) = event.split_once(": ").unwrap_or_default();
+
+        let (player, message) = match said.split_once(": ") {
+            Some(parts) => parts,
+            None => return,
+        };
+
+        if let Some((_, match_record)) = self.current_match.as_mut() {
+            match_record.chat.push(ChatMessage {
+                time: self.last_time.unwrap_or_default(),
+                line: self.match_lines,
+                player: player.to_string(),
+                message: message.to_string(),
+                team_only,
+            });
+        }
+    }
+
+    fn handle_user_joined_match(&mut self, line: &str) -> Option<ParserEvent> {
+        let mut split_line = line.split('\\');
+        let username = match split_line.nth(1) {
+            Some(username) => username,
+            None => return Some(self.malformed_line(line)),
+        };
+
+        // 0:25 ClientUserinfoChanged: 2 n\Dono da Bola\t\0...
+        let client_id = line
+            .split("ClientUserinfoChanged: ")
+            .nth(1)
+            .and_then(|userinfo| userinfo.split_whitespace().next());
 
-        if let Some(match_record) = matches.get_mut(current_match) {
+        if let Some((_, match_record)) = self.current_match.as_mut() {
             match_record.players.insert(username.to_string());
             match_record.kills.insert(username.to_string(), 0);
+
+            let team = ParserState::userinfo_value(line, "t")
+                .and_then(|team| match_record.metadata.dialect.rules().team_name(team));
+            match team {
+                Some(team) => match_record
+                    .teams
+                    .insert(username.to_string(), team.to_string()),
+                None => match_record.teams.remove(username),
+            };
+
+            if let Some(client_id) = client_id {
+                ParserState::register_client_name(match_record, client_id, username);
+
+                let identity = match_record
+                    .slot_identities
+                    .entry(client_id.to_string())
+                    .or_default();
+                identity.update(line);
+                if identity.is_known() {
+                    let identity = identity.clone();
+                    match_record
+                        .client_identities
+                        .insert(username.to_string(), identity);
+                }
+            }
         }
+
+        None
     }
 
-    fn handle_user_kills(
-        line: String,
-        matches: &mut HashMap<String, MatchRecord>,
-        current_match: &String,
-    ) {
+    // 0:20 ClientUserinfo: 2 \ip\10.0.0.7:27960\name\Isgalamido\cl_guid\4F2A...
+    fn handle_client_userinfo(&mut self, event: &str) {
+        let (client_id, userinfo) = match event
+            .trim_start_matches("ClientUserinfo: ")
+            .split_once(char::is_whitespace)
+        {
+            Some(split) => split,
+            None => return,
+        };
+
+        if let Some((_, match_record)) = self.current_match.as_mut() {
+            let identity = match_record
+                .slot_identities
+                .entry(client_id.to_string())
+                .or_default();
+            identity.update(userinfo);
+
+            // Clients already named get it right away, the others when they are
+            if let Some(username) = match_record
+                .client_names
+                .get(client_id)
+                .filter(|_| identity.is_known())
+            {
+                let identity = identity.clone();
+                match_record
+                    .client_identities
+                    .insert(username.clone(), identity);
+            }
+        }
+    }
+
+    /// Value of `key` in a `\key\value` userinfo string, empty values left out.
+    fn userinfo_value<'a>(userinfo: &'a str, key: &str) -> Option<&'a str> {
+        let fields = userinfo.split('\\').collect::<Vec<&str>>();
+
+        // Names can hold a backslash, so pairs are looked up from the end rather than by position
+        fields
+            .windows(2)
+            .rev()
+            .find(|pair| pair[0] == key)
+            .map(|pair| pair[1])
+            .filter(|value| !value.is_empty())
+    }
+
+    fn register_client_name(match_record: &mut MatchRecord, client_id: &str, username: &str) {
+        let previous_name = match_record
+            .client_names
+            .insert(client_id.to_string(), username.to_string());
+
+        if let Some(previous_name) = previous_name.filter(|previous_name| previous_name != username)
+        {
+            match_record
+                .aliases
+                .entry(previous_name.clone())
+                .or_default()
+                .insert(username.to_string());
+            match_record
+                .aliases
+                .entry(username.to_string())
+                .or_default()
+                .insert(previous_name);
+        }
+    }
+
+    fn handle_user_kills(&mut self, line: &str) -> Option<ParserEvent> {
         let game_environment = "<world>";
 
-        let split_line = line.split(": ").nth(2).unwrap();
-        let killer = split_line.split("killed").nth(0).unwrap().trim();
-        let killed = split_line
-            .split("killed")
-            .nth(1)
-            .unwrap()
-            .split("by")
-            .nth(0)
-            .unwrap()
-            .trim();
-        let weapon = split_line.split("by").nth(1).unwrap().trim();
+        let dialect = match self.current_match.as_ref() {
+            Some((_, match_record)) => match_record.metadata.dialect,
+            None => self.options.dialect.unwrap_or_default(),
+        };
+        let (killer, killed, weapon) = match dialect.rules().parse_kill(line) {
+            Some(kill) => kill,
+            None => return Some(self.malformed_line(line)),
+        };
+
+        let (match_id, match_record) = self.current_match.as_mut()?;
+        match_record.total_kills += 1;
+
+        if killer == game_environment {
+            ParserState::remove_kill_score_from_user(match_record, killed);
+        } else if killer != killed {
+            ParserState::add_kill_score_to_user(match_record, killer);
+        }
+
+        ParserState::register_kill_means(match_record, weapon);
+
+        let time = self.last_time.unwrap_or_default();
+        ParserState::register_kill_highlights(match_record, self.options, time, killer, killed);
+
+        match_record.kill_events.push(KillEvent {
+            time,
+            line: self.match_lines,
+            killer: killer.to_string(),
+            killed: killed.to_string(),
+            means: weapon.to_string(),
+        });
+
+        if !self.kill_events {
+            return None;
+        }
+        Some(ParserEvent::Kill {
+            match_id: match_id.clone(),
+            killer: killer.to_string(),
+            killed: killed.to_string(),
+            means: weapon.to_string(),
+        })
+    }
+
+    /// Tracks first blood, streaks and multi-kills as kills come in, in the order they happened.
+    fn register_kill_highlights(
+        match_record: &mut MatchRecord,
+        options: ParserOptions,
+        time: u32,
+        killer: &str,
+        killed: &str,
+    ) {
+        let frag = killer != "<world>" && killer != killed;
+
+        if frag && match_record.awards.first_blood.is_none() {
+            match_record.awards.first_blood = Some(killer.to_string());
+            match_record.highlights.push(Highlight::FirstBlood {
+                time,
+                killer: killer.to_string(),
+                victim: killed.to_string(),
+            });
+        }
+
+        // Dying, whatever the cause, ends the victim's streak
+        if let Some(streak) = match_record.current_streaks.remove(killed) {
+            if streak >= STREAK_HIGHLIGHT_MIN {
+                match_record.highlights.push(Highlight::StreakEnded {
+                    time,
+                    player: killed.to_string(),
+                    streak,
+                    ended_by: killer.to_string(),
+                });
+            }
+        }
+
+        if !frag {
+            return;
+        }
 
-        if let Some(match_record) = matches.get_mut(current_match) {
-            match_record.total_kills += 1;
+        let streak = match_record
+            .current_streaks
+            .entry(killer.to_string())
+            .or_default();
+        *streak += 1;
+        let longest_streak = match_record
+            .awards
+            .longest_streaks
+            .entry(killer.to_string())
+            .or_default();
+        *longest_streak = (*longest_streak).max(*streak);
 
-            if killer == game_environment {
-                Parser::remove_kill_score_from_user(match_record, killed);
-            } else if killer != killed {
-                Parser::add_kill_score_to_user(match_record, killer);
+        let chain = match_record
+            .multi_kill_chains
+            .entry(killer.to_string())
+            .or_insert(MultiKillChain {
+                start_time: time,
+                kills: 0,
+                highlight: None,
+            });
+        if time.saturating_sub(chain.start_time) > options.multi_kill_window_secs {
+            *chain = MultiKillChain {
+                start_time: time,
+                kills: 0,
+                highlight: None,
+            };
+        }
+        chain.kills += 1;
+
+        if chain.kills < 2 {
+            return;
+        }
+
+        match chain.highlight {
+            Some(index) => {
+                if let Some(Highlight::MultiKill { kills, .. }) =
+                    match_record.highlights.get_mut(index)
+                {
+                    *kills = chain.kills;
+                }
+            }
+            None => {
+                chain.highlight = Some(match_record.highlights.len());
+                match_record.highlights.push(Highlight::MultiKill {
+                    time: chain.start_time,
+                    player: killer.to_string(),
+                    kills: chain.kills,
+                });
             }
+        }
+
+        let best_multi_kill = match_record
+            .awards
+            .best_multi_kills
+            .entry(killer.to_string())
+            .or_default();
+        *best_multi_kill = (*best_multi_kill).max(chain.kills);
+    }
+
+    /// Records the lines only the match's dialect knows, ignoring any other.
+    fn handle_game_event(&mut self, event: &str) {
+        let time = self.last_time.unwrap_or_default();
 
-            Parser::register_kill_means(match_record, weapon);
+        if let Some((_, match_record)) = self.current_match.as_mut() {
+            let client_names = &match_record.client_names;
+            let client_name = |client_id: &str| client_names.get(client_id).cloned();
+
+            if let Some(game_event) =
+                match_record
+                    .metadata
+                    .dialect
+                    .rules()
+                    .parse_event(time, event, &client_name)
+            {
+                match_record.game_events.push(game_event);
+            }
+        }
+    }
+
+    fn malformed_line(&self, line: &str) -> ParserEvent {
+        ParserEvent::MalformedLine {
+            line: self.lines,
+            content: line.to_string(),
         }
     }
 
@@ -168,22 +1032,358 @@ impl Parser {
             .or_insert(1);
     }
 
-    fn generate_ranking(matches: &mut HashMap<String, MatchRecord>, current_match: &String) {
-        if let Some(match_record) = matches.get_mut(current_match) {
-            let mut raw_ranking = match_record.kills.iter().collect::<Vec<(&String, &i16)>>();
-            raw_ranking.sort_by(|a, b| b.1.cmp(a.1));
+    fn generate_ranking(match_record: &mut MatchRecord) {
+        match_record.ranking = match_record.standings();
+    }
+}
+
+/// Matches of a slice of a log starting on a match boundary, parsed on its own.
+pub(crate) struct ParsedSegment {
+    /// In the order they started.
+    matches: Vec<MatchRecord>,
+    lines: u64,
+    first_malformed_line: Option<(u64, String)>,
+}
+
+/// Gathers the completed matches out of the parser events.
+#[derive(Default)]
+struct MatchCollector {
+    matches: HashMap<String, MatchRecord>,
+    first_malformed_line: Option<(u64, String)>,
+}
+
+impl MatchCollector {
+    fn collect(&mut self, event: ParserEvent) {
+        match event {
+            ParserEvent::MatchEnded { match_id, record } => {
+                self.matches.insert(match_id, *record);
+            }
+            ParserEvent::MalformedLine { line, content } => {
+                self.first_malformed_line.get_or_insert((line, content));
+            }
+            _ => {}
+        }
+    }
 
-            let ranking = raw_ranking
-                .iter()
-                .enumerate()
-                .map(|(i, (player, kills))| RankingPosition {
-                    player: player.to_string(),
-                    kills: **kills,
-                    position: (i + 1) as u16,
-                })
-                .collect::<Vec<RankingPosition>>();
+    /// A log without any match is rejected, pointing to the first malformed line when there is one.
+    fn into_matches(self) -> Result<HashMap<String, MatchRecord>, ParseError> {
+        if !self.matches.is_empty() {
+            return Ok(self.matches);
+        }
 
-            match_record.ranking = ranking;
+        match self.first_malformed_line {
+            Some((line, content)) => Err(ParseError::MalformedLine { line, content }),
+            None => Err(ParseError::NoMatches),
         }
     }
 }
+
+pub struct Parser {}
+
+impl Parser {
+    pub fn parse(file_path: String) -> Result<HashMap<String, MatchRecord>, ParseError> {
+        Parser::parse_with_options(file_path, ParserOptions::default())
+    }
+
+    pub fn parse_with_options(
+        file_path: String,
+        options: ParserOptions,
+    ) -> Result<HashMap<String, MatchRecord>, ParseError> {
+        let mut state = ParserState::with_options(options);
+        let mut collector = MatchCollector::default();
+
+        Parser::read_from_offset(file_path, &mut state, &mut collector)?;
+        state
+            .finish()
+            .into_iter()
+            .for_each(|event| collector.collect(event));
+
+        collector.into_matches()
+    }
+
+    /// Parses a whole log already loaded in memory, as done by the browser bundle.
+    pub fn parse_bytes(bytes: &[u8]) -> Result<HashMap<String, MatchRecord>, ParseError> {
+        Parser::parse_bytes_with_options(bytes, ParserOptions::default())
+    }
+
+    pub fn parse_bytes_with_options(
+        bytes: &[u8],
+        options: ParserOptions,
+    ) -> Result<HashMap<String, MatchRecord>, ParseError> {
+        let mut state = ParserState::with_options(options);
+        let mut collector = MatchCollector::default();
+
+        let mut events = state.push_chunk(bytes);
+        events.extend(state.finish());
+        events
+            .into_iter()
+            .for_each(|event| collector.collect(event));
+
+        collector.into_matches()
+    }
+
+    /// Parses a slice of a log starting on a match boundary. `last_time` is the game time logged
+    /// last before the slice and `next_time` the one of the `InitGame` line right after it, so the
+    /// matches get the times a parse of the whole log gives them.
+    pub(crate) fn parse_segment(
+        bytes: &[u8],
+        options: ParserOptions,
+        last_time: Option<u32>,
+        next_time: Option<u32>,
+    ) -> ParsedSegment {
+        let mut state = ParserState {
+            last_time,
+            ..ParserState::with_options(options)
+        };
+        let mut matches = Vec::new();
+        let mut first_malformed_line = None;
+        let mut collect = |event| match event {
+            ParserEvent::MatchEnded { record, .. } => matches.push(*record),
+            ParserEvent::MalformedLine { line, content } => {
+                first_malformed_line.get_or_insert((line, content));
+            }
+            _ => {}
+        };
+
+        state.push_chunk_with(bytes, |_, event| collect(event));
+        // The match left open is closed by the next `InitGame` line when parsing the whole log
+        if next_time.is_some() {
+            state.last_time = next_time;
+        }
+        state.finish().into_iter().for_each(collect);
+
+        ParsedSegment {
+            matches,
+            lines: state.lines,
+            first_malformed_line,
+        }
+    }
+
+    /// Puts the segments of a log back together, in the order they come in the log.
+    pub(crate) fn merge_segments(
+        segments: Vec<ParsedSegment>,
+    ) -> Result<HashMap<String, MatchRecord>, ParseError> {
+        let mut collector = MatchCollector::default();
+        let mut lines = 0;
+
+        for segment in segments {
+            // Every match started is ended, so keys go on counting the matches of the earlier segments
+            for record in segment.matches {
+                collector
+                    .matches
+                    .insert(format!("game_{}", collector.matches.len()), record);
+            }
+            if let Some((line, content)) = segment.first_malformed_line {
+                collector
+                    .first_malformed_line
+                    .get_or_insert((lines + line, content));
+            }
+            lines += segment.lines;
+        }
+
+        collector.into_matches()
+    }
+
+    /// Parses `file_path` from the offset where `state` stopped, returning the matches completed
+    /// meanwhile. The match still in progress stays in `state`.
+    pub fn resume(
+        file_path: String,
+        state: &mut ParserState,
+    ) -> Result<HashMap<String, MatchRecord>, ParseError> {
+        let mut collector = MatchCollector::default();
+        Parser::read_from_offset(file_path, state, &mut collector)?;
+
+        Ok(collector.matches)
+    }
+
+    fn read_from_offset(
+        file_path: String,
+        state: &mut ParserState,
+        collector: &mut MatchCollector,
+    ) -> Result<(), ParseError> {
+        let mut file_buffer = Parser::load_file(file_path)?;
+        file_buffer.seek(SeekFrom::Start(state.offset()))?;
+
+        loop {
+            let chunk = file_buffer.fill_buf()?;
+            if chunk.is_empty() {
+                break;
+            }
+
+            state.push_chunk_with(chunk, |_, event| collector.collect(event));
+
+            let chunk_size = chunk.len();
+            file_buffer.consume(chunk_size);
+        }
+
+        Ok(())
+    }
+
+    fn load_file(file_path: String) -> Result<BufReader<File>, ParseError> {
+        Ok(BufReader::new(File::open(file_path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+
+    use super::*;
+    use crate::model::generator::{GeneratorOptions, LogGenerator};
+
+    const FIRST_PART: &str = "  0:00 InitGame: \\sv_hostname\\Test\\g_gametype\\0\\mapname\\q3dm17\n  0:10 Kill: 1022 2 22: <world> kil";
+    const SECOND_PART: &str = "led Isgalamido by MOD_TRIGGER_HURT\n  0:20 ShutdownGame:\n";
+
+    #[test]
+    fn resume_reads_a_split_line_once() {
+        let path = std::env::temp_dir().join(format!("q3a-resume-{}.log", std::process::id()));
+        fs::write(&path, FIRST_PART).unwrap();
+        let file_path = path.to_string_lossy().into_owned();
+        let mut state = ParserState::default();
+
+        assert!(Parser::resume(file_path.clone(), &mut state)
+            .unwrap()
+            .is_empty());
+        assert_eq!(state.offset(), FIRST_PART.len() as u64);
+        // Nothing written since, the incomplete line must not be read again
+        assert!(Parser::resume(file_path.clone(), &mut state)
+            .unwrap()
+            .is_empty());
+        assert_eq!(state.offset(), FIRST_PART.len() as u64);
+
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(SECOND_PART.as_bytes())
+            .unwrap();
+        let matches = Parser::resume(file_path, &mut state).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let record = matches.values().next().unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(record.total_kills, 1);
+        assert_eq!(record.kill_events.len(), 1);
+        assert_eq!(record.kill_events[0].killer, "<world>");
+        assert_eq!(record.kill_events[0].killed, "Isgalamido");
+        assert_eq!(
+            state.offset(),
+            (FIRST_PART.len() + SECOND_PART.len()) as u64
+        );
+    }
+
+    #[test]
+    fn offset_counts_the_incomplete_line() {
+        let mut state = ParserState::default();
+        state.push_chunk_with(FIRST_PART.as_bytes(), |_, _| {});
+        state.push_chunk_with(SECOND_PART.as_bytes(), |_, _| {});
+
+        assert_eq!(
+            state.offset(),
+            (FIRST_PART.len() + SECOND_PART.len()) as u64
+        );
+    }
+
+    #[test]
+    fn split_time_ignores_times_that_overflow() {
+        assert_eq!(
+            ParserState::split_time("  1:05 ShutdownGame:".trim_start()),
+            (Some(65), "ShutdownGame:")
+        );
+        assert_eq!(
+            ParserState::split_time("99999999:00 ShutdownGame:"),
+            (None, "99999999:00 ShutdownGame:")
+        );
+    }
+
+    #[test]
+    fn events_of_one_second_keep_their_line() {
+        let log = "  0:00 InitGame: \\sv_hostname\\Test\\g_gametype\\0\\mapname\\q3dm17\n  0:10 say: Isgalamido: gg\n  0:10 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT\n  0:20 ShutdownGame:\n";
+        let matches = Parser::parse_bytes(log.as_bytes()).unwrap();
+        let record = matches.values().next().unwrap();
+
+        assert_eq!(record.chat[0].line, 1);
+        assert_eq!(record.kill_events[0].line, 2);
+    }
+
+    #[test]
+    fn kill_events_are_handed_over_only_when_asked() {
+        let log = format!("{FIRST_PART}{SECOND_PART}");
+        let is_kill = |event: &ParserEvent| matches!(event, ParserEvent::Kill { .. });
+
+        let events = ParserState::default().push_chunk(log.as_bytes());
+        assert!(!events.iter().any(is_kill));
+        assert_eq!(events.len(), 2);
+
+        let events = ParserState::default()
+            .with_kill_events()
+            .push_chunk(log.as_bytes());
+        assert_eq!(events.iter().filter(|event| is_kill(event)).count(), 1);
+    }
+
+    fn generated_log() -> Vec<u8> {
+        LogGenerator::new(GeneratorOptions {
+            seed: 7,
+            matches: 20,
+            ..GeneratorOptions::default()
+        })
+        .generate_bytes()
+    }
+
+    #[test]
+    fn chunks_parse_like_the_whole_log() {
+        let log = generated_log();
+        let expected = comparable_matches(&Parser::parse_bytes(&log).unwrap());
+
+        for chunk_size in [1, 7, 64, 4096] {
+            let mut state = ParserState::default();
+            let mut collector = MatchCollector::default();
+            for chunk in log.chunks(chunk_size) {
+                state.push_chunk_with(chunk, |_, event| collector.collect(event));
+            }
+            state
+                .finish()
+                .into_iter()
+                .for_each(|event| collector.collect(event));
+
+            assert_eq!(state.offset(), log.len() as u64);
+            assert_eq!(
+                comparable_matches(&collector.into_matches().unwrap()),
+                expected,
+                "chunks of {chunk_size} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn resuming_a_growing_log_parses_like_the_whole_log() {
+        let log = generated_log();
+        let expected = comparable_matches(&Parser::parse_bytes(&log).unwrap());
+        let path = std::env::temp_dir().join(format!("q3a-growing-{}.log", std::process::id()));
+        let file_path = path.to_string_lossy().into_owned();
+        fs::write(&path, b"").unwrap();
+
+        // Written in uneven pieces, most of them ending in the middle of a line
+        let mut state = ParserState::default();
+        let mut matches = HashMap::new();
+        for piece in log.chunks(log.len() / 9 + 13) {
+            OpenOptions::new()
+                .append(true)
+                .open(&path)
+                .unwrap()
+                .write_all(piece)
+                .unwrap();
+            matches.extend(Parser::resume(file_path.clone(), &mut state).unwrap());
+        }
+        fs::remove_file(&path).unwrap();
+        let mut collector = MatchCollector::default();
+        state
+            .finish()
+            .into_iter()
+            .for_each(|event| collector.collect(event));
+        matches.extend(collector.matches);
+
+        assert_eq!(comparable_matches(&matches), expected);
+    }
+}
@@ -0,0 +1,381 @@
+use std::collections::{BTreeSet, HashMap};
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+
+use crate::store::{match_number, StoredMatch};
+
+/// Path of a player's profile page.
+pub fn player_path(name: &str) -> String {
+    format!("/players/{}", utf8_percent_encode(name, NON_ALPHANUMERIC))
+}
+
+/// Player name out of the `:name` segment of `player_path`.
+pub fn player_name_from_path(segment: &str) -> String {
+    percent_decode_str(segment).decode_utf8_lossy().into_owned()
+}
+
+/// Players picked in the query string of the comparison page, as sent by its form.
+pub fn comparison_names_from_query(query: &str) -> Vec<String> {
+    query
+        .trim_start_matches('?')
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .filter(|(key, _)| *key == "player")
+        .map(|(_, value)| {
+            percent_decode_str(&value.replace('+', " "))
+                .decode_utf8_lossy()
+                .into_owned()
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Everyone who joined, killed or got killed in any of the matches, `<world>` left out.
+pub fn player_names(stored_matches: &[StoredMatch]) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+
+    for record in stored_matches
+        .iter()
+        .map(|stored_match| &stored_match.record)
+    {
+        names.extend(
+            record
+                .ranking
+                .iter()
+                .map(|ranking_position| ranking_position.player.clone()),
+        );
+        for kill in record.kill_events.iter() {
+            names.insert(kill.killer.clone());
+            names.insert(kill.killed.clone());
+        }
+    }
+
+    names.remove("<world>");
+    names
+}
+
+/// How a player did in one of the matches they played.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlayerMatch {
+    pub match_id: String,
+    pub match_key: String,
+    pub uploaded_at: u64,
+    pub map: Option<String>,
+    pub position: Option<u16>,
+    pub players: usize,
+    pub score: i16,
+    /// Other players fragged, suicides left out.
+    pub kills: u32,
+    /// Deaths of any kind, `<world>` and suicides included.
+    pub deaths: u32,
+}
+
+impl PlayerMatch {
+    pub fn kill_death_ratio(&self) -> f64 {
+        kill_death_ratio(self.kills, self.deaths)
+    }
+}
+
+/// Kills per death, with deathless players counting their kills as is.
+pub fn kill_death_ratio(kills: u32, deaths: u32) -> f64 {
+    kills as f64 / deaths.max(1) as f64
+}
+
+/// A player's record across every stored match.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlayerProfile {
+    pub name: String,
+    pub aliases: BTreeSet<String>,
+    /// Matches in upload order.
+    pub matches: Vec<PlayerMatch>,
+    /// Weapons by kills made with them, most used first.
+    pub best_weapons: Vec<(String, u32)>,
+    /// Player who killed them the most, and how many times.
+    pub nemesis: Option<(String, u32)>,
+    /// Player they killed the most, and how many times.
+    pub favourite_victim: Option<(String, u32)>,
+}
+
+impl PlayerProfile {
+    /// Aggregates the matches `name` played, `None` when they never played any.
+    pub fn build(name: &str, stored_matches: &[StoredMatch]) -> Option<PlayerProfile> {
+        let mut aliases = BTreeSet::new();
+        let mut matches = Vec::new();
+        let mut weapons: HashMap<String, u32> = HashMap::new();
+        let mut killed_by: HashMap<String, u32> = HashMap::new();
+        let mut victims: HashMap<String, u32> = HashMap::new();
+
+        for stored_match in stored_matches {
+            let record = &stored_match.record;
+            let ranking_position = record
+                .ranking
+                .iter()
+                .find(|ranking_position| ranking_position.player == name);
+            let took_part = ranking_position.is_some()
+                || record
+                    .kill_events
+                    .iter()
+                    .any(|kill| kill.killer == name || kill.killed == name);
+
+            if !took_part {
+                continue;
+            }
+
+            aliases.extend(record.aliases.get(name).cloned().unwrap_or_default());
+
+            let mut kills = 0;
+            let mut deaths = 0;
+            for kill in record.kill_events.iter() {
+                if kill.killed == name {
+                    deaths += 1;
+                    if kill.killer != name && kill.killer != "<world>" {
+                        *killed_by.entry(kill.killer.clone()).or_default() += 1;
+                    }
+                } else if kill.killer == name {
+                    kills += 1;
+                    *weapons.entry(kill.means.clone()).or_default() += 1;
+                    *victims.entry(kill.killed.clone()).or_default() += 1;
+                }
+            }
+
+            matches.push(PlayerMatch {
+                match_id: stored_match.id.clone(),
+                match_key: stored_match.match_key.clone(),
+                uploaded_at: stored_match.uploaded_at,
+                map: record.metadata.map.clone(),
+                position: ranking_position.map(|ranking_position| ranking_position.position),
+                players: record.ranking.len(),
+                score: ranking_position
+                    .map(|ranking_position| ranking_position.kills)
+                    .unwrap_or_default(),
+                kills,
+                deaths,
+            });
+        }
+
+        if matches.is_empty() {
+            return None;
+        }
+
+        matches.sort_by(|a, b| {
+            (a.uploaded_at, match_number(&a.match_key))
+                .cmp(&(b.uploaded_at, match_number(&b.match_key)))
+        });
+
+        Some(PlayerProfile {
+            name: name.to_string(),
+            aliases,
+            matches,
+            best_weapons: PlayerProfile::most_first(weapons),
+            nemesis: PlayerProfile::most_first(killed_by).into_iter().next(),
+            favourite_victim: PlayerProfile::most_first(victims).into_iter().next(),
+        })
+    }
+
+    /// Profiles of everyone who played any of the matches, best first: most wins, then most
+    /// kills, then by name.
+    pub fn build_all(stored_matches: &[StoredMatch]) -> Vec<PlayerProfile> {
+        let mut profiles = player_names(stored_matches)
+            .iter()
+            .filter_map(|name| PlayerProfile::build(name, stored_matches))
+            .collect::<Vec<PlayerProfile>>();
+
+        profiles.sort_by(|a, b| {
+            b.wins()
+                .cmp(&a.wins())
+                .then_with(|| b.total_kills().cmp(&a.total_kills()))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        profiles
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.matches
+            .iter()
+            .map(|player_match| player_match.kills)
+            .sum()
+    }
+
+    pub fn total_deaths(&self) -> u32 {
+        self.matches
+            .iter()
+            .map(|player_match| player_match.deaths)
+            .sum()
+    }
+
+    /// Matches they finished in first place.
+    pub fn wins(&self) -> usize {
+        self.matches
+            .iter()
+            .filter(|player_match| player_match.position == Some(1))
+            .count()
+    }
+
+    /// K/D ratio after each match, counting every match played up to it.
+    pub fn kill_death_history(&self) -> Vec<f64> {
+        let mut kills = 0;
+        let mut deaths = 0;
+
+        self.matches
+            .iter()
+            .map(|player_match| {
+                kills += player_match.kills;
+                deaths += player_match.deaths;
+                kill_death_ratio(kills, deaths)
+            })
+            .collect()
+    }
+
+    /// Counts sorted from the highest, ties ordered by name.
+    fn most_first(counts: HashMap<String, u32>) -> Vec<(String, u32)> {
+        let mut counts = counts.into_iter().collect::<Vec<(String, u32)>>();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::parser::Parser;
+
+    const ISGALAMIDO: &str = r"  0:01 ClientUserinfoChanged: 2 n\Isgalamido\t\0\model\uriel/zael";
+    const ZEH: &str = r"  0:01 ClientUserinfoChanged: 3 n\Zeh\t\0\model\sarge/default";
+    const MOCINHA: &str = r"  0:01 ClientUserinfoChanged: 4 n\Mocinha\t\0\model\sarge";
+
+    fn stored_match(id: &str, uploaded_at: u64, lines: &[&str]) -> StoredMatch {
+        let mut log = vec![r"  0:00 InitGame: \sv_hostname\Test\g_gametype\0\mapname\q3dm17"];
+        log.extend(lines);
+        log.push("  5:00 ShutdownGame:");
+        let (upload_id, number) = id.split_once('-').unwrap();
+
+        StoredMatch {
+            id: id.to_string(),
+            upload_id: upload_id.to_string(),
+            match_key: format!("game_{number}"),
+            uploaded_at,
+            record: Parser::parse_bytes(log.join("\n").as_bytes())
+                .unwrap()
+                .into_values()
+                .next()
+                .unwrap(),
+        }
+    }
+
+    /// Three matches, the first one uploaded last.
+    fn stored_matches() -> Vec<StoredMatch> {
+        vec![
+            stored_match(
+                "b-1",
+                20,
+                &[
+                    ISGALAMIDO,
+                    ZEH,
+                    "  0:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+                    "  0:20 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+                    "  0:30 Kill: 3 2 6: Zeh killed Isgalamido by MOD_ROCKET",
+                    "  0:40 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT",
+                ],
+            ),
+            stored_match(
+                "a-0",
+                10,
+                &[
+                    ISGALAMIDO,
+                    ZEH,
+                    MOCINHA,
+                    "  0:10 Kill: 4 2 10: Mocinha killed Isgalamido by MOD_RAILGUN",
+                    "  0:20 Kill: 2 4 6: Isgalamido killed Mocinha by MOD_ROCKET",
+                    "  0:30 Kill: 2 2 7: Isgalamido killed Isgalamido by MOD_ROCKET_SPLASH",
+                ],
+            ),
+            stored_match(
+                "a-2",
+                10,
+                &[
+                    ZEH,
+                    MOCINHA,
+                    "  0:10 Kill: 3 4 1: Zeh killed Mocinha by MOD_SHOTGUN",
+                ],
+            ),
+        ]
+    }
+
+    #[test]
+    fn aggregates_the_matches_played() {
+        let profile = PlayerProfile::build("Isgalamido", &stored_matches()).unwrap();
+        let matches = profile
+            .matches
+            .iter()
+            .map(|player_match| {
+                (
+                    player_match.match_id.as_str(),
+                    player_match.position,
+                    player_match.players,
+                    player_match.score,
+                    player_match.kills,
+                    player_match.deaths,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            matches,
+            [("a-0", Some(1), 3, 1, 1, 2), ("b-1", Some(1), 2, 1, 2, 2)]
+        );
+        assert_eq!(
+            (
+                profile.total_kills(),
+                profile.total_deaths(),
+                profile.wins()
+            ),
+            (3, 4, 2)
+        );
+        assert_eq!(profile.kill_death_history(), [0.5, 0.75]);
+        assert_eq!(
+            profile.best_weapons,
+            [
+                ("MOD_RAILGUN".to_string(), 2),
+                ("MOD_ROCKET".to_string(), 1)
+            ]
+        );
+        // Tied, the first by name
+        assert_eq!(profile.nemesis, Some(("Mocinha".to_string(), 1)));
+        assert_eq!(profile.favourite_victim, Some(("Zeh".to_string(), 2)));
+    }
+
+    #[test]
+    fn players_who_never_played_have_no_profile() {
+        assert!(PlayerProfile::build("Dono da Bola", &stored_matches()).is_none());
+        assert!(PlayerProfile::build("<world>", &stored_matches()).is_some());
+        assert!(!player_names(&stored_matches()).contains("<world>"));
+    }
+
+    #[test]
+    fn ranks_profiles_by_wins_then_kills() {
+        let names = PlayerProfile::build_all(&stored_matches())
+            .into_iter()
+            .map(|profile| profile.name)
+            .collect::<Vec<String>>();
+
+        assert_eq!(names, ["Isgalamido", "Zeh", "Mocinha"]);
+    }
+
+    #[test]
+    fn deathless_players_count_their_kills() {
+        assert_eq!(kill_death_ratio(3, 0), 3.0);
+        assert_eq!(kill_death_ratio(3, 2), 1.5);
+    }
+
+    #[test]
+    fn names_survive_the_paths_and_queries() {
+        assert_eq!(player_path("Dono da Bola"), "/players/Dono%20da%20Bola");
+        assert_eq!(player_name_from_path("Dono%20da%20Bola"), "Dono da Bola");
+        assert_eq!(
+            comparison_names_from_query("?player=Isgalamido&player=Dono+da+Bola&player=&other=Zeh"),
+            ["Isgalamido", "Dono da Bola"]
+        );
+    }
+}
@@ -0,0 +1,894 @@
+use std::collections::HashMap;
+use std::f64::consts::{PI, SQRT_2};
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::store::{match_number, StoredMatch};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RatingAlgorithm {
+    Elo,
+    #[default]
+    Glicko2,
+    TrueSkill,
+}
+
+impl RatingAlgorithm {
+    pub const ALL: [RatingAlgorithm; 3] = [
+        RatingAlgorithm::Elo,
+        RatingAlgorithm::Glicko2,
+        RatingAlgorithm::TrueSkill,
+    ];
+
+    /// Name used in query strings and settings.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RatingAlgorithm::Elo => "elo",
+            RatingAlgorithm::Glicko2 => "glicko2",
+            RatingAlgorithm::TrueSkill => "trueskill",
+        }
+    }
+}
+
+impl fmt::Display for RatingAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RatingAlgorithm::Elo => "Elo",
+            RatingAlgorithm::Glicko2 => "Glicko-2",
+            RatingAlgorithm::TrueSkill => "TrueSkill",
+        })
+    }
+}
+
+impl FromStr for RatingAlgorithm {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        RatingAlgorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.as_str() == value.to_lowercase())
+            .ok_or_else(|| {
+                format!("Unknown rating algorithm {value:?}, expected elo, glicko2 or trueskill")
+            })
+    }
+}
+
+/// What a match is turned into before rating it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RatingInput {
+    /// Free-for-all: every player beat everyone ranked below them.
+    #[default]
+    Placements,
+    /// Each kill is a duel won by the killer, suicides and `<world>` left out.
+    Duels,
+}
+
+impl RatingInput {
+    pub const ALL: [RatingInput; 2] = [RatingInput::Placements, RatingInput::Duels];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RatingInput::Placements => "placements",
+            RatingInput::Duels => "duels",
+        }
+    }
+}
+
+impl fmt::Display for RatingInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RatingInput::Placements => "Placements",
+            RatingInput::Duels => "Duels",
+        })
+    }
+}
+
+impl FromStr for RatingInput {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        RatingInput::ALL
+            .into_iter()
+            .find(|input| input.as_str() == value.to_lowercase())
+            .ok_or_else(|| format!("Unknown rating input {value:?}, expected placements or duels"))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct EloSettings {
+    pub initial_rating: f64,
+    /// Most points a player can win or lose in one match.
+    pub k_factor: f64,
+}
+
+impl Default for EloSettings {
+    fn default() -> Self {
+        EloSettings {
+            initial_rating: 1500.0,
+            k_factor: 32.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Glicko2Settings {
+    pub initial_rating: f64,
+    pub initial_deviation: f64,
+    pub initial_volatility: f64,
+    /// How much the volatility can change between matches, usually between 0.3 and 1.2.
+    pub tau: f64,
+}
+
+impl Default for Glicko2Settings {
+    fn default() -> Self {
+        Glicko2Settings {
+            initial_rating: 1500.0,
+            initial_deviation: 350.0,
+            initial_volatility: 0.06,
+            tau: 0.5,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct TrueSkillSettings {
+    pub mu: f64,
+    pub sigma: f64,
+    /// Skill difference giving the better player about a 76% chance to win.
+    pub beta: f64,
+    /// Uncertainty added before every match, so ratings keep moving.
+    pub tau: f64,
+    pub draw_probability: f64,
+}
+
+impl Default for TrueSkillSettings {
+    fn default() -> Self {
+        TrueSkillSettings {
+            mu: 25.0,
+            sigma: 25.0 / 3.0,
+            beta: 25.0 / 6.0,
+            tau: 25.0 / 300.0,
+            draw_probability: 0.1,
+        }
+    }
+}
+
+/// Which algorithm rates the players, from what, and the parameters of each algorithm.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct RatingSettings {
+    pub algorithm: RatingAlgorithm,
+    pub input: RatingInput,
+    pub elo: EloSettings,
+    pub glicko2: Glicko2Settings,
+    pub trueskill: TrueSkillSettings,
+}
+
+impl RatingSettings {
+    /// Rejects parameters the algorithms can't work with, naming the first one found.
+    pub fn validate(&self) -> Result<(), String> {
+        let positive = [
+            ("elo.k_factor", self.elo.k_factor),
+            ("glicko2.initial_deviation", self.glicko2.initial_deviation),
+            (
+                "glicko2.initial_volatility",
+                self.glicko2.initial_volatility,
+            ),
+            ("glicko2.tau", self.glicko2.tau),
+            ("trueskill.sigma", self.trueskill.sigma),
+            ("trueskill.beta", self.trueskill.beta),
+        ];
+        for (name, value) in positive {
+            if !(value > 0.0 && value.is_finite()) {
+                return Err(format!("{name} must be positive, got {value}"));
+            }
+        }
+        if !(self.trueskill.tau >= 0.0 && self.trueskill.tau.is_finite()) {
+            return Err(format!(
+                "trueskill.tau can't be negative, got {}",
+                self.trueskill.tau
+            ));
+        }
+        if !(0.0..1.0).contains(&self.trueskill.draw_probability) {
+            return Err(format!(
+                "trueskill.draw_probability must be between 0 and 1, got {}",
+                self.trueskill.draw_probability
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Same parameters with the algorithm or input picked by a request.
+    pub fn with_choices(
+        &self,
+        algorithm: Option<RatingAlgorithm>,
+        input: Option<RatingInput>,
+    ) -> RatingSettings {
+        RatingSettings {
+            algorithm: algorithm.unwrap_or(self.algorithm),
+            input: input.unwrap_or(self.input),
+            ..self.clone()
+        }
+    }
+}
+
+/// A player's rating right after a match they played.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RatingPoint {
+    pub match_id: String,
+    pub match_key: String,
+    pub uploaded_at: u64,
+    pub rating: f64,
+    pub deviation: Option<f64>,
+    /// Rating won or lost in the match.
+    pub change: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlayerRating {
+    pub player: String,
+    pub rating: f64,
+    /// Uncertainty of the rating, `None` for Elo which doesn't track any.
+    pub deviation: Option<f64>,
+    pub matches: usize,
+    /// In the order the matches were rated.
+    pub history: Vec<RatingPoint>,
+}
+
+/// Ratings of everyone after replaying every stored match in upload order.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RatingTable {
+    pub algorithm: RatingAlgorithm,
+    pub input: RatingInput,
+    /// Highest rating first, ties ordered by name.
+    pub players: Vec<PlayerRating>,
+}
+
+impl RatingTable {
+    pub fn compute(stored_matches: &[StoredMatch], settings: &RatingSettings) -> RatingTable {
+        let mut ordered_matches = stored_matches.iter().collect::<Vec<&StoredMatch>>();
+        ordered_matches.sort_by(|a, b| {
+            (a.uploaded_at, &a.upload_id, match_number(&a.match_key)).cmp(&(
+                b.uploaded_at,
+                &b.upload_id,
+                match_number(&b.match_key),
+            ))
+        });
+
+        let mut states: HashMap<String, PlayerState> = HashMap::new();
+        let mut histories: HashMap<String, Vec<RatingPoint>> = HashMap::new();
+
+        for stored_match in ordered_matches {
+            let games = MatchGames::from_match(stored_match, settings.input);
+            if games.participants.is_empty() {
+                continue;
+            }
+
+            for player in games.participants.iter() {
+                states
+                    .entry(player.clone())
+                    .or_insert_with(|| PlayerState::initial(settings));
+            }
+
+            let ratings_before = games
+                .participants
+                .iter()
+                .map(|player| states[player].rating)
+                .collect::<Vec<f64>>();
+
+            match settings.algorithm {
+                RatingAlgorithm::Elo => rate_elo(&settings.elo, &mut states, &games),
+                RatingAlgorithm::Glicko2 => rate_glicko2(&settings.glicko2, &mut states, &games),
+                RatingAlgorithm::TrueSkill => {
+                    rate_trueskill(&settings.trueskill, &mut states, &games)
+                }
+            }
+
+            for (player, rating_before) in games.participants.iter().zip(ratings_before) {
+                let state = &states[player];
+                histories
+                    .entry(player.clone())
+                    .or_default()
+                    .push(RatingPoint {
+                        match_id: stored_match.id.clone(),
+                        match_key: stored_match.match_key.clone(),
+                        uploaded_at: stored_match.uploaded_at,
+                        rating: state.rating,
+                        deviation: state.deviation(settings.algorithm),
+                        change: state.rating - rating_before,
+                    });
+            }
+        }
+
+        let mut players = states
+            .into_iter()
+            .map(|(player, state)| {
+                let history = histories.remove(&player).unwrap_or_default();
+                PlayerRating {
+                    rating: state.rating,
+                    deviation: state.deviation(settings.algorithm),
+                    matches: history.len(),
+                    history,
+                    player,
+                }
+            })
+            .collect::<Vec<PlayerRating>>();
+        players.sort_by(|a, b| {
+            b.rating
+                .total_cmp(&a.rating)
+                .then_with(|| a.player.cmp(&b.player))
+        });
+
+        RatingTable {
+            algorithm: settings.algorithm,
+            input: settings.input,
+            players,
+        }
+    }
+
+    pub fn player(&self, name: &str) -> Option<&PlayerRating> {
+        self.players
+            .iter()
+            .find(|player_rating| player_rating.player == name)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct PlayerState {
+    rating: f64,
+    deviation: f64,
+    volatility: f64,
+}
+
+impl PlayerState {
+    fn initial(settings: &RatingSettings) -> PlayerState {
+        match settings.algorithm {
+            RatingAlgorithm::Elo => PlayerState {
+                rating: settings.elo.initial_rating,
+                deviation: 0.0,
+                volatility: 0.0,
+            },
+            RatingAlgorithm::Glicko2 => PlayerState {
+                rating: settings.glicko2.initial_rating,
+                deviation: settings.glicko2.initial_deviation,
+                volatility: settings.glicko2.initial_volatility,
+            },
+            RatingAlgorithm::TrueSkill => PlayerState {
+                rating: settings.trueskill.mu,
+                deviation: settings.trueskill.sigma,
+                volatility: 0.0,
+            },
+        }
+    }
+
+    fn deviation(&self, algorithm: RatingAlgorithm) -> Option<f64> {
+        (algorithm != RatingAlgorithm::Elo).then_some(self.deviation)
+    }
+}
+
+/// One-on-one results of a match, `score` being 1 when `player` won, 0.5 on a draw.
+struct Game {
+    player: String,
+    opponent: String,
+    score: f64,
+}
+
+struct MatchGames {
+    participants: Vec<String>,
+    /// In the order they should be applied by algorithms updating after each game.
+    games: Vec<Game>,
+    /// Games between players ranked next to each other, all a chain of updates needs.
+    adjacent_games: Vec<Game>,
+}
+
+impl MatchGames {
+    fn from_match(stored_match: &StoredMatch, input: RatingInput) -> MatchGames {
+        let record = &stored_match.record;
+
+        match input {
+            RatingInput::Placements => {
+                let ranking = &record.ranking;
+                let result = |i: usize, j: usize| Game {
+                    player: ranking[i].player.clone(),
+                    opponent: ranking[j].player.clone(),
+                    score: if ranking[i].kills == ranking[j].kills {
+                        0.5
+                    } else {
+                        1.0
+                    },
+                };
+
+                MatchGames {
+                    participants: ranking
+                        .iter()
+                        .map(|ranking_position| ranking_position.player.clone())
+                        .collect(),
+                    games: (0..ranking.len())
+                        .flat_map(|i| (i + 1..ranking.len()).map(move |j| (i, j)))
+                        .map(|(i, j)| result(i, j))
+                        .collect(),
+                    adjacent_games: (1..ranking.len()).map(|j| result(j - 1, j)).collect(),
+                }
+            }
+            RatingInput::Duels => {
+                let games = record
+                    .kill_events
+                    .iter()
+                    .filter(|kill| kill.killer != "<world>" && kill.killer != kill.killed)
+                    .map(|kill| Game {
+                        player: kill.killer.clone(),
+                        opponent: kill.killed.clone(),
+                        score: 1.0,
+                    })
+                    .collect::<Vec<Game>>();
+                let mut participants = Vec::new();
+                for game in games.iter() {
+                    for player in [&game.player, &game.opponent] {
+                        if !participants.contains(player) {
+                            participants.push(player.clone());
+                        }
+                    }
+                }
+
+                MatchGames {
+                    participants,
+                    adjacent_games: Vec::new(),
+                    games,
+                }
+            }
+        }
+    }
+
+    /// Every game seen from each side, opponents paired with the player's score.
+    fn by_player(&self) -> HashMap<&str, Vec<(&str, f64)>> {
+        let mut by_player: HashMap<&str, Vec<(&str, f64)>> = HashMap::new();
+        for game in self.games.iter() {
+            by_player
+                .entry(&game.player)
+                .or_default()
+                .push((&game.opponent, game.score));
+            by_player
+                .entry(&game.opponent)
+                .or_default()
+                .push((&game.player, 1.0 - game.score));
+        }
+        by_player
+    }
+}
+
+/// Everyone is updated at once from the ratings before the match, the change averaged over their
+/// games so a match moves a rating by `k_factor` at most.
+fn rate_elo(settings: &EloSettings, states: &mut HashMap<String, PlayerState>, games: &MatchGames) {
+    let before = states.clone();
+
+    for (player, results) in games.by_player() {
+        let rating = before[player].rating;
+        let surprise = results
+            .iter()
+            .map(|(opponent, score)| {
+                let expected =
+                    1.0 / (1.0 + 10_f64.powf((before[*opponent].rating - rating) / 400.0));
+                score - expected
+            })
+            .sum::<f64>();
+
+        if let Some(state) = states.get_mut(player) {
+            state.rating += settings.k_factor * surprise / results.len() as f64;
+        }
+    }
+}
+
+/// Glicko-2 scale factor between the Glicko and Glicko-2 rating scales.
+const GLICKO2_SCALE: f64 = 173.7178;
+const GLICKO2_CONVERGENCE: f64 = 0.000001;
+/// Most steps taken by each search of the volatility.
+const GLICKO2_ITERATIONS: usize = 100;
+
+fn glicko2_g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi * phi / (PI * PI)).sqrt()
+}
+
+fn glicko2_expected(mu: f64, opponent_mu: f64, opponent_phi: f64) -> f64 {
+    1.0 / (1.0 + (-glicko2_g(opponent_phi) * (mu - opponent_mu)).exp())
+}
+
+/// A match is a rating period: players who sat it out only grow more uncertain.
+fn rate_glicko2(
+    settings: &Glicko2Settings,
+    states: &mut HashMap<String, PlayerState>,
+    games: &MatchGames,
+) {
+    let before = states.clone();
+    let scaled = |state: &PlayerState| {
+        (
+            (state.rating - settings.initial_rating) / GLICKO2_SCALE,
+            state.deviation / GLICKO2_SCALE,
+        )
+    };
+    let max_phi = settings.initial_deviation / GLICKO2_SCALE;
+    let by_player = games.by_player();
+
+    for (player, state) in states.iter_mut() {
+        let (mu, phi) = scaled(&before[player]);
+        let results = by_player
+            .get(player.as_str())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let mut variance_inverse = 0.0;
+        let mut improvement = 0.0;
+        for (opponent, score) in results {
+            let (opponent_mu, opponent_phi) = scaled(&before[*opponent]);
+            let g = glicko2_g(opponent_phi);
+            let expected = glicko2_expected(mu, opponent_mu, opponent_phi);
+            variance_inverse += g * g * expected * (1.0 - expected);
+            improvement += g * (score - expected);
+        }
+
+        // Games against opponents so far apart that the result was certain tell nothing either
+        if variance_inverse <= 0.0 {
+            state.deviation = (phi * phi + state.volatility * state.volatility)
+                .sqrt()
+                .min(max_phi)
+                * GLICKO2_SCALE;
+            continue;
+        }
+        let variance = 1.0 / variance_inverse;
+        let delta = variance * improvement;
+
+        let volatility = glicko2_volatility(settings.tau, state.volatility, phi, variance, delta);
+        let phi_star = (phi * phi + volatility * volatility).sqrt();
+        let new_phi = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / variance).sqrt();
+        let new_mu = mu + new_phi * new_phi * improvement;
+
+        state.rating = new_mu * GLICKO2_SCALE + settings.initial_rating;
+        state.deviation = new_phi * GLICKO2_SCALE;
+        state.volatility = volatility;
+    }
+}
+
+/// New volatility, found with the Illinois algorithm from step 5 of Glickman's paper.
+fn glicko2_volatility(tau: f64, volatility: f64, phi: f64, variance: f64, delta: f64) -> f64 {
+    let a = (volatility * volatility).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        ex * (delta * delta - phi * phi - variance - ex)
+            / (2.0 * (phi * phi + variance + ex).powi(2))
+            - (x - a) / (tau * tau)
+    };
+
+    let mut lower = a;
+    let mut upper = if delta * delta > phi * phi + variance {
+        (delta * delta - phi * phi - variance).ln()
+    } else {
+        let mut k = 1.0;
+        while f(a - k * tau) < 0.0 && k < GLICKO2_ITERATIONS as f64 {
+            k += 1.0;
+        }
+        a - k * tau
+    };
+
+    let mut f_lower = f(lower);
+    let mut f_upper = f(upper);
+    for _ in 0..GLICKO2_ITERATIONS {
+        if (upper - lower).abs() <= GLICKO2_CONVERGENCE {
+            break;
+        }
+
+        let candidate = lower + (lower - upper) * f_lower / (f_upper - f_lower);
+        let f_candidate = f(candidate);
+        if f_candidate * f_upper <= 0.0 {
+            lower = upper;
+            f_lower = f_upper;
+        } else {
+            f_lower /= 2.0;
+        }
+        upper = candidate;
+        f_upper = f_candidate;
+    }
+
+    (lower / 2.0).exp()
+}
+
+fn normal_pdf(x: f64) -> f64 {
+    (-x * x / 2.0).exp() / (2.0 * PI).sqrt()
+}
+
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / SQRT_2)
+}
+
+/// Complementary error function, with a fractional error below 1.2e-7.
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let r = t
+        * (-z * z - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+fn inverse_normal_cdf(probability: f64) -> f64 {
+    let (mut low, mut high) = (-10.0, 10.0);
+    for _ in 0..100 {
+        let middle = (low + high) / 2.0;
+        if normal_cdf(middle) < probability {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// Mean and variance corrections for a win, `diff` and `margin` scaled by the performance spread.
+fn trueskill_win(diff: f64, margin: f64) -> (f64, f64) {
+    let x = diff - margin;
+    let denominator = normal_cdf(x);
+    let v = if denominator > f64::MIN_POSITIVE {
+        normal_pdf(x) / denominator
+    } else {
+        -x
+    };
+    (v, (v * (v + x)).clamp(f64::EPSILON, 1.0 - f64::EPSILON))
+}
+
+fn trueskill_draw(diff: f64, margin: f64) -> (f64, f64) {
+    let abs_diff = diff.abs();
+    let (a, b) = (margin - abs_diff, -margin - abs_diff);
+    let denominator = normal_cdf(a) - normal_cdf(b);
+    if denominator <= f64::MIN_POSITIVE {
+        return (a * diff.signum(), 1.0);
+    }
+
+    let v = (normal_pdf(b) - normal_pdf(a)) / denominator;
+    let w = v * v + (a * normal_pdf(a) - b * normal_pdf(b)) / denominator;
+    (
+        v * if diff < 0.0 { -1.0 } else { 1.0 },
+        w.clamp(f64::EPSILON, 1.0 - f64::EPSILON),
+    )
+}
+
+/// TrueSkill applied one game at a time: players ranked next to each other for placements, which
+/// is how the full free-for-all factor graph chains them, or every duel in the order it happened.
+fn rate_trueskill(
+    settings: &TrueSkillSettings,
+    states: &mut HashMap<String, PlayerState>,
+    games: &MatchGames,
+) {
+    for player in games.participants.iter() {
+        if let Some(state) = states.get_mut(player) {
+            state.deviation =
+                (state.deviation * state.deviation + settings.tau * settings.tau).sqrt();
+        }
+    }
+
+    let draw_margin =
+        inverse_normal_cdf((settings.draw_probability + 1.0) / 2.0) * SQRT_2 * settings.beta;
+    let ordered_games = if games.adjacent_games.is_empty() {
+        &games.games
+    } else {
+        &games.adjacent_games
+    };
+
+    for game in ordered_games.iter() {
+        let (winner, loser) = (&states[&game.player], &states[&game.opponent]);
+        let winner_variance = winner.deviation * winner.deviation;
+        let loser_variance = loser.deviation * loser.deviation;
+        let c = (2.0 * settings.beta * settings.beta + winner_variance + loser_variance).sqrt();
+
+        let diff = (winner.rating - loser.rating) / c;
+        let (v, w) = if game.score == 0.5 {
+            trueskill_draw(diff, draw_margin / c)
+        } else {
+            trueskill_win(diff, draw_margin / c)
+        };
+
+        for (player, variance, direction) in [
+            (&game.player, winner_variance, 1.0),
+            (&game.opponent, loser_variance, -1.0),
+        ] {
+            if let Some(state) = states.get_mut(player) {
+                state.rating += direction * variance / c * v;
+                state.deviation = (variance * (1.0 - variance / (c * c) * w)).max(0.0).sqrt();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::parser::Parser;
+
+    /// A stored match between Alice and Bob, each killing the other `kills` times.
+    fn duel(index: usize, alice_kills: usize, bob_kills: usize) -> StoredMatch {
+        let mut log = String::from(
+            "  0:00 InitGame: \\sv_hostname\\Test\n  0:01 ClientUserinfoChanged: 2 n\\Alice\\t\\0\n  0:01 ClientUserinfoChanged: 3 n\\Bob\\t\\0\n",
+        );
+        log.push_str(&"  0:05 Kill: 2 3 10: Alice killed Bob by MOD_RAILGUN\n".repeat(alice_kills));
+        log.push_str(&"  0:06 Kill: 3 2 10: Bob killed Alice by MOD_RAILGUN\n".repeat(bob_kills));
+        log.push_str("  0:09 ShutdownGame:\n");
+
+        StoredMatch {
+            id: format!("upload-{index}"),
+            upload_id: "upload".to_string(),
+            match_key: format!("game_{index}"),
+            uploaded_at: 0,
+            record: Parser::parse_bytes(log.as_bytes())
+                .unwrap()
+                .into_values()
+                .next()
+                .unwrap(),
+        }
+    }
+
+    fn ratings(
+        stored_matches: &[StoredMatch],
+        algorithm: RatingAlgorithm,
+    ) -> (PlayerRating, PlayerRating) {
+        let settings = RatingSettings::default().with_choices(Some(algorithm), None);
+        let table = RatingTable::compute(stored_matches, &settings);
+
+        (
+            table.player("Alice").unwrap().clone(),
+            table.player("Bob").unwrap().clone(),
+        )
+    }
+
+    fn assert_close(value: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (value - expected).abs() <= tolerance,
+            "{value} is not within {tolerance} of {expected}"
+        );
+    }
+
+    #[test]
+    fn glicko2_follows_glickmans_example() {
+        let state = |rating: f64, deviation: f64| PlayerState {
+            rating,
+            deviation,
+            volatility: 0.06,
+        };
+        let mut states = HashMap::from([
+            ("player".to_string(), state(1500.0, 200.0)),
+            ("a".to_string(), state(1400.0, 30.0)),
+            ("b".to_string(), state(1550.0, 100.0)),
+            ("c".to_string(), state(1700.0, 300.0)),
+        ]);
+        let game = |opponent: &str, score: f64| Game {
+            player: "player".to_string(),
+            opponent: opponent.to_string(),
+            score,
+        };
+        let games = MatchGames {
+            participants: ["player", "a", "b", "c"].map(String::from).to_vec(),
+            games: vec![game("a", 1.0), game("b", 0.0), game("c", 0.0)],
+            adjacent_games: Vec::new(),
+        };
+
+        rate_glicko2(&Glicko2Settings::default(), &mut states, &games);
+
+        let player = &states["player"];
+        assert_close(player.rating, 1464.06, 0.01);
+        assert_close(player.deviation, 151.52, 0.01);
+        assert_close(player.volatility, 0.05999, 0.00001);
+    }
+
+    #[test]
+    fn glicko2_survives_certain_results() {
+        let mut states = HashMap::from([
+            (
+                "strong".to_string(),
+                PlayerState {
+                    rating: 1_000_000.0,
+                    deviation: 30.0,
+                    volatility: 0.06,
+                },
+            ),
+            (
+                "weak".to_string(),
+                PlayerState {
+                    rating: 1500.0,
+                    deviation: 30.0,
+                    volatility: 0.06,
+                },
+            ),
+        ]);
+        let games = MatchGames {
+            participants: ["strong", "weak"].map(String::from).to_vec(),
+            games: vec![Game {
+                player: "strong".to_string(),
+                opponent: "weak".to_string(),
+                score: 1.0,
+            }],
+            adjacent_games: Vec::new(),
+        };
+
+        rate_glicko2(&Glicko2Settings::default(), &mut states, &games);
+
+        assert!(states
+            .values()
+            .all(|state| state.rating.is_finite() && state.deviation.is_finite()));
+    }
+
+    #[test]
+    fn glicko2_volatility_search_ends_for_any_tau() {
+        for tau in [1e-9, 0.5, 1e9] {
+            assert!(glicko2_volatility(tau, 0.06, 1.0, 1.0, 0.1).is_finite());
+        }
+    }
+
+    #[test]
+    fn elo_moves_ratings_on_wins_only() {
+        let (alice, bob) = ratings(&[duel(0, 2, 1)], RatingAlgorithm::Elo);
+        assert_close(alice.rating, 1516.0, 1e-9);
+        assert_close(bob.rating, 1484.0, 1e-9);
+        assert_eq!(alice.deviation, None);
+
+        let (alice, bob) = ratings(&[duel(0, 1, 1)], RatingAlgorithm::Elo);
+        assert_close(alice.rating, 1500.0, 1e-9);
+        assert_close(bob.rating, 1500.0, 1e-9);
+
+        // A draw against a stronger player gains rating
+        let (alice, bob) = ratings(&[duel(0, 1, 0), duel(1, 1, 1)], RatingAlgorithm::Elo);
+        assert!(alice.history[1].change < 0.0);
+        assert!(bob.history[1].change > 0.0);
+        assert_close(alice.history[1].change, -bob.history[1].change, 1e-9);
+    }
+
+    #[test]
+    fn trueskill_matches_the_reference_implementation() {
+        // Values of `trueskill.rate_1vs1` with the default environment
+        let (alice, bob) = ratings(&[duel(0, 3, 1)], RatingAlgorithm::TrueSkill);
+        assert_close(alice.rating, 29.396, 0.001);
+        assert_close(bob.rating, 20.604, 0.001);
+        assert_close(alice.deviation.unwrap(), 7.171, 0.001);
+        assert_close(bob.deviation.unwrap(), 7.171, 0.001);
+
+        let (alice, bob) = ratings(&[duel(0, 2, 2)], RatingAlgorithm::TrueSkill);
+        assert_close(alice.rating, 25.0, 0.001);
+        assert_close(bob.rating, 25.0, 0.001);
+        assert_close(alice.deviation.unwrap(), 6.458, 0.001);
+    }
+
+    #[test]
+    fn settings_the_algorithms_cant_use_are_rejected() {
+        assert!(RatingSettings::default().validate().is_ok());
+
+        let mut settings = RatingSettings::default();
+        settings.glicko2.tau = 0.0;
+        assert!(settings.validate().unwrap_err().contains("glicko2.tau"));
+
+        let mut settings = RatingSettings::default();
+        settings.trueskill.beta = -1.0;
+        assert!(settings.validate().unwrap_err().contains("trueskill.beta"));
+
+        let mut settings = RatingSettings::default();
+        settings.glicko2.initial_deviation = f64::NAN;
+        assert!(settings
+            .validate()
+            .unwrap_err()
+            .contains("glicko2.initial_deviation"));
+    }
+}
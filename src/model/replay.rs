@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+
+use crate::model::parser::{KillEvent, MatchRecord, RankingPosition};
+
+/// A match's kill events laid out to be played back, times in seconds since the server started.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub start_time: u32,
+    pub end_time: u32,
+    /// Kills in the order they happened.
+    pub kills: Vec<KillEvent>,
+    players: Vec<String>,
+}
+
+impl Replay {
+    pub fn from_record(record: &MatchRecord) -> Replay {
+        let start_time = record
+            .metadata
+            .start_time
+            .or_else(|| record.kill_events.first().map(|kill| kill.time))
+            .unwrap_or_default();
+        let end_time = record
+            .metadata
+            .end_time
+            .or_else(|| record.kill_events.last().map(|kill| kill.time))
+            .unwrap_or(start_time)
+            .max(start_time);
+
+        Replay {
+            start_time,
+            end_time,
+            kills: record.kill_events.clone(),
+            players: record
+                .ranking
+                .iter()
+                .map(|ranking_position| ranking_position.player.clone())
+                .collect(),
+        }
+    }
+
+    pub fn duration(&self) -> u32 {
+        self.end_time - self.start_time
+    }
+
+    /// Kills that happened up to `elapsed` seconds into the match, in log order up to the first
+    /// one logged later, since a clock going back leaves the times out of order.
+    pub fn kills_until(&self, elapsed: u32) -> &[KillEvent] {
+        let time = self.start_time.saturating_add(elapsed);
+        let played = self
+            .kills
+            .iter()
+            .take_while(|kill| kill.time <= time)
+            .count();
+        &self.kills[..played]
+    }
+
+    /// Scoreboard `elapsed` seconds into the match, everyone who ends up ranked listed from the
+    /// start and ties ordered by name.
+    pub fn standings_at(&self, elapsed: u32) -> Vec<RankingPosition> {
+        let mut scores: BTreeMap<&str, i16> = self
+            .players
+            .iter()
+            .map(|player| (player.as_str(), 0))
+            .collect();
+        for (player, points) in self
+            .kills_until(elapsed)
+            .iter()
+            .filter_map(KillEvent::score_change)
+        {
+            *scores.entry(player).or_default() += points;
+        }
+
+        let mut scores = scores.into_iter().collect::<Vec<(&str, i16)>>();
+        scores.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+        scores
+            .into_iter()
+            .enumerate()
+            .map(|(i, (player, kills))| RankingPosition {
+                player: player.to_string(),
+                kills,
+                position: (i + 1) as u16,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::parser::Parser;
+
+    fn replay(kills: &[&str]) -> Replay {
+        let mut log = vec![
+            r"  1:00 InitGame: \sv_hostname\Test\g_gametype\0\mapname\q3dm17",
+            r"  1:01 ClientUserinfoChanged: 2 n\Isgalamido\t\0\model\uriel/zael",
+            r"  1:01 ClientUserinfoChanged: 3 n\Zeh\t\0\model\sarge/default",
+        ];
+        log.extend(kills);
+        log.push("  2:00 ShutdownGame:");
+        let record = Parser::parse_bytes(log.join("\n").as_bytes())
+            .unwrap()
+            .into_values()
+            .next()
+            .unwrap();
+
+        Replay::from_record(&record)
+    }
+
+    fn scores(standings: Vec<RankingPosition>) -> Vec<(String, i16)> {
+        standings
+            .into_iter()
+            .map(|ranking_position| (ranking_position.player, ranking_position.kills))
+            .collect()
+    }
+
+    #[test]
+    fn plays_kills_back_from_the_match_start() {
+        let replay = replay(&[
+            "  1:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+            "  1:20 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT",
+            "  1:30 Kill: 3 2 10: Zeh killed Isgalamido by MOD_RAILGUN",
+        ]);
+
+        assert_eq!(replay.duration(), 60);
+        assert_eq!(replay.kills_until(9).len(), 0);
+        assert_eq!(replay.kills_until(10).len(), 1);
+        assert_eq!(replay.kills_until(60).len(), 3);
+        assert_eq!(replay.kills_until(u32::MAX).len(), 3);
+    }
+
+    #[test]
+    fn scrubs_in_log_order_when_the_clock_went_back() {
+        let replay = replay(&[
+            "  1:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+            "  1:20 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+            "  1:15 Kill: 3 2 10: Zeh killed Isgalamido by MOD_RAILGUN",
+            "  1:30 Kill: 3 2 10: Zeh killed Isgalamido by MOD_RAILGUN",
+        ]);
+
+        assert_eq!(replay.kills_until(15).len(), 1);
+        assert_eq!(replay.kills_until(20).len(), 3);
+        assert_eq!(replay.kills_until(30).len(), 4);
+    }
+
+    #[test]
+    fn standings_list_every_player_from_the_start() {
+        let replay = replay(&[
+            "  1:10 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT",
+            "  1:20 Kill: 3 3 7: Zeh killed Zeh by MOD_ROCKET_SPLASH",
+            "  1:30 Kill: 3 2 10: Zeh killed Isgalamido by MOD_RAILGUN",
+        ]);
+
+        assert_eq!(
+            scores(replay.standings_at(0)),
+            [("Isgalamido".to_string(), 0), ("Zeh".to_string(), 0)]
+        );
+        assert_eq!(
+            scores(replay.standings_at(20)),
+            [("Zeh".to_string(), 0), ("Isgalamido".to_string(), -1)]
+        );
+        assert_eq!(
+            scores(replay.standings_at(60)),
+            [("Zeh".to_string(), 1), ("Isgalamido".to_string(), -1)]
+        );
+        assert_eq!(
+            replay
+                .standings_at(60)
+                .iter()
+                .map(|ranking_position| ranking_position.position)
+                .collect::<Vec<u16>>(),
+            [1, 2]
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::parser::MatchRecord;
+
+/// A player's kill score right after `time`, in seconds since the server started.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScorePoint {
+    pub time: u32,
+    pub score: i16,
+}
+
+/// Moment a player took the lead alone, ties not counting as a change.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LeadChange {
+    pub time: u32,
+    pub leader: String,
+    pub score: i16,
+}
+
+/// Kill score of every player along a match, replayed from its kill events.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScoreTimeline {
+    pub start_time: u32,
+    pub end_time: u32,
+    /// Points of each player, starting at zero and with one more point per kill that changed it.
+    pub series: BTreeMap<String, Vec<ScorePoint>>,
+    pub lead_changes: Vec<LeadChange>,
+}
+
+impl ScoreTimeline {
+    /// Scores follow the parser rules, see `KillEvent::score_change`.
+    pub fn from_record(record: &MatchRecord) -> ScoreTimeline {
+        let start_time = record
+            .metadata
+            .start_time
+            .or_else(|| record.kill_events.first().map(|kill| kill.time))
+            .unwrap_or_default();
+        let end_time = record
+            .metadata
+            .end_time
+            .or_else(|| record.kill_events.last().map(|kill| kill.time))
+            .unwrap_or(start_time)
+            .max(start_time);
+
+        let mut scores: BTreeMap<String, i16> = record
+            .ranking
+            .iter()
+            .map(|ranking_position| (ranking_position.player.clone(), 0))
+            .collect();
+        let mut series: BTreeMap<String, Vec<ScorePoint>> = scores
+            .keys()
+            .map(|player| {
+                (
+                    player.clone(),
+                    vec![ScorePoint {
+                        time: start_time,
+                        score: 0,
+                    }],
+                )
+            })
+            .collect();
+        let mut lead_changes = Vec::new();
+        let mut leader: Option<String> = None;
+
+        for kill in record.kill_events.iter() {
+            let (player, points) = match kill.score_change() {
+                Some(score_change) => score_change,
+                None => continue,
+            };
+
+            let score = scores.entry(player.to_string()).or_default();
+            *score += points;
+            series
+                .entry(player.to_string())
+                .or_insert_with(|| {
+                    vec![ScorePoint {
+                        time: start_time,
+                        score: 0,
+                    }]
+                })
+                .push(ScorePoint {
+                    time: kill.time,
+                    score: *score,
+                });
+
+            if let Some((sole_leader, score)) = ScoreTimeline::sole_leader(&scores) {
+                if leader.as_deref() != Some(sole_leader) {
+                    leader = Some(sole_leader.to_string());
+                    lead_changes.push(LeadChange {
+                        time: kill.time,
+                        leader: sole_leader.to_string(),
+                        score,
+                    });
+                }
+            }
+        }
+
+        ScoreTimeline {
+            start_time,
+            end_time,
+            series,
+            lead_changes,
+        }
+    }
+
+    /// Lowest and highest score reached by anyone, zero always included.
+    pub fn score_range(&self) -> (i16, i16) {
+        self.series
+            .values()
+            .flatten()
+            .fold((0, 0), |(min, max), point| {
+                (min.min(point.score), max.max(point.score))
+            })
+    }
+
+    fn sole_leader(scores: &BTreeMap<String, i16>) -> Option<(&str, i16)> {
+        let top_score = *scores.values().max()?;
+        let mut leaders = scores.iter().filter(|(_, score)| **score == top_score);
+
+        match (leaders.next(), leaders.next()) {
+            (Some((player, score)), None) => Some((player.as_str(), *score)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::parser::Parser;
+
+    fn timeline(kills: &[&str]) -> ScoreTimeline {
+        let mut log = vec![
+            r"  1:00 InitGame: \sv_hostname\Test\g_gametype\0\mapname\q3dm17",
+            r"  1:01 ClientUserinfoChanged: 2 n\Isgalamido\t\0\model\uriel/zael",
+            r"  1:01 ClientUserinfoChanged: 3 n\Zeh\t\0\model\sarge/default",
+        ];
+        log.extend(kills);
+        log.push("  2:00 ShutdownGame:");
+        let record = Parser::parse_bytes(log.join("\n").as_bytes())
+            .unwrap()
+            .into_values()
+            .next()
+            .unwrap();
+
+        ScoreTimeline::from_record(&record)
+    }
+
+    fn leaders(timeline: &ScoreTimeline) -> Vec<(u32, &str, i16)> {
+        timeline
+            .lead_changes
+            .iter()
+            .map(|lead_change| {
+                (
+                    lead_change.time,
+                    lead_change.leader.as_str(),
+                    lead_change.score,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ties_do_not_change_the_lead() {
+        let timeline = timeline(&[
+            "  1:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+            "  1:20 Kill: 3 2 10: Zeh killed Isgalamido by MOD_RAILGUN",
+            "  1:30 Kill: 3 2 10: Zeh killed Isgalamido by MOD_RAILGUN",
+            "  1:40 Kill: 1022 3 22: <world> killed Zeh by MOD_TRIGGER_HURT",
+            "  1:50 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+        ]);
+
+        assert_eq!(
+            leaders(&timeline),
+            [
+                (70, "Isgalamido", 1),
+                (90, "Zeh", 2),
+                (110, "Isgalamido", 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn losing_a_point_to_the_world_can_hand_the_lead_over() {
+        let timeline = timeline(&[
+            "  1:10 Kill: 1022 3 22: <world> killed Zeh by MOD_TRIGGER_HURT",
+            "  1:20 Kill: 3 3 7: Zeh killed Zeh by MOD_ROCKET_SPLASH",
+        ]);
+
+        assert_eq!(leaders(&timeline), [(70, "Isgalamido", 0)]);
+    }
+
+    #[test]
+    fn series_start_at_zero_and_only_move_on_score_changes() {
+        let timeline = timeline(&[
+            "  1:10 Kill: 2 3 10: Isgalamido killed Zeh by MOD_RAILGUN",
+            "  1:20 Kill: 3 3 7: Zeh killed Zeh by MOD_ROCKET_SPLASH",
+            "  1:30 Kill: 1022 3 22: <world> killed Zeh by MOD_TRIGGER_HURT",
+        ]);
+
+        assert_eq!((timeline.start_time, timeline.end_time), (60, 120));
+        assert_eq!(
+            timeline.series["Isgalamido"],
+            [
+                ScorePoint { time: 60, score: 0 },
+                ScorePoint { time: 70, score: 1 }
+            ]
+        );
+        assert_eq!(
+            timeline.series["Zeh"],
+            [
+                ScorePoint { time: 60, score: 0 },
+                ScorePoint {
+                    time: 90,
+                    score: -1
+                }
+            ]
+        );
+        assert_eq!(timeline.score_range(), (-1, 1));
+    }
+}
@@ -0,0 +1,670 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt;
+use std::io::{self, BufRead};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::dialect::{LogDialect, Quake3};
+use crate::model::parser::{format_game_time, ParserState};
+
+/// Lines compared with each new line to find duplicates.
+const DUPLICATE_WINDOW: usize = 8;
+/// A forward jump at least this long back past an earlier clock means two clocks alternate.
+const INTERLEAVED_JUMP_SECS: u32 = 10;
+/// Client slot the logs give to `<world>` kills.
+const WORLD_ID: &str = "1022";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Unusual but harmless.
+    Info,
+    /// The stats are likely right, but something is off.
+    Warning,
+    /// The stats of the match can't be trusted.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// No `m:ss` time in front of the line.
+    MissingTime,
+    /// The time went back in the middle of a match.
+    TimeWentBackwards,
+    /// Times alternate between two clocks, as when two servers write to the same file.
+    InterleavedTimestamps,
+    /// The time went back between matches, as when the server restarts.
+    ClockReset,
+    /// A kill by or of a client slot nobody connected to in the match.
+    UnknownClient,
+    /// A kill naming a client differently from its last userinfo.
+    NameMismatch,
+    /// A match event before any `InitGame`, or after the match ended.
+    OutsideMatch,
+    /// A match started while the previous one never ended.
+    MissingShutdown,
+    /// The log ends in the middle of a match.
+    UnterminatedMatch,
+    /// The same line, time included, shortly after itself.
+    DuplicateLine,
+    /// A recognised event line whose content couldn't be understood.
+    MalformedLine,
+}
+
+impl AnomalyKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            AnomalyKind::TimeWentBackwards
+            | AnomalyKind::InterleavedTimestamps
+            | AnomalyKind::UnknownClient
+            | AnomalyKind::OutsideMatch
+            | AnomalyKind::MalformedLine => Severity::Error,
+            AnomalyKind::MissingTime
+            | AnomalyKind::NameMismatch
+            | AnomalyKind::MissingShutdown
+            | AnomalyKind::DuplicateLine => Severity::Warning,
+            AnomalyKind::ClockReset | AnomalyKind::UnterminatedMatch => Severity::Info,
+        }
+    }
+}
+
+/// Something wrong found on a line, `line` counting from 1.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Anomaly {
+    pub line: u64,
+    pub severity: Severity,
+    pub kind: AnomalyKind,
+    pub message: String,
+    pub content: String,
+    /// Parser key of the match it affects, e.g. `game_3`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_key: Option<String>,
+}
+
+/// Anomalies of a log, in the order of its lines.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ValidationReport {
+    pub lines: u64,
+    /// `InitGame` lines seen.
+    pub matches: usize,
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl ValidationReport {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.anomalies
+            .iter()
+            .filter(|anomaly| anomaly.severity == severity)
+            .count()
+    }
+
+    /// Nothing worse than an info.
+    pub fn is_clean(&self) -> bool {
+        self.anomalies
+            .iter()
+            .all(|anomaly| anomaly.severity == Severity::Info)
+    }
+
+    /// Keys of the matches with errors, whose stats shouldn't be trusted.
+    pub fn untrusted_matches(&self) -> BTreeSet<String> {
+        self.anomalies
+            .iter()
+            .filter(|anomaly| anomaly.severity == Severity::Error)
+            .filter_map(|anomaly| anomaly.match_key.clone())
+            .collect()
+    }
+}
+
+/// Match opened by the last `InitGame`.
+struct OpenMatch {
+    key: String,
+    start_line: u64,
+    /// Name of each connected client slot, once it has one.
+    clients: HashMap<String, Option<String>>,
+    /// Time right before the clock last went back.
+    earlier_clock: Option<u32>,
+    interleaved: bool,
+}
+
+/// Checks a log line by line for signs of crashes, concurrent writers and other corruption, the
+/// way `ParserState` reads it.
+#[derive(Default)]
+pub struct LogValidator {
+    lines: u64,
+    started_matches: usize,
+    current_match: Option<OpenMatch>,
+    /// Whether the lines outside any match since the last one were already reported.
+    outside_reported: bool,
+    last_time: Option<u32>,
+    recent_lines: VecDeque<(u64, String)>,
+    anomalies: Vec<Anomaly>,
+}
+
+impl LogValidator {
+    pub fn new() -> Self {
+        LogValidator::default()
+    }
+
+    /// Validates a whole log held in memory.
+    pub fn validate_bytes(log: &[u8]) -> ValidationReport {
+        // Reading from memory can't fail
+        LogValidator::validate(log).unwrap_or_default()
+    }
+
+    pub fn validate<R: BufRead>(mut reader: R) -> io::Result<ValidationReport> {
+        let mut validator = LogValidator::new();
+        let mut raw_line = Vec::new();
+
+        while reader.read_until(b'\n', &mut raw_line)? > 0 {
+            validator.push_line(&String::from_utf8_lossy(&raw_line));
+            raw_line.clear();
+        }
+
+        Ok(validator.finish())
+    }
+
+    /// Feeds a single line.
+    pub fn push_line(&mut self, line: &str) {
+        let line = line.trim();
+        self.lines += 1;
+        if line.is_empty() {
+            return;
+        }
+
+        let (time, event) = ParserState::split_time(line);
+        let separator =
+            event.starts_with("------------------------------------------------------------");
+
+        // Picking up two items of a kind in a second is common
+        if !separator && !event.starts_with("Item:") {
+            self.check_duplicate(line);
+        }
+        match time {
+            Some(time) => self.check_time(line, time, event, separator),
+            None => self.report(
+                line,
+                AnomalyKind::MissingTime,
+                "No m:ss time in front of the line".to_string(),
+            ),
+        }
+
+        if event.starts_with("InitGame:") {
+            self.start_match(line);
+        } else if event.starts_with("ShutdownGame:") || separator {
+            self.current_match = None;
+        } else if event.starts_with("Kill:") {
+            self.check_kill(line, event);
+        } else if let Some(userinfo) = event.strip_prefix("ClientUserinfoChanged:") {
+            self.register_client(line, event, userinfo);
+        } else if let Some(client) = ["ClientConnect:", "ClientUserinfo:", "ClientBegin:"]
+            .iter()
+            .find_map(|prefix| event.strip_prefix(prefix))
+        {
+            self.register_client(line, event, client);
+        } else if let Some(client) = event.strip_prefix("ClientDisconnect:") {
+            if let Some(current_match) = self.current_match.as_mut() {
+                current_match.clients.remove(client.trim());
+            }
+        } else if event.starts_with("say:")
+            || event.starts_with("sayteam:")
+            || event.starts_with("Item:")
+        {
+            self.check_in_match(line, event);
+        }
+    }
+
+    /// Report of every line fed so far, noting a match left open.
+    pub fn finish(mut self) -> ValidationReport {
+        if let Some(current_match) = self.current_match.take() {
+            self.anomalies.push(Anomaly {
+                line: self.lines,
+                severity: AnomalyKind::UnterminatedMatch.severity(),
+                kind: AnomalyKind::UnterminatedMatch,
+                message: format!(
+                    "The log ends in the match started at line {}, which may still be going on",
+                    current_match.start_line
+                ),
+                content: String::new(),
+                match_key: Some(current_match.key),
+            });
+        }
+
+        ValidationReport {
+            lines: self.lines,
+            matches: self.started_matches,
+            anomalies: self.anomalies,
+        }
+    }
+
+    fn report(&mut self, line: &str, kind: AnomalyKind, message: String) {
+        self.anomalies.push(Anomaly {
+            line: self.lines,
+            severity: kind.severity(),
+            kind,
+            message,
+            content: line.to_string(),
+            match_key: self
+                .current_match
+                .as_ref()
+                .map(|current_match| current_match.key.clone()),
+        });
+    }
+
+    fn check_duplicate(&mut self, line: &str) {
+        let duplicate_of = self
+            .recent_lines
+            .iter()
+            .find(|(_, recent_line)| recent_line == line)
+            .map(|(line_number, _)| *line_number);
+        if let Some(duplicate_of) = duplicate_of {
+            self.report(
+                line,
+                AnomalyKind::DuplicateLine,
+                format!("Same line as line {duplicate_of}"),
+            );
+        }
+
+        self.recent_lines.push_back((self.lines, line.to_string()));
+        if self.recent_lines.len() > DUPLICATE_WINDOW {
+            self.recent_lines.pop_front();
+        }
+    }
+
+    fn check_time(&mut self, line: &str, time: u32, event: &str, separator: bool) {
+        let last_time = match self.last_time.replace(time) {
+            Some(last_time) => last_time,
+            None => return,
+        };
+
+        if time < last_time {
+            let between_matches =
+                self.current_match.is_none() || separator || event.starts_with("InitGame:");
+            if between_matches {
+                let message = format!(
+                    "The clock went back from {} to {}, the server probably restarted",
+                    format_game_time(last_time),
+                    format_game_time(time)
+                );
+                return self.report(line, AnomalyKind::ClockReset, message);
+            }
+
+            if let Some(current_match) = self.current_match.as_mut() {
+                current_match.earlier_clock = Some(last_time);
+                // Interleaved clocks go back on every switch, once is enough
+                if current_match.interleaved {
+                    return;
+                }
+            }
+            let message = format!(
+                "The time went back from {} to {} in the middle of a match",
+                format_game_time(last_time),
+                format_game_time(time)
+            );
+            return self.report(line, AnomalyKind::TimeWentBackwards, message);
+        }
+
+        let switched_back = self
+            .current_match
+            .as_mut()
+            .filter(|current_match| !current_match.interleaved)
+            .and_then(|current_match| {
+                let earlier_clock = current_match.earlier_clock.filter(|earlier_clock| {
+                    last_time < *earlier_clock
+                        && time >= *earlier_clock
+                        && time - last_time >= INTERLEAVED_JUMP_SECS
+                })?;
+                current_match.interleaved = true;
+                Some(earlier_clock)
+            });
+        if let Some(earlier_clock) = switched_back {
+            let message = format!(
+                "The time jumped from {} back past {}, two clocks seem to write to this log",
+                format_game_time(last_time),
+                format_game_time(earlier_clock)
+            );
+            self.report(line, AnomalyKind::InterleavedTimestamps, message);
+        }
+    }
+
+    fn start_match(&mut self, line: &str) {
+        if let Some(previous_match) = self.current_match.as_ref() {
+            let message = format!(
+                "The match started at line {} never ended, the next one started without a ShutdownGame",
+                previous_match.start_line
+            );
+            self.report(line, AnomalyKind::MissingShutdown, message);
+        }
+
+        self.current_match = Some(OpenMatch {
+            key: format!("game_{}", self.started_matches),
+            start_line: self.lines,
+            clients: HashMap::new(),
+            earlier_clock: None,
+            interleaved: false,
+        });
+        self.started_matches += 1;
+        self.outside_reported = false;
+    }
+
+    /// Reports the first match event of each stretch of lines outside any match.
+    fn check_in_match(&mut self, line: &str, event: &str) -> bool {
+        if self.current_match.is_some() {
+            return true;
+        }
+
+        if !self.outside_reported {
+            self.outside_reported = true;
+            let command = event.split(':').next().unwrap_or_default();
+            let message = format!("{command} line outside any match, its InitGame is missing");
+            self.report(line, AnomalyKind::OutsideMatch, message);
+        }
+        false
+    }
+
+    // ClientUserinfoChanged: 2 n\Isgalamido\t\0...
+    // ClientConnect: 2
+    fn register_client(&mut self, line: &str, event: &str, client: &str) {
+        if !self.check_in_match(line, event) {
+            return;
+        }
+
+        let client_id = match client.split_whitespace().next() {
+            Some(client_id) => client_id.to_string(),
+            None => {
+                return self.report(
+                    line,
+                    AnomalyKind::MalformedLine,
+                    "No client slot".to_string(),
+                )
+            }
+        };
+        let name = event
+            .starts_with("ClientUserinfoChanged:")
+            .then(|| line.split('\\').nth(1).map(String::from));
+        if name == Some(None) {
+            return self.report(
+                line,
+                AnomalyKind::MalformedLine,
+                "No player name".to_string(),
+            );
+        }
+
+        if let Some(current_match) = self.current_match.as_mut() {
+            let known_name = current_match.clients.entry(client_id).or_default();
+            if let Some(name) = name.flatten() {
+                *known_name = Some(name);
+            }
+        }
+    }
+
+    // Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT
+    fn check_kill(&mut self, line: &str, event: &str) {
+        if !self.check_in_match(line, event) {
+            return;
+        }
+
+        let mut client_ids = event
+            .trim_start_matches("Kill:")
+            .split(':')
+            .next()
+            .unwrap_or_default()
+            .split_whitespace();
+        let (killer_id, victim_id, killer, victim) = match (
+            client_ids.next(),
+            client_ids.next(),
+            Quake3.parse_kill(event),
+        ) {
+            (Some(killer_id), Some(victim_id), Some((killer, victim, _))) => {
+                (killer_id, victim_id, killer, victim)
+            }
+            _ => {
+                return self.report(
+                    line,
+                    AnomalyKind::MalformedLine,
+                    "Cannot read the kill".to_string(),
+                )
+            }
+        };
+
+        for (client_id, name) in [(killer_id, killer), (victim_id, victim)] {
+            if client_id == WORLD_ID {
+                continue;
+            }
+
+            let known_name = self
+                .current_match
+                .as_ref()
+                .map(|current_match| current_match.clients.get(client_id).cloned());
+            match known_name {
+                Some(None) => {
+                    let message = format!(
+                        "Kill naming client {client_id} ({name}), who isn't connected to the match"
+                    );
+                    self.report(line, AnomalyKind::UnknownClient, message);
+                }
+                Some(Some(Some(known_name))) if known_name != name => {
+                    let message = format!(
+                        "Client {client_id} is named {known_name}, the kill names it {name}"
+                    );
+                    self.report(line, AnomalyKind::NameMismatch, message);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INIT_GAME: &str = r"  0:00 InitGame: \sv_hostname\Test\g_gametype\0\mapname\q3dm17";
+    const ISGALAMIDO: &str = r"  0:01 ClientUserinfoChanged: 2 n\Isgalamido\t\0\model\uriel/zael";
+    const ZEH: &str = r"  0:01 ClientUserinfoChanged: 3 n\Zeh\t\0\model\sarge/default";
+    const NEXT_INIT_GAME: &str = r"  0:00 InitGame: \sv_hostname\Test\g_gametype\0\mapname\q3dm6";
+
+    fn anomalies(lines: &[&str]) -> Vec<(u64, AnomalyKind)> {
+        LogValidator::validate_bytes(lines.join("\n").as_bytes())
+            .anomalies
+            .iter()
+            .map(|anomaly| (anomaly.line, anomaly.kind))
+            .collect()
+    }
+
+    #[test]
+    fn a_clean_match_has_no_anomaly() {
+        let kill = "  0:10 Kill: 2 3 7: Isgalamido killed Zeh by MOD_ROCKET_SPLASH";
+        let world_kill = "  0:20 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT";
+
+        assert_eq!(
+            anomalies(&[
+                INIT_GAME,
+                ISGALAMIDO,
+                ZEH,
+                kill,
+                world_kill,
+                "  0:30 ShutdownGame:"
+            ]),
+            []
+        );
+    }
+
+    #[test]
+    fn reports_lines_without_time() {
+        let init_game = INIT_GAME.trim_start().trim_start_matches("0:00 ");
+
+        assert_eq!(
+            anomalies(&[init_game, "  0:30 ShutdownGame:"]),
+            [(1, AnomalyKind::MissingTime)]
+        );
+    }
+
+    #[test]
+    fn reports_the_time_going_back_in_a_match() {
+        let lines = [
+            INIT_GAME,
+            "  0:10 say: Isgalamido: hi",
+            "  0:05 say: Isgalamido: bye",
+            "  0:12 ShutdownGame:",
+        ];
+
+        assert_eq!(anomalies(&lines), [(3, AnomalyKind::TimeWentBackwards)]);
+    }
+
+    #[test]
+    fn reports_interleaved_clocks_once() {
+        let lines = [
+            INIT_GAME,
+            "  1:00 Item: 2 weapon_rocketlauncher",
+            "  0:30 Item: 3 item_armor_body",
+            "  1:10 Item: 2 ammo_rockets",
+            "  0:40 Item: 3 item_health",
+            "  1:20 Item: 2 item_quad",
+            "  1:30 ShutdownGame:",
+        ];
+
+        assert_eq!(
+            anomalies(&lines),
+            [
+                (3, AnomalyKind::TimeWentBackwards),
+                (4, AnomalyKind::InterleavedTimestamps)
+            ]
+        );
+    }
+
+    #[test]
+    fn a_short_jump_forward_is_not_an_interleaved_clock() {
+        let lines = [
+            INIT_GAME,
+            "  1:00 Item: 2 weapon_rocketlauncher",
+            "  0:58 Item: 3 item_armor_body",
+            "  1:01 Item: 2 ammo_rockets",
+            "  1:30 ShutdownGame:",
+        ];
+
+        assert_eq!(anomalies(&lines), [(3, AnomalyKind::TimeWentBackwards)]);
+    }
+
+    #[test]
+    fn the_clock_going_back_between_matches_is_a_reset() {
+        let lines = [
+            INIT_GAME,
+            "  5:00 ShutdownGame:",
+            NEXT_INIT_GAME,
+            "  0:30 ShutdownGame:",
+        ];
+
+        assert_eq!(anomalies(&lines), [(3, AnomalyKind::ClockReset)]);
+    }
+
+    #[test]
+    fn reports_kills_of_clients_not_connected() {
+        let kill = "  0:10 Kill: 2 4 7: Isgalamido killed Mocinha by MOD_ROCKET_SPLASH";
+
+        assert_eq!(
+            anomalies(&[INIT_GAME, ISGALAMIDO, kill, "  0:30 ShutdownGame:"]),
+            [(3, AnomalyKind::UnknownClient)]
+        );
+    }
+
+    #[test]
+    fn reports_kills_naming_a_client_differently() {
+        let kill = "  0:10 Kill: 2 3 7: Mocinha killed Zeh by MOD_ROCKET_SPLASH";
+
+        assert_eq!(
+            anomalies(&[INIT_GAME, ISGALAMIDO, ZEH, kill, "  0:30 ShutdownGame:"]),
+            [(4, AnomalyKind::NameMismatch)]
+        );
+    }
+
+    #[test]
+    fn reports_each_stretch_of_lines_outside_matches_once() {
+        let lines = [
+            "  0:01 say: Isgalamido: anyone?",
+            "  0:02 say: Isgalamido: hello?",
+            INIT_GAME,
+            "  0:30 ShutdownGame:",
+            "  0:31 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT",
+            "  0:32 say: Isgalamido: gg",
+        ];
+
+        assert_eq!(
+            anomalies(&lines),
+            [
+                (1, AnomalyKind::OutsideMatch),
+                (3, AnomalyKind::ClockReset),
+                (5, AnomalyKind::OutsideMatch)
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_matches_without_shutdown() {
+        assert_eq!(
+            anomalies(&[INIT_GAME, NEXT_INIT_GAME, "  0:30 ShutdownGame:"]),
+            [(2, AnomalyKind::MissingShutdown)]
+        );
+    }
+
+    #[test]
+    fn reports_a_log_ending_in_a_match() {
+        let report = LogValidator::validate_bytes([INIT_GAME, ISGALAMIDO].join("\n").as_bytes());
+
+        assert_eq!(report.anomalies.len(), 1);
+        assert_eq!(report.anomalies[0].kind, AnomalyKind::UnterminatedMatch);
+        assert_eq!(report.anomalies[0].line, 2);
+        assert_eq!(report.anomalies[0].match_key.as_deref(), Some("game_0"));
+    }
+
+    #[test]
+    fn reports_duplicates_within_the_window_only() {
+        let repeated = "  0:10 say: Isgalamido: gg";
+        let mut lines = vec![INIT_GAME, repeated, repeated];
+        let others = (0..DUPLICATE_WINDOW)
+            .map(|n| format!("  0:10 say: Zeh: {n}"))
+            .collect::<Vec<String>>();
+        lines.extend(others.iter().map(String::as_str));
+        lines.extend([repeated, "  0:30 ShutdownGame:"]);
+
+        assert_eq!(anomalies(&lines), [(3, AnomalyKind::DuplicateLine)]);
+    }
+
+    #[test]
+    fn items_picked_up_twice_are_not_duplicates() {
+        let item = "  0:10 Item: 2 ammo_rockets";
+
+        assert_eq!(
+            anomalies(&[INIT_GAME, item, item, "  0:30 ShutdownGame:"]),
+            []
+        );
+    }
+
+    #[test]
+    fn reports_malformed_event_lines() {
+        let lines = [
+            INIT_GAME,
+            "  0:01 ClientUserinfoChanged: 2",
+            "  0:02 Kill: 2",
+            "  0:30 ShutdownGame:",
+        ];
+
+        assert_eq!(
+            anomalies(&lines),
+            [
+                (2, AnomalyKind::MalformedLine),
+                (3, AnomalyKind::MalformedLine)
+            ]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use leptos::*;
+use leptos_meta::*;
+use leptos_router::*;
+
+use crate::api::players::{compare_players, get_player_names};
+use crate::error_template::{AppError, ErrorTemplate};
+use crate::links::{match_href, player_href};
+use crate::model::comparison::PlayerComparison;
+use crate::model::player::{comparison_names_from_query, kill_death_ratio, PlayerProfile};
+use crate::store::match_number;
+
+/// Weapons listed for each player.
+const TOP_WEAPONS: usize = 3;
+
+fn network_error(cx: Scope, err: ServerFnError) -> View {
+    let mut errors = Errors::default();
+    errors.insert_with_default_key(AppError::Network(err.to_string()));
+    view! { cx, <ErrorTemplate outside_errors=errors/> }.into_view(cx)
+}
+
+#[component]
+pub fn ComparePage(cx: Scope) -> impl IntoView {
+    let location = use_location(cx);
+    let selected = move || comparison_names_from_query(&location.search.get());
+    let player_names = create_resource(cx, || (), move |_| get_player_names(cx));
+    let comparison = create_resource(cx, selected, move |names| async move {
+        if names.len() < 2 {
+            Ok(None)
+        } else {
+            compare_players(cx, names).await.map(Some)
+        }
+    });
+
+    view! { cx,
+        <Title text="Compare players - Quake 3 Arena server log parser"/>
+
+        <div class="w-full flex flex-col gap-12">
+            <h1 class="text-3xl font-bold text-center">"Compare players"</h1>
+
+            <Suspense fallback=move || view! { cx, <p class="text-xl text-center">"Loading players..."</p> }>
+                {move || player_names.read(cx).map(|player_names| match player_names {
+                    Ok(player_names) if player_names.is_empty() => view! { cx,
+                        <p class="text-xl text-center">"No matches stored yet"</p>
+                    }.into_view(cx),
+                    Ok(player_names) => {
+                        let selected = selected();
+
+                        view! { cx,
+                            <form method="get" action="/compare" class="flex flex-col gap-4 items-center">
+                                <fieldset class="flex flex-row flex-wrap gap-4 justify-center">
+                                    <legend class="mb-2 text-center text-gray-300">"Pick two or more players"</legend>
+                                    {player_names.into_iter().map(|name| view! { cx,
+                                        <label class="flex flex-row gap-2 items-center px-3 py-1 rounded-lg bg-gray-700 cursor-pointer">
+                                            <input type="checkbox" name="player" value=name.clone() checked=selected.contains(&name)/>
+                                            {name.clone()}
+                                        </label>
+                                    }).collect_view(cx)}
+                                </fieldset>
+                                <button type="submit" class="px-4 py-1 rounded-lg bg-blue-600 hover:bg-blue-500 font-medium">
+                                    "Compare"
+                                </button>
+                            </form>
+                        }.into_view(cx)
+                    }
+                    Err(err) => network_error(cx, err),
+                })}
+            </Suspense>
+
+            <Suspense fallback=move || view! { cx, <p class="text-xl text-center">"Comparing..."</p> }>
+                {move || comparison.read(cx).map(|comparison| match comparison {
+                    Ok(Some(comparison)) if comparison.profiles.len() >= 2 => view! { cx,
+                        <PlayerComparisonView comparison/>
+                    }.into_view(cx),
+                    Ok(Some(_)) => view! { cx,
+                        <p class="text-xl text-center">"Only one of the picked players played any stored match"</p>
+                    }.into_view(cx),
+                    Ok(None) => ().into_view(cx),
+                    Err(err) => network_error(cx, err),
+                })}
+            </Suspense>
+        </div>
+    }
+}
+
+/// Stats, duels and shared matches of the compared players, one column each.
+#[component]
+pub fn PlayerComparisonView(cx: Scope, comparison: PlayerComparison) -> impl IntoView {
+    let names = comparison
+        .profiles
+        .iter()
+        .map(|profile| profile.name.clone())
+        .collect::<Vec<String>>();
+
+    let player_headers = move |first_column: &'static str| {
+        view! { cx,
+            <tr>
+                <th scope="col" class="px-6 py-3">{first_column}</th>
+                {names.clone().into_iter().map(|name| view! { cx,
+                    <th scope="col" class="px-6 py-3">
+                        <a href=player_href(cx, &name) class="hover:underline">{name.clone()}</a>
+                    </th>
+                }).collect_view(cx)}
+            </tr>
+        }
+    };
+
+    let stat_row = |title: &'static str, values: Vec<String>| {
+        view! { cx,
+            <tr class="border-b bg-gray-900 border-gray-700">
+                <th scope="row" class="px-6 py-4 font-medium">{title}</th>
+                {values.into_iter().map(|value| view! { cx, <td class="px-6 py-4">{value}</td> }).collect_view(cx)}
+            </tr>
+        }
+    };
+    let stat = |value: fn(&PlayerProfile) -> String| {
+        comparison
+            .profiles
+            .iter()
+            .map(value)
+            .collect::<Vec<String>>()
+    };
+
+    let weapons = comparison
+        .profiles
+        .iter()
+        .map(|profile| {
+            let total = profile.total_kills().max(1) as f64;
+            profile
+                .best_weapons
+                .iter()
+                .take(TOP_WEAPONS)
+                .map(|(means, kills)| format!("{means} ({:.0}%)", *kills as f64 / total * 100.0))
+                .collect::<Vec<String>>()
+                .join(", ")
+        })
+        .collect::<Vec<String>>();
+
+    let duels = comparison.duels.clone();
+    let shared_matches = comparison.shared_matches.clone();
+
+    view! { cx,
+        <div class="flex flex-col gap-12">
+            <div>
+                <h3 class="text-xl font-medium text-center mb-2">"Side by side"</h3>
+                <div class="relative overflow-x-auto shadow-md sm:rounded-lg">
+                    <table class="w-full text-sm text-left text-gray-400">
+                        <thead class="text-xs uppercase bg-gray-700 text-gray-400">{player_headers("")}</thead>
+                        <tbody>
+                            {stat_row("Matches", stat(|profile| profile.matches.len().to_string()))}
+                            {stat_row("Wins", stat(|profile| profile.wins().to_string()))}
+                            {stat_row("Frags", stat(|profile| profile.total_kills().to_string()))}
+                            {stat_row("Deaths", stat(|profile| profile.total_deaths().to_string()))}
+                            {stat_row("K/D", stat(|profile| format!("{:.2}", kill_death_ratio(profile.total_kills(), profile.total_deaths()))))}
+                            {stat_row("Favourite weapons", weapons)}
+                        </tbody>
+                    </table>
+                </div>
+            </div>
+
+            <div>
+                <h3 class="text-xl font-medium text-center mb-2">"Direct duels"</h3>
+                <p class="text-sm text-center text-gray-400 mb-2">"Rows are killers, columns are their victims."</p>
+                <div class="relative overflow-x-auto shadow-md sm:rounded-lg">
+                    <table class="w-full text-sm text-left text-gray-400">
+                        <thead class="text-xs uppercase bg-gray-700 text-gray-400">{player_headers("")}</thead>
+                        <tbody>
+                            {duels.into_iter().enumerate().map(|(killer_index, victims)| view! { cx,
+                                <tr class="border-b bg-gray-900 border-gray-700">
+                                    <th scope="row" class="px-6 py-4 font-medium">{comparison.profiles[killer_index].name.clone()}</th>
+                                    {victims.into_iter().enumerate().map(|(victim_index, kills)| view! { cx,
+                                        <td class="px-6 py-4">
+                                            {if victim_index == killer_index { "-".to_string() } else { kills.to_string() }}
+                                        </td>
+                                    }).collect_view(cx)}
+                                </tr>
+                            }).collect_view(cx)}
+                        </tbody>
+                    </table>
+                </div>
+            </div>
+
+            <div>
+                <h3 class="text-xl font-medium text-center mb-2">"Shared matches"</h3>
+                {if shared_matches.is_empty() {
+                    view! { cx, <p class="text-center">"They never played the same match"</p> }.into_view(cx)
+                } else {
+                    view! { cx,
+                        <div class="relative overflow-x-auto shadow-md sm:rounded-lg">
+                            <table class="w-full text-sm text-left text-gray-400">
+                                <thead class="text-xs uppercase bg-gray-700 text-gray-400">{player_headers("Match")}</thead>
+                                <tbody>
+                                    {shared_matches.into_iter().map(|shared_match| view! { cx,
+                                        <tr class="border-b bg-gray-900 border-gray-700">
+                                            <th scope="row" class="px-6 py-4 font-medium">
+                                                <a href=match_href(cx, &shared_match.match_id) class="hover:underline">
+                                                    {format!("Match {}", match_number(&shared_match.match_key))}
+                                                </a>
+                                                <span class="block text-xs text-gray-400">
+                                                    {shared_match.map.clone().unwrap_or_else(|| "Unknown".to_string())}
+                                                </span>
+                                            </th>
+                                            {shared_match.positions.into_iter().map(|position| view! { cx,
+                                                <td class="px-6 py-4">
+                                                    {match position {
+                                                        Some(position) => format!("{position} of {}", shared_match.players),
+                                                        None => "Unranked".to_string(),
+                                                    }}
+                                                </td>
+                                            }).collect_view(cx)}
+                                        </tr>
+                                    }).collect_view(cx)}
+                                </tbody>
+                            </table>
+                        </div>
+                    }.into_view(cx)
+                }}
+            </div>
+        </div>
+    }
+}
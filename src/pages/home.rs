@@ -1,84 +1,222 @@
 use std::collections::HashMap;
 
-use leptos::{html::Input, *};
+use leptos::*;
+use leptos_router::A;
 
 use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::{closure::Closure, JsCast};
 
-use reqwest::{multipart, Client, Url};
+use crate::components::drop_zone::LogDropZone;
+use crate::components::export_links::ExportLinks;
+use crate::components::highlights::{AwardList, HighlightBadges};
+use crate::components::match_tables::{MeansTable, RankingTable};
+use crate::components::upload_status::{UploadProgress, UploadStatus};
+use crate::config::{api_url, client_parser_options};
+use crate::error_template::{AppError, ErrorBody, ErrorTemplate};
+use crate::links::match_href;
+use crate::model::export::ordered_matches;
+use crate::model::parser::{MatchRecord, Parser};
+use crate::store::upload_id_of;
 
-use crate::model::parser::MatchRecord;
+/// Files up to this size are parsed by the WebAssembly bundle, bigger ones are sent to the server.
+pub const CLIENT_PARSE_LIMIT_BYTES: f64 = 32.0 * 1024.0 * 1024.0;
+
+/// `None` when the user cancelled before the matches came back.
+type ParseOutcome = Option<Result<HashMap<String, MatchRecord>, AppError>>;
+
+type ProgressHandler = Closure<dyn FnMut(web_sys::ProgressEvent)>;
 
 #[wasm_bindgen]
 pub async fn file_to_u8(file: web_sys::File) -> Result<js_sys::Uint8Array, wasm_bindgen::JsValue> {
-    let buffer = wasm_bindgen_futures::JsFuture::from(file.array_buffer())
-        .await
-        .unwrap();
+    let buffer = wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await?;
     let u8_array = js_sys::Uint8Array::new(&buffer);
     Ok(u8_array)
 }
 
+/// Gives the browser a chance to paint before blocking it.
+async fn next_tick() {
+    let tick = js_sys::Promise::new(&mut |resolve, _| {
+        let _ = window().set_timeout_with_callback(&resolve);
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(tick).await;
+}
+
+fn error_from_response(body: &str) -> AppError {
+    serde_json::from_str::<ErrorBody>(body)
+        .map(AppError::from)
+        .unwrap_or_else(|_| AppError::Network(format!("The server answered with: {body}")))
+}
+
+/// Streams `file` to the server through an `XMLHttpRequest`, which unlike `fetch` reports
+/// upload progress. The request is kept in `request` so that cancelling can abort it.
+async fn parse_on_server(
+    file: web_sys::File,
+    status: RwSignal<UploadStatus>,
+    request: StoredValue<Option<web_sys::XmlHttpRequest>>,
+) -> ParseOutcome {
+    let request_error = |_| AppError::Network("The upload could not be started".to_string());
+    let send = || -> Result<(web_sys::XmlHttpRequest, js_sys::Promise, [ProgressHandler; 2]), AppError> {
+        let form = web_sys::FormData::new().map_err(request_error)?;
+        form.append_with_blob_and_filename("log", &file, &file.name())
+            .map_err(request_error)?;
+
+        let xhr = web_sys::XmlHttpRequest::new().map_err(request_error)?;
+        xhr.open("POST", &api_url("/api/parse_log_file")).map_err(request_error)?;
+
+        let on_progress = ProgressHandler::new(move |event: web_sys::ProgressEvent| {
+            if event.length_computable() && status.get_untracked() != UploadStatus::Cancelled {
+                status.set(UploadStatus::Uploading {
+                    loaded: event.loaded(),
+                    total: event.total(),
+                });
+            }
+        });
+        let on_uploaded = ProgressHandler::new(move |_: web_sys::ProgressEvent| {
+            if status.get_untracked() != UploadStatus::Cancelled {
+                status.set(UploadStatus::Parsing);
+            }
+        });
+        let upload = xhr.upload().map_err(request_error)?;
+        upload.set_onprogress(Some(on_progress.as_ref().unchecked_ref()));
+        upload.set_onload(Some(on_uploaded.as_ref().unchecked_ref()));
+
+        // Settles once the request is over, whether it succeeded, failed or got aborted
+        let finished = js_sys::Promise::new(&mut |resolve, _| xhr.set_onloadend(Some(&resolve)));
+
+        xhr.send_with_opt_form_data(Some(&form)).map_err(request_error)?;
+        Ok((xhr, finished, [on_progress, on_uploaded]))
+    };
+
+    status.set(UploadStatus::Uploading {
+        loaded: 0.0,
+        total: file.size(),
+    });
+    let (xhr, finished, _handlers) = match send() {
+        Ok(sent) => sent,
+        Err(err) => return Some(Err(err)),
+    };
+    request.set_value(Some(xhr.clone()));
+    let _ = wasm_bindgen_futures::JsFuture::from(finished).await;
+    request.set_value(None);
+
+    if status.get_untracked() == UploadStatus::Cancelled {
+        return None;
+    }
+
+    let body = xhr.response_text().ok().flatten().unwrap_or_default();
+    let result = match xhr.status().unwrap_or_default() {
+        0 => Err(AppError::Network(
+            "The connection to the server was lost".to_string(),
+        )),
+        200..=299 => serde_json::from_str::<HashMap<String, MatchRecord>>(&body)
+            .map_err(|err| AppError::Network(err.to_string())),
+        _ => Err(error_from_response(&body)),
+    };
+
+    Some(result)
+}
+
+/// Parses small files in the browser and sends bigger ones, or any the user saves, to the
+/// server, keeping `status` up to date along the way.
+async fn parse_file(
+    file: web_sys::File,
+    on_server: bool,
+    status: RwSignal<UploadStatus>,
+    request: StoredValue<Option<web_sys::XmlHttpRequest>>,
+) -> ParseOutcome {
+    let outcome = if on_server || file.size() > CLIENT_PARSE_LIMIT_BYTES {
+        parse_on_server(file, status, request).await
+    } else {
+        parse_locally(file, status).await
+    };
+
+    if outcome.is_some() {
+        status.set(UploadStatus::Idle);
+    }
+
+    outcome
+}
+
+async fn parse_locally(file: web_sys::File, status: RwSignal<UploadStatus>) -> ParseOutcome {
+    status.set(UploadStatus::Reading);
+    let buffer = match file_to_u8(file).await {
+        Ok(buffer) => buffer,
+        Err(_) => {
+            return Some(Err(AppError::InvalidUpload(
+                "The file could not be read".to_string(),
+            )))
+        }
+    };
+
+    if status.get_untracked() == UploadStatus::Cancelled {
+        return None;
+    }
+
+    status.set(UploadStatus::Parsing);
+    next_tick().await;
+
+    let mut body = vec![0; buffer.length() as usize];
+    buffer.copy_to(&mut body[..]);
+
+    let result =
+        Parser::parse_bytes_with_options(&body, client_parser_options()).map_err(AppError::from);
+
+    if status.get_untracked() == UploadStatus::Cancelled {
+        return None;
+    }
+
+    Some(result)
+}
+
 #[component]
 pub fn HomePage(cx: Scope) -> impl IntoView {
     let (logs, set_logs) = create_signal::<Vec<(String, MatchRecord)>>(cx, Vec::new());
-    let log_file_ref = create_node_ref::<Input>(cx);
-
-    pub fn submit_callback(
-        file_ref: NodeRef<Input>,
-        set_logs: WriteSignal<Vec<(String, MatchRecord)>>,
-    ) -> impl Fn(web_sys::Event) {
-        move |_event: web_sys::Event| {
-            let file_input = file_ref.get().expect("could not capture file input");
-
-            let file = file_input.files().unwrap().get(0).unwrap();
-
-            let buffer = file_to_u8(file);
-
-            spawn_local(async move {
-                let buffer = buffer.await.unwrap();
-                let mut body = vec![0; buffer.length() as usize];
-                buffer.copy_to(&mut body[..]);
-
-                let file = multipart::Part::bytes::<Vec<u8>>(body).file_name("server-log");
-                let form = reqwest::multipart::Form::new().part("log", file);
-
-                let client = Client::new();
-
-                let url = Url::parse("http://127.0.0.1:3000/api/parse_log_file").unwrap();
-
-                let res = client.post(url).multipart(form).send().await;
-                let res_body = res
-                    .expect("failed to get response")
-                    .json::<HashMap<String, MatchRecord>>()
-                    .await
-                    .expect("failed to get payload");
-
-                let mut ordered_logs = Vec::new();
-                for (match_number, record) in res_body.iter() {
-                    ordered_logs.push((match_number.to_owned(), record.to_owned()));
-                }
-                ordered_logs.sort_by(|a, b| {
-                    let a =
-                        a.0.chars()
-                            .filter(|c| c.is_numeric())
-                            .collect::<String>()
-                            .parse::<i32>()
-                            .unwrap();
-                    let b =
-                        b.0.chars()
-                            .filter(|c| c.is_numeric())
-                            .collect::<String>()
-                            .parse::<i32>()
-                            .unwrap();
-
-                    a.cmp(&b)
-                });
+    let (error, set_error) = create_signal::<Option<AppError>>(cx, None);
+    let status = create_rw_signal(cx, UploadStatus::Idle);
+    let request = store_value::<Option<web_sys::XmlHttpRequest>>(cx, None);
+
+    // Kept so that matches parsed in the browser can be sent to the server when the user saves them
+    let last_file = store_value::<Option<web_sys::File>>(cx, None);
 
-                set_logs(ordered_logs);
-            });
+    let parse_action = create_action(cx, move |(file, on_server): &(web_sys::File, bool)| {
+        parse_file(file.clone(), *on_server, status, request)
+    });
+    let pending = parse_action.pending();
+
+    create_effect(cx, move |_| match parse_action.value().get() {
+        Some(Some(Ok(matches))) => {
+            set_error.set(None);
+            set_logs.set(ordered_matches(matches.iter()));
         }
-    }
+        Some(Some(Err(err))) => {
+            set_logs.set(Vec::new());
+            set_error.set(Some(err));
+        }
+        _ => {}
+    });
+
+    let cancel = move || {
+        status.set(UploadStatus::Cancelled);
+        request.with_value(|request| {
+            if let Some(xhr) = request {
+                let _ = xhr.abort();
+            }
+        });
+    };
+
+    let submit = move |file: web_sys::File| {
+        set_error.set(None);
+        last_file.set_value(Some(file.clone()));
+        parse_action.dispatch((file, false));
+    };
 
-    let submit_handler = submit_callback(log_file_ref, set_logs);
+    // The server parses the log again rather than trusting matches parsed in the browser
+    let save = move |_| {
+        if let Some(file) = last_file.get_value() {
+            set_error.set(None);
+            parse_action.dispatch((file, true));
+        }
+    };
 
     view! { cx,
                 <div class="w-full flex flex-col gap-8">
@@ -121,30 +259,54 @@ pub fn HomePage(cx: Scope) -> impl IntoView {
 
                     <div>
                         <div>
-                            <label class="text-md">
-                                <span class="block mb-2 font-medium">"Upload a server log file:"</span>
-                                <input
-                                    type="file"
-                                    accept=".log,.txt"
-                                    id="file-input"
-                                    _ref=log_file_ref
-                                    name="file-input"
-                                    on:change=submit_handler
-                                    class="block w-full border rounded-lg cursor-pointer text-gray-400 focus:outline-none bg-gray-700 border-gray-600 placeholder-gray-400"
-                                />
-                                <p class="mt-1 text-sm text-gray-300">"TXT or LOG."</p>
-                            </label>
+                            <span class="block mb-2 text-md font-medium">"Upload a server log file:"</span>
+                            <LogDropZone disabled=pending on_submit=submit/>
+                            <p class="mt-1 text-sm text-gray-300">{format!("TXT or LOG. Files up to {} MB are parsed right in the browser, bigger ones are sent to the server. Save the matches parsed in the browser to get their shareable pages and exports.", CLIENT_PARSE_LIMIT_BYTES / (1024.0 * 1024.0))}</p>
                         </div>
 
+                        <UploadProgress status pending on_cancel=cancel/>
+
                         {move || {
-                            if  logs.get().is_empty() {
+                            if pending.get() {
+                                view! { cx, <div></div> }
+                            } else if let Some(error) = error.get() {
+                                let mut errors = Errors::default();
+                                errors.insert_with_default_key(error);
+
+                                view! { cx,
+                                    <div class="flex flex-col my-12 gap-4 items-center">
+                                        <ErrorTemplate outside_errors=errors/>
+                                    </div> }
+                            } else if logs.get().is_empty() {
                                 view! { cx,
                                     <div  class="flex flex-col my-12 gap-12 justify-center">
                                         <p class="text-xl text-center">"No logs parsed yet"</p>
                                     </div> }
                             } else {
+                                // Matches are only stored, and so exportable, once the server parsed them
+                                let upload_id = logs.get().iter().find_map(|(_, record)| {
+                                    record.id.as_deref().and_then(upload_id_of).map(String::from)
+                                });
+
                                 view! { cx,
                                 <div class="flex flex-col my-12 gap-12">
+                                    {match upload_id {
+                                        Some(upload_id) => view! { cx,
+                                            <ExportLinks endpoint=api_url(&format!("/api/uploads/{upload_id}/export"))/>
+                                        }.into_view(cx),
+                                        None => view! { cx,
+                                            <div class="flex flex-row gap-4 items-center justify-center">
+                                                <span class="text-sm text-gray-300">"Parsed in your browser, nothing was sent to the server."</span>
+                                                <button
+                                                    type="button"
+                                                    class="px-3 py-1 rounded-lg bg-blue-600 hover:bg-blue-500 text-sm font-medium"
+                                                    on:click=save
+                                                >
+                                                    "Save to the server"
+                                                </button>
+                                            </div>
+                                        }.into_view(cx),
+                                    }}
                                     <For
                                         each={move || logs.get()}
                                         key={|(match_number, _)| match_number.to_owned()}
@@ -159,79 +321,23 @@ pub fn HomePage(cx: Scope) -> impl IntoView {
                                                 cx,
                                                 <div class="flex flex-col gap-4 w-full">
                                                     <h2 class="text-2xl font-bold text-center">{format!("Match {match_number}")}</h2>
+                                                    {record.id.clone().map(|id| view! { cx,
+                                                        <A href=match_href(cx, &id) class="text-center hover:underline">"Match details"</A>
+                                                        <ExportLinks endpoint=api_url(&format!("/api/matches/{id}/export")) tables=true/>
+                                                    })}
+                                                    <HighlightBadges record=record.clone()/>
                                                     <div class="flex flex-row w-full justify-around">
                                                         <div>
                                                             <h3 class="text-xl font-medium text-center mb-2">"Players ranking"</h3>
-                                                            <div class="relative overflow-x-auto shadow-md sm:rounded-lg">
-                                                                <table class="w-full text-sm text-left text-gray-400">
-                                                                    <thead class="text-xs uppercase bg-gray-700 text-gray-400">
-                                                                        <tr>
-                                                                            <th scope="col" class="px-6 py-3">"Ranking"</th>
-                                                                            <th scope="col" class="px-6 py-3">"Player"</th>
-                                                                            <th scope="col" class="px-6 py-3">"Kill Score"</th>
-                                                                        </tr>
-                                                                    </thead>
-                                                                    <tbody>
-                                                                        <For
-                                                                            each={move || record.ranking.clone()}
-                                                                            key={|ranking_position| ranking_position.player.to_owned()}
-                                                                            view=move |cx, ranking_position| {
-                                                                                view! {
-                                                                                    cx,
-                                                                                    <tr class="border-b bg-gray-900 border-gray-700">
-                                                                                        <td class="px-6 py-4">{format!("{}", ranking_position.position)}</td>
-                                                                                        <td class="px-6 py-4">{format!("{}", ranking_position.player)}</td>
-                                                                                        <td class="px-6 py-4">{format!("{}", ranking_position.kills)}</td>
-                                                                                    </tr>
-                                                                                }
-                                                                            }
-                                                                        />
-                                                                    </tbody>
-                                                                </table>
-                                                            </div>
+                                                            <RankingTable record=record.clone()/>
                                                         </div>
 
                                                         <div>
                                                             <h3 class="text-xl font-medium text-center mb-2">"Means of kills"</h3>
-                                                            {
-                                                                if record.kills_by_means.is_empty() {
-                                                                    view! { cx,
-                                                                        <div>
-                                                                            <p class="text-center">"No valid means of kills registered"</p>
-                                                                        </div>
-                                                                    }
-                                                                } else {
-                                                                    view! { cx,
-                                                                        <div class="relative overflow-x-auto shadow-md sm:rounded-lg">
-                                                                            <table class="w-full text-sm text-left text-gray-400">
-                                                                                <thead class="text-xs uppercase bg-gray-700 text-gray-400">
-                                                                                    <tr>
-                                                                                        <th scope="col" class="px-6 py-3">"Damage source"</th>
-                                                                                        <th scope="col" class="px-6 py-3">"Kills"</th>
-                                                                                    </tr>
-                                                                                </thead>
-                                                                                <tbody>
-                                                                                    <For
-                                                                                        each={move || record.kills_by_means.clone()}
-                                                                                        key={|(means, _)| means.to_owned()}
-                                                                                        view=move |cx, (means, kills)| {
-                                                                                            view! {
-                                                                                                cx,
-                                                                                                <tr class="border-b bg-gray-900 border-gray-700">
-                                                                                                    <td class="px-6 py-4">{format!("{means}")}</td>
-                                                                                                    <td class="px-6 py-4">{format!("{kills}")}</td>
-                                                                                                </tr>
-                                                                                            }
-                                                                                        }
-                                                                                    />
-                                                                                </tbody>
-                                                                            </table>
-                                                                        </div>
-                                                                    }
-                                                                }
-                                                            }
+                                                            <MeansTable record=record.clone()/>
                                                         </div>
                                                     </div>
+                                                    <AwardList record=record.clone()/>
                                                     <span class="text-sm">{format!("Total match kills: {}", record.total_kills)}</span>
                                                 </div>
                                             }
@@ -0,0 +1,145 @@
+use leptos::*;
+
+use wasm_bindgen::{closure::Closure, JsCast};
+
+use crate::components::coloured_text::ColouredText;
+use crate::config::api_url;
+use crate::live::{FeedEntry, LiveEvent, FEED_SIZE};
+use crate::model::parser::RankingPosition;
+
+#[component]
+pub fn LivePage(cx: Scope) -> impl IntoView {
+    let (match_id, set_match_id) = create_signal::<Option<String>>(cx, None);
+    let (ranking, set_ranking) = create_signal::<Vec<RankingPosition>>(cx, Vec::new());
+    let (feed, set_feed) = create_signal::<Vec<FeedEntry>>(cx, Vec::new());
+    let (total_kills, set_total_kills) = create_signal::<i16>(cx, 0);
+    let (match_ended, set_match_ended) = create_signal(cx, false);
+
+    // Effects only run in the browser, so the server render shows the empty scoreboard
+    create_effect(cx, move |_| {
+        let event_source = match web_sys::EventSource::new(&api_url("/api/live")) {
+            Ok(event_source) => event_source,
+            Err(_) => return,
+        };
+
+        let on_message = Closure::<dyn FnMut(web_sys::MessageEvent)>::new(
+            move |message: web_sys::MessageEvent| {
+                let data = message.data().as_string().unwrap_or_default();
+                let event = match serde_json::from_str::<LiveEvent>(&data) {
+                    Ok(event) => event,
+                    Err(_) => return,
+                };
+
+                match event {
+                    LiveEvent::Snapshot {
+                        match_id,
+                        ranking,
+                        total_kills,
+                        feed,
+                    } => {
+                        set_match_id.set(match_id);
+                        set_ranking.set(ranking);
+                        set_total_kills.set(total_kills);
+                        set_feed.set(feed);
+                        set_match_ended.set(false);
+                    }
+                    LiveEvent::MatchStarted { match_id } => {
+                        set_match_id.set(Some(match_id));
+                        set_ranking.set(Vec::new());
+                        set_total_kills.set(0);
+                        set_feed.set(Vec::new());
+                        set_match_ended.set(false);
+                    }
+                    LiveEvent::Kill {
+                        entry, total_kills, ..
+                    } => {
+                        set_total_kills.set(total_kills);
+                        set_feed.update(|feed| {
+                            feed.insert(0, entry);
+                            feed.truncate(FEED_SIZE);
+                        });
+                    }
+                    LiveEvent::RankingChanged { ranking, .. } => {
+                        set_ranking.set(ranking);
+                    }
+                    LiveEvent::MatchEnded { record, .. } => {
+                        set_ranking.set(record.ranking);
+                        set_total_kills.set(record.total_kills);
+                        set_match_ended.set(true);
+                    }
+                }
+            },
+        );
+        event_source.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+        on_cleanup(cx, move || {
+            event_source.close();
+            drop(on_message);
+        });
+    });
+
+    view! { cx,
+        <div class="w-full flex flex-col gap-8">
+            <h1 class="text-3xl font-bold text-center">"Live match"</h1>
+            <p class="text-xl text-center">
+                {move || match (match_id.get(), match_ended.get()) {
+                    (None, _) => "Waiting for a match to start".to_string(),
+                    (Some(match_id), false) => format!("Following {match_id}"),
+                    (Some(match_id), true) => format!("{match_id} has ended"),
+                }}
+            </p>
+
+            <div class="flex flex-row w-full justify-around">
+                <div>
+                    <h3 class="text-xl font-medium text-center mb-2">"Scoreboard"</h3>
+                    <div class="relative overflow-x-auto shadow-md sm:rounded-lg">
+                        <table class="w-full text-sm text-left text-gray-400">
+                            <thead class="text-xs uppercase bg-gray-700 text-gray-400">
+                                <tr>
+                                    <th scope="col" class="px-6 py-3">"Ranking"</th>
+                                    <th scope="col" class="px-6 py-3">"Player"</th>
+                                    <th scope="col" class="px-6 py-3">"Kill Score"</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                <For
+                                    each={move || ranking.get()}
+                                    key={|ranking_position| (ranking_position.player.to_owned(), ranking_position.position, ranking_position.kills)}
+                                    view=move |cx, ranking_position| {
+                                        view! {
+                                            cx,
+                                            <tr class="border-b bg-gray-900 border-gray-700">
+                                                <td class="px-6 py-4">{ranking_position.position}</td>
+                                                <td class="px-6 py-4"><ColouredText text=ranking_position.player/></td>
+                                                <td class="px-6 py-4">{ranking_position.kills}</td>
+                                            </tr>
+                                        }
+                                    }
+                                />
+                            </tbody>
+                        </table>
+                    </div>
+                    <span class="text-sm">{move || format!("Total match kills: {}", total_kills.get())}</span>
+                </div>
+
+                <div class="w-1/3">
+                    <h3 class="text-xl font-medium text-center mb-2">"Kill feed"</h3>
+                    <ul class="flex flex-col gap-2">
+                        {move || {
+                            feed.get()
+                                .into_iter()
+                                .map(|entry| view! { cx,
+                                    <li class="px-4 py-2 rounded-lg bg-gray-900 text-sm">
+                                        <span class="font-medium"><ColouredText text=entry.killer/></span>
+                                        <span class="text-gray-400">{format!(" [{}] ", entry.means)}</span>
+                                        <span class="font-medium"><ColouredText text=entry.killed/></span>
+                                    </li>
+                                })
+                                .collect_view(cx)
+                        }}
+                    </ul>
+                </div>
+            </div>
+        </div>
+    }
+}
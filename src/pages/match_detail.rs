@@ -0,0 +1,371 @@
+use std::collections::BTreeSet;
+
+use leptos::*;
+use leptos_meta::*;
+use leptos_router::*;
+
+use crate::api::matches::get_match;
+use crate::components::coloured_text::ColouredText;
+use crate::components::highlights::{AwardList, HighlightBadges};
+use crate::components::kill_feed::KillFeedReplay;
+use crate::components::match_tables::{MeansTable, RankingTable};
+use crate::components::score_chart::ScoreChart;
+use crate::error_template::{AppError, ErrorTemplate};
+use crate::links::LinkStyle;
+use crate::model::names::strip_colour_codes;
+use crate::model::parser::{format_game_time, MatchRecord};
+use crate::store::{match_number, StoredMatch};
+
+/// A kill or a chat message, in the order they happened.
+#[derive(Clone)]
+enum TimelineEntry {
+    Kill {
+        time: u32,
+        killer: String,
+        killed: String,
+        means: String,
+    },
+    Chat {
+        time: u32,
+        player: String,
+        message: String,
+        team_only: bool,
+    },
+}
+
+fn timeline(record: &MatchRecord) -> Vec<TimelineEntry> {
+    let kills = record.kill_events.iter().map(|kill| {
+        let entry = TimelineEntry::Kill {
+            time: kill.time,
+            killer: kill.killer.clone(),
+            killed: kill.killed.clone(),
+            means: kill.means.clone(),
+        };
+        ((kill.time, kill.line), entry)
+    });
+    let chat = record.chat.iter().map(|message| {
+        let entry = TimelineEntry::Chat {
+            time: message.time,
+            player: message.player.clone(),
+            message: message.message.clone(),
+            team_only: message.team_only,
+        };
+        ((message.time, message.line), entry)
+    });
+
+    // The line breaks ties between kills and messages logged on the same second
+    let mut timeline = kills.chain(chat).collect::<Vec<_>>();
+    timeline.sort_by_key(|(position, _)| *position);
+    timeline.into_iter().map(|(_, entry)| entry).collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    Overview,
+    Replay,
+}
+
+fn match_summary(stored_match: &StoredMatch) -> String {
+    let record = &stored_match.record;
+    let map = record
+        .metadata
+        .map
+        .clone()
+        .unwrap_or_else(|| "an unknown map".to_string());
+    let winner = record
+        .ranking
+        .first()
+        .map(|ranking_position| {
+            format!(
+                ", won by {} with {} kills",
+                strip_colour_codes(&ranking_position.player),
+                ranking_position.kills
+            )
+        })
+        .unwrap_or_default();
+
+    format!(
+        "{} players on {map}{winner}. Total match kills: {}.",
+        record.ranking.len(),
+        record.total_kills
+    )
+}
+
+#[component]
+pub fn MatchPage(cx: Scope) -> impl IntoView {
+    let params = use_params_map(cx);
+    let stored_match = create_resource(
+        cx,
+        move || params.with(|params| params.get("id").cloned().unwrap_or_default()),
+        move |id| get_match(cx, id),
+    );
+
+    view! { cx,
+        <Suspense fallback=move || view! { cx, <p class="text-xl text-center">"Loading match..."</p> }>
+            {move || stored_match.read(cx).map(|stored_match| match stored_match {
+                Ok(Some(stored_match)) => view! { cx, <MatchDetail stored_match/> }.into_view(cx),
+                Ok(None) => {
+                    let mut errors = Errors::default();
+                    errors.insert_with_default_key(AppError::NotFound);
+                    view! { cx, <ErrorTemplate outside_errors=errors/> }.into_view(cx)
+                }
+                Err(err) => {
+                    let mut errors = Errors::default();
+                    errors.insert_with_default_key(AppError::Network(err.to_string()));
+                    view! { cx, <ErrorTemplate outside_errors=errors/> }.into_view(cx)
+                }
+            })}
+        </Suspense>
+    }
+}
+
+/// Everything known about a stored match, also rendered on its own by the static report.
+#[component]
+pub fn MatchDetail(cx: Scope, stored_match: StoredMatch) -> impl IntoView {
+    let title = format!(
+        "Match {} - Quake 3 Arena server log parser",
+        match_number(&stored_match.match_key)
+    );
+    let summary = match_summary(&stored_match);
+    let record = stored_match.record;
+    let metadata = record.metadata.clone();
+
+    let kill_matrix = record.kill_matrix();
+    let victims = kill_matrix
+        .values()
+        .flat_map(|victims| victims.keys().cloned())
+        .collect::<BTreeSet<String>>();
+    let weapon_breakdown = record.weapon_breakdown();
+    let weapons = weapon_breakdown
+        .values()
+        .flat_map(|weapons| weapons.keys().cloned())
+        .collect::<BTreeSet<String>>();
+    let game_event_counts = record.game_event_counts();
+    let game_event_kinds = game_event_counts
+        .values()
+        .flat_map(|counts| counts.keys().copied())
+        .collect::<BTreeSet<&'static str>>();
+    let timeline = timeline(&record);
+
+    let tab = create_rw_signal(cx, Tab::Overview);
+    // Static reports ship without the WebAssembly bundle the replay runs on
+    let interactive = use_context::<LinkStyle>(cx).unwrap_or_default() == LinkStyle::Server;
+    let tab_button = move |label: &'static str, value: Tab| {
+        view! { cx,
+            <button
+                type="button"
+                role="tab"
+                class="px-4 py-2 -mb-px border-b-2 font-medium"
+                class:border-blue-500=move || tab.get() == value
+                class:border-transparent=move || tab.get() != value
+                aria-selected=move || (tab.get() == value).to_string()
+                on:click=move |_| tab.set(value)
+            >
+                {label}
+            </button>
+        }
+    };
+    let replay_record = record.clone();
+
+    view! { cx,
+        <Title text=title.clone()/>
+        <Meta name="description" content=summary.clone()/>
+        <Meta property="og:title" content=title/>
+        <Meta property="og:description" content=summary/>
+
+        <div class="w-full flex flex-col gap-12">
+            <h1 class="text-3xl font-bold text-center">{format!("Match {}", match_number(&stored_match.match_key))}</h1>
+
+            <div class="flex flex-row w-full justify-around text-center">
+                <div>
+                    <p class="text-sm text-gray-400">"Map"</p>
+                    <p class="text-xl">{metadata.map.clone().unwrap_or_else(|| "Unknown".to_string())}</p>
+                </div>
+                <div>
+                    <p class="text-sm text-gray-400">"Game"</p>
+                    <p class="text-xl">{metadata.dialect.to_string()}</p>
+                </div>
+                <div>
+                    <p class="text-sm text-gray-400">"Game type"</p>
+                    <p class="text-xl">
+                        {metadata.game_type_name().map(String::from).or(metadata.game_type.clone()).unwrap_or_else(|| "Unknown".to_string())}
+                    </p>
+                </div>
+                <div>
+                    <p class="text-sm text-gray-400">"Server"</p>
+                    <p class="text-xl">{metadata.server_name.clone().unwrap_or_else(|| "Unknown".to_string())}</p>
+                </div>
+                <div>
+                    <p class="text-sm text-gray-400">"Duration"</p>
+                    <p class="text-xl">{metadata.duration().map(format_game_time).unwrap_or_else(|| "Unknown".to_string())}</p>
+                </div>
+                <div>
+                    <p class="text-sm text-gray-400">"Total kills"</p>
+                    <p class="text-xl">{record.total_kills}</p>
+                </div>
+            </div>
+
+            <HighlightBadges record=record.clone()/>
+
+            <Show when=move || interactive fallback=|_| ()>
+                <div class="flex flex-row gap-2 justify-center border-b border-gray-700" role="tablist">
+                    {tab_button("Overview", Tab::Overview)}
+                    {tab_button("Replay", Tab::Replay)}
+                </div>
+            </Show>
+
+            <Show when=move || tab.get() == Tab::Replay fallback=|_| ()>
+                <KillFeedReplay record=replay_record.clone()/>
+            </Show>
+
+            <div class="flex flex-col gap-12" class:hidden=move || tab.get() != Tab::Overview>
+                <div class="flex flex-row w-full justify-around">
+                    <div>
+                        <h3 class="text-xl font-medium text-center mb-2">"Scoreboard"</h3>
+                        <RankingTable record=record.clone()/>
+                    </div>
+
+                    <div>
+                        <h3 class="text-xl font-medium text-center mb-2">"Means of kills"</h3>
+                        <MeansTable record=record.clone()/>
+                    </div>
+                </div>
+
+                <AwardList record=record.clone()/>
+
+                <div>
+                    <h3 class="text-xl font-medium text-center mb-2">"Score over time"</h3>
+                    <ScoreChart record=record.clone()/>
+                </div>
+
+                <div>
+                    <h3 class="text-xl font-medium text-center mb-2">"Kill matrix"</h3>
+                    <p class="text-sm text-center text-gray-400 mb-2">"Rows are killers, columns are their victims."</p>
+                    <div class="relative overflow-x-auto shadow-md sm:rounded-lg">
+                        <table class="w-full text-sm text-left text-gray-400">
+                            <thead class="text-xs uppercase bg-gray-700 text-gray-400">
+                                <tr>
+                                    <th scope="col" class="px-6 py-3"></th>
+                                    {victims.iter().map(|victim| view! { cx,
+                                        <th scope="col" class="px-6 py-3"><ColouredText text=victim.clone()/></th>
+                                    }).collect_view(cx)}
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {kill_matrix.iter().map(|(killer, killed)| view! { cx,
+                                    <tr class="border-b bg-gray-900 border-gray-700">
+                                        <th scope="row" class="px-6 py-4 font-medium"><ColouredText text=killer.clone()/></th>
+                                        {victims.iter().map(|victim| view! { cx,
+                                            <td class="px-6 py-4">{killed.get(victim).copied().unwrap_or_default()}</td>
+                                        }).collect_view(cx)}
+                                    </tr>
+                                }).collect_view(cx)}
+                            </tbody>
+                        </table>
+                    </div>
+                </div>
+
+                <div>
+                    <h3 class="text-xl font-medium text-center mb-2">"Weapon breakdown"</h3>
+                    <div class="relative overflow-x-auto shadow-md sm:rounded-lg">
+                        <table class="w-full text-sm text-left text-gray-400">
+                            <thead class="text-xs uppercase bg-gray-700 text-gray-400">
+                                <tr>
+                                    <th scope="col" class="px-6 py-3">"Player"</th>
+                                    {weapons.iter().map(|weapon| view! { cx,
+                                        <th scope="col" class="px-6 py-3">{weapon.clone()}</th>
+                                    }).collect_view(cx)}
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {weapon_breakdown.iter().map(|(player, kills)| view! { cx,
+                                    <tr class="border-b bg-gray-900 border-gray-700">
+                                        <th scope="row" class="px-6 py-4 font-medium"><ColouredText text=player.clone()/></th>
+                                        {weapons.iter().map(|weapon| view! { cx,
+                                            <td class="px-6 py-4">{kills.get(weapon).copied().unwrap_or_default()}</td>
+                                        }).collect_view(cx)}
+                                    </tr>
+                                }).collect_view(cx)}
+                            </tbody>
+                        </table>
+                    </div>
+                </div>
+
+                {(!game_event_counts.is_empty()).then(|| view! { cx,
+                    <div>
+                        <h3 class="text-xl font-medium text-center mb-2">"Objectives"</h3>
+                        <div class="relative overflow-x-auto shadow-md sm:rounded-lg">
+                            <table class="w-full text-sm text-left text-gray-400">
+                                <thead class="text-xs uppercase bg-gray-700 text-gray-400">
+                                    <tr>
+                                        <th scope="col" class="px-6 py-3">"Player"</th>
+                                        {game_event_kinds.iter().map(|kind| view! { cx,
+                                            <th scope="col" class="px-6 py-3">{*kind}</th>
+                                        }).collect_view(cx)}
+                                    </tr>
+                                </thead>
+                                <tbody>
+                                    {game_event_counts.iter().map(|(player, counts)| view! { cx,
+                                        <tr class="border-b bg-gray-900 border-gray-700">
+                                            <th scope="row" class="px-6 py-4 font-medium"><ColouredText text=player.clone()/></th>
+                                            {game_event_kinds.iter().map(|kind| view! { cx,
+                                                <td class="px-6 py-4">{counts.get(kind).copied().unwrap_or_default()}</td>
+                                            }).collect_view(cx)}
+                                        </tr>
+                                    }).collect_view(cx)}
+                                </tbody>
+                            </table>
+                        </div>
+                    </div>
+                })}
+
+                <div class="flex flex-row w-full justify-around gap-8">
+                    <div class="w-1/2">
+                        <h3 class="text-xl font-medium text-center mb-2">"Timeline"</h3>
+                        <ul class="flex flex-col gap-2">
+                            {timeline.into_iter().map(|entry| match entry {
+                                TimelineEntry::Kill { time, killer, killed, means } => view! { cx,
+                                    <li class="px-4 py-2 rounded-lg bg-gray-900 text-sm">
+                                        <span class="text-gray-400">{format!("{} ", format_game_time(time))}</span>
+                                        <span class="font-medium"><ColouredText text=killer/></span>
+                                        <span class="text-gray-400">{format!(" [{means}] ")}</span>
+                                        <span class="font-medium"><ColouredText text=killed/></span>
+                                    </li>
+                                },
+                                TimelineEntry::Chat { time, player, message, team_only } => view! { cx,
+                                    <li class="px-4 py-2 rounded-lg bg-[#3c4450] text-sm">
+                                        <span class="text-gray-400">{format!("{} ", format_game_time(time))}</span>
+                                        <span class="font-medium">
+                                            <ColouredText text=player/>
+                                            {format!("{}: ", if team_only { " (team)" } else { "" })}
+                                        </span>
+                                        <span><ColouredText text=message/></span>
+                                    </li>
+                                },
+                            }).collect_view(cx)}
+                        </ul>
+                    </div>
+
+                    <div class="w-1/2">
+                        <h3 class="text-xl font-medium text-center mb-2">"Chat"</h3>
+                        {if record.chat.is_empty() {
+                            view! { cx, <p class="text-center">"Nobody said anything"</p> }.into_view(cx)
+                        } else {
+                            view! { cx,
+                                <ul class="flex flex-col gap-2">
+                                    {record.chat.iter().map(|message| view! { cx,
+                                        <li class="px-4 py-2 rounded-lg bg-[#3c4450] text-sm">
+                                            <span class="text-gray-400">{format!("{} ", format_game_time(message.time))}</span>
+                                            <span class="font-medium"><ColouredText text=message.player.clone()/>": "</span>
+                                            <span><ColouredText text=message.message.clone()/></span>
+                                        </li>
+                                    }).collect_view(cx)}
+                                </ul>
+                            }.into_view(cx)
+                        }}
+                    </div>
+                </div>
+            </div>
+        </div>
+    }
+}
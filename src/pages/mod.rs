@@ -1 +1,6 @@
+pub mod compare;
 pub mod home;
+pub mod live;
+pub mod match_detail;
+pub mod player;
+pub mod ratings;
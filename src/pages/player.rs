@@ -0,0 +1,197 @@
+use leptos::*;
+use leptos_meta::*;
+use leptos_router::*;
+
+use crate::api::players::get_player_profile;
+use crate::components::coloured_text::ColouredText;
+use crate::error_template::{AppError, ErrorTemplate};
+use crate::links::{match_href, player_href};
+use crate::model::player::{kill_death_ratio, player_name_from_path, PlayerProfile};
+use crate::store::match_number;
+
+#[component]
+pub fn PlayerPage(cx: Scope) -> impl IntoView {
+    let params = use_params_map(cx);
+    let profile = create_resource(
+        cx,
+        move || {
+            params.with(|params| {
+                player_name_from_path(&params.get("name").cloned().unwrap_or_default())
+            })
+        },
+        move |name| get_player_profile(cx, name),
+    );
+
+    view! { cx,
+        <Suspense fallback=move || view! { cx, <p class="text-xl text-center">"Loading player..."</p> }>
+            {move || profile.read(cx).map(|profile| match profile {
+                Ok(Some(profile)) => view! { cx, <PlayerProfileView profile/> }.into_view(cx),
+                Ok(None) => {
+                    let mut errors = Errors::default();
+                    errors.insert_with_default_key(AppError::NotFound);
+                    view! { cx, <ErrorTemplate outside_errors=errors/> }.into_view(cx)
+                }
+                Err(err) => {
+                    let mut errors = Errors::default();
+                    errors.insert_with_default_key(AppError::Network(err.to_string()));
+                    view! { cx, <ErrorTemplate outside_errors=errors/> }.into_view(cx)
+                }
+            })}
+        </Suspense>
+    }
+}
+
+/// A player's profile, also rendered on its own by the static report.
+#[component]
+pub fn PlayerProfileView(cx: Scope, profile: PlayerProfile) -> impl IntoView {
+    let title = format!("{} - Quake 3 Arena server log parser", profile.name);
+    let kills = profile.total_kills();
+    let deaths = profile.total_deaths();
+    let matches_played = profile.matches.len();
+    let wins = profile.wins();
+    let summary =
+        format!("{matches_played} matches played, {wins} won. {kills} kills and {deaths} deaths.");
+    let kill_death_history = profile.kill_death_history();
+    let aliases = profile
+        .aliases
+        .iter()
+        .enumerate()
+        .map(|(index, alias)| {
+            view! { cx,
+                {(index > 0).then_some(", ")}
+                <ColouredText text=alias.clone()/>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let opponent = |opponent: Option<(String, u32)>| match opponent {
+        Some((player, times)) => view! { cx,
+            <p class="text-xl">
+                <a href=player_href(cx, &player) class="hover:underline">{player.clone()}</a>
+                {if times == 1 { " (once)".to_string() } else { format!(" ({times} times)") }}
+            </p>
+        }
+        .into_view(cx),
+        None => view! { cx, <p class="text-xl">"Nobody"</p> }.into_view(cx),
+    };
+
+    view! { cx,
+        <Title text=title.clone()/>
+        <Meta name="description" content=summary.clone()/>
+        <Meta property="og:title" content=title/>
+        <Meta property="og:description" content=summary/>
+
+        <div class="w-full flex flex-col gap-12">
+            <div class="flex flex-col gap-2 items-center">
+                <h1 class="text-3xl font-bold text-center">{profile.name.clone()}</h1>
+                {(!aliases.is_empty()).then(|| view! { cx,
+                    <p class="text-sm text-gray-400">"Also known as "{aliases.collect_view(cx)}</p>
+                })}
+            </div>
+
+            <div class="flex flex-row w-full justify-around text-center">
+                <div>
+                    <p class="text-sm text-gray-400">"Matches"</p>
+                    <p class="text-xl">{matches_played}</p>
+                </div>
+                <div>
+                    <p class="text-sm text-gray-400">"Wins"</p>
+                    <p class="text-xl">{wins}</p>
+                </div>
+                <div>
+                    <p class="text-sm text-gray-400">"Kills"</p>
+                    <p class="text-xl">{kills}</p>
+                </div>
+                <div>
+                    <p class="text-sm text-gray-400">"Deaths"</p>
+                    <p class="text-xl">{deaths}</p>
+                </div>
+                <div>
+                    <p class="text-sm text-gray-400">"K/D"</p>
+                    <p class="text-xl">{format!("{:.2}", kill_death_ratio(kills, deaths))}</p>
+                </div>
+                <div>
+                    <p class="text-sm text-gray-400">"Nemesis"</p>
+                    {opponent(profile.nemesis.clone())}
+                </div>
+                <div>
+                    <p class="text-sm text-gray-400">"Favourite victim"</p>
+                    {opponent(profile.favourite_victim.clone())}
+                </div>
+            </div>
+
+            <div class="flex flex-row w-full justify-around gap-8">
+                <div>
+                    <h3 class="text-xl font-medium text-center mb-2">"Matches"</h3>
+                    <div class="relative overflow-x-auto shadow-md sm:rounded-lg">
+                        <table class="w-full text-sm text-left text-gray-400">
+                            <thead class="text-xs uppercase bg-gray-700 text-gray-400">
+                                <tr>
+                                    <th scope="col" class="px-6 py-3">"Match"</th>
+                                    <th scope="col" class="px-6 py-3">"Map"</th>
+                                    <th scope="col" class="px-6 py-3">"Placement"</th>
+                                    <th scope="col" class="px-6 py-3">"Kill Score"</th>
+                                    <th scope="col" class="px-6 py-3">"K/D"</th>
+                                    <th scope="col" class="px-6 py-3">"Overall K/D"</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {profile.matches.clone().into_iter().zip(kill_death_history).map(|(player_match, overall)| {
+                                    let kill_death_ratio = player_match.kill_death_ratio();
+
+                                    view! { cx,
+                                        <tr class="border-b bg-gray-900 border-gray-700">
+                                            <td class="px-6 py-4">
+                                                <a href=match_href(cx, &player_match.match_id) class="hover:underline">
+                                                    {format!("Match {}", match_number(&player_match.match_key))}
+                                                </a>
+                                            </td>
+                                            <td class="px-6 py-4">{player_match.map.clone().unwrap_or_else(|| "Unknown".to_string())}</td>
+                                            <td class="px-6 py-4">
+                                                {match player_match.position {
+                                                    Some(position) => format!("{position} of {}", player_match.players),
+                                                    None => "Unranked".to_string(),
+                                                }}
+                                            </td>
+                                            <td class="px-6 py-4">{player_match.score}</td>
+                                            <td class="px-6 py-4">{format!("{kill_death_ratio:.2}")}</td>
+                                            <td class="px-6 py-4">{format!("{overall:.2}")}</td>
+                                        </tr>
+                                    }
+                                }).collect_view(cx)}
+                            </tbody>
+                        </table>
+                    </div>
+                </div>
+
+                <div>
+                    <h3 class="text-xl font-medium text-center mb-2">"Best weapons"</h3>
+                    {if profile.best_weapons.is_empty() {
+                        view! { cx, <p class="text-center">"No kills registered"</p> }.into_view(cx)
+                    } else {
+                        view! { cx,
+                            <div class="relative overflow-x-auto shadow-md sm:rounded-lg">
+                                <table class="w-full text-sm text-left text-gray-400">
+                                    <thead class="text-xs uppercase bg-gray-700 text-gray-400">
+                                        <tr>
+                                            <th scope="col" class="px-6 py-3">"Damage source"</th>
+                                            <th scope="col" class="px-6 py-3">"Kills"</th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {profile.best_weapons.clone().into_iter().map(|(means, kills)| view! { cx,
+                                            <tr class="border-b bg-gray-900 border-gray-700">
+                                                <td class="px-6 py-4">{means}</td>
+                                                <td class="px-6 py-4">{kills}</td>
+                                            </tr>
+                                        }).collect_view(cx)}
+                                    </tbody>
+                                </table>
+                            </div>
+                        }.into_view(cx)
+                    }}
+                </div>
+            </div>
+        </div>
+    }
+}
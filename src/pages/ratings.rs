@@ -0,0 +1,142 @@
+use leptos::*;
+use leptos_meta::*;
+use leptos_router::*;
+
+use crate::api::ratings::get_ratings;
+use crate::components::data_table::{Cell, Column, DataTable};
+use crate::error_template::{AppError, ErrorTemplate};
+use crate::links::player_href;
+use crate::model::rating::{RatingAlgorithm, RatingInput, RatingTable};
+
+#[component]
+pub fn RatingsPage(cx: Scope) -> impl IntoView {
+    let query = use_query_map(cx);
+    let choices = move || {
+        query.with(|query| {
+            (
+                query
+                    .get("algorithm")
+                    .and_then(|algorithm| algorithm.parse::<RatingAlgorithm>().ok()),
+                query
+                    .get("input")
+                    .and_then(|input| input.parse::<RatingInput>().ok()),
+            )
+        })
+    };
+    let ratings = create_resource(cx, choices, move |(algorithm, input)| {
+        get_ratings(cx, algorithm, input)
+    });
+
+    view! { cx,
+        <Title text="Ratings - Quake 3 Arena server log parser"/>
+
+        <div class="w-full flex flex-col gap-12">
+            <h1 class="text-3xl font-bold text-center">"Ratings"</h1>
+
+            <Suspense fallback=move || view! { cx, <p class="text-xl text-center">"Rating players..."</p> }>
+                {move || ratings.read(cx).map(|ratings| match ratings {
+                    Ok(ratings) => view! { cx, <RatingsView ratings/> }.into_view(cx),
+                    Err(err) => {
+                        let mut errors = Errors::default();
+                        errors.insert_with_default_key(AppError::Network(err.to_string()));
+                        view! { cx, <ErrorTemplate outside_errors=errors/> }.into_view(cx)
+                    }
+                })}
+            </Suspense>
+        </div>
+    }
+}
+
+/// Leaderboard of a rating table, with the algorithm and input it was computed with.
+#[component]
+fn RatingsView(cx: Scope, ratings: RatingTable) -> impl IntoView {
+    let tracks_uncertainty = ratings.algorithm != RatingAlgorithm::Elo;
+    let precision = if ratings.algorithm == RatingAlgorithm::TrueSkill {
+        2
+    } else {
+        0
+    };
+
+    let mut columns = vec![Column::text("Player"), Column::number("Rating")];
+    if tracks_uncertainty {
+        columns.push(Column::number("Uncertainty"));
+    }
+    columns.extend([Column::number("Matches"), Column::number("Last change")]);
+
+    let rows = ratings
+        .players
+        .iter()
+        .map(|player_rating| {
+            let last_change = player_rating
+                .history
+                .last()
+                .map(|point| point.change)
+                .unwrap_or_default();
+
+            let mut row = vec![
+                Cell::link(
+                    &player_rating.player,
+                    player_href(cx, &player_rating.player),
+                ),
+                Cell::Decimal {
+                    value: player_rating.rating,
+                    precision,
+                },
+            ];
+            if let Some(deviation) = player_rating.deviation {
+                row.push(Cell::Decimal {
+                    value: deviation,
+                    precision,
+                });
+            }
+            row.extend([
+                Cell::integer(player_rating.matches as i64),
+                Cell::Decimal {
+                    value: last_change,
+                    precision: precision.max(1),
+                },
+            ]);
+            row
+        })
+        .collect::<Vec<Vec<Cell>>>();
+
+    let algorithm_options = RatingAlgorithm::ALL
+        .into_iter()
+        .map(|algorithm| view! { cx,
+            <option value=algorithm.as_str() selected=algorithm == ratings.algorithm>{algorithm.to_string()}</option>
+        })
+        .collect_view(cx);
+    let input_options = RatingInput::ALL
+        .into_iter()
+        .map(|input| view! { cx,
+            <option value=input.as_str() selected=input == ratings.input>{input.to_string()}</option>
+        })
+        .collect_view(cx);
+
+    view! { cx,
+        <form method="get" action="/ratings" class="flex flex-row gap-4 justify-center items-end">
+            <label class="flex flex-col gap-1 text-sm">
+                "Algorithm"
+                <select name="algorithm" class="px-3 py-1 rounded-lg bg-gray-700 border border-gray-600">{algorithm_options}</select>
+            </label>
+            <label class="flex flex-col gap-1 text-sm">
+                "Rated from"
+                <select name="input" class="px-3 py-1 rounded-lg bg-gray-700 border border-gray-600">{input_options}</select>
+            </label>
+            <button type="submit" class="px-4 py-1 rounded-lg bg-blue-600 hover:bg-blue-500 font-medium">"Rate"</button>
+        </form>
+
+        <p class="text-sm text-center text-gray-400">
+            {match ratings.input {
+                RatingInput::Placements => "Every match counts as each player beating everyone ranked below them.",
+                RatingInput::Duels => "Every kill counts as a duel won by the killer.",
+            }}
+        </p>
+
+        {if ratings.players.is_empty() {
+            view! { cx, <p class="text-xl text-center">"No matches stored yet"</p> }.into_view(cx)
+        } else {
+            view! { cx, <DataTable columns rows/> }.into_view(cx)
+        }}
+    }
+}
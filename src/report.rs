@@ -0,0 +1,186 @@
+use cfg_if::cfg_if;
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use leptos::*;
+use leptos_meta::*;
+use thiserror::Error;
+
+use crate::components::leaderboard::{LeaderboardTable, MatchListTable};
+use crate::links::{static_file_stem, LinkStyle};
+use crate::model::parallel::ParallelParser;
+use crate::model::parser::{ParseError, ParserOptions};
+use crate::model::identity::{AliasMap, IdentityResolver};
+use crate::model::player::PlayerProfile;
+use crate::pages::match_detail::MatchDetail;
+use crate::pages::player::PlayerProfileView;
+use crate::store::{match_number, StoredMatch};
+
+/// Tailwind output also served by the app, copied next to the report pages.
+const STYLESHEET: &str = include_str!("../style/output.css");
+const STYLESHEET_FILE: &str = "q3aparser.css";
+
+#[derive(Debug, Error)]
+pub enum ReportError {
+    #[error("Cannot parse {path}: {source}")]
+    Parse { path: PathBuf, source: ParseError },
+    #[error("Cannot write {path}: {source}")]
+    Write { path: PathBuf, source: io::Error },
+}
+
+/// What a static report was made of.
+#[derive(Clone, Debug, Default)]
+pub struct ReportSummary {
+    pub matches: usize,
+    pub players: usize,
+    pub pages: usize,
+}
+
+/// Parses each log as an upload of its own, numbered from 1 in the given order.
+pub fn matches_from_logs(paths: &[PathBuf], options: ParserOptions) -> Result<Vec<StoredMatch>, ReportError> {
+    let parser = ParallelParser::new(options);
+    let mut matches = Vec::new();
+
+    for (index, path) in paths.iter().enumerate() {
+        let upload_id = format!("log{}", index + 1);
+        let uploaded_at = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        let parsed_log = parser.parse(path.to_string_lossy().into_owned()).map_err(|source| ReportError::Parse {
+            path: path.clone(),
+            source,
+        })?;
+
+        let mut upload = parsed_log
+            .into_iter()
+            .map(|(match_key, mut record)| {
+                let id = format!("{upload_id}-{}", match_number(&match_key));
+                record.id = Some(id.clone());
+
+                StoredMatch {
+                    id,
+                    upload_id: upload_id.clone(),
+                    match_key,
+                    uploaded_at,
+                    record,
+                }
+            })
+            .collect::<Vec<StoredMatch>>();
+        upload.sort_by_key(|stored_match| match_number(&stored_match.match_key));
+
+        matches.extend(upload);
+    }
+
+    Ok(matches)
+}
+
+/// Renders the leaderboard, every match and every player into `output_dir` as plain HTML
+/// files linking to each other, ready for any static web host. Players are merged into
+/// identities with `aliases`, see `IdentityResolver`.
+pub fn write_static_report(
+    matches: &[StoredMatch],
+    aliases: &AliasMap,
+    output_dir: &Path,
+) -> Result<ReportSummary, ReportError> {
+    let identities = IdentityResolver::build(matches, aliases);
+    let profiles = PlayerProfile::build_all(&identities.resolve_matches(matches));
+    let mut summary = ReportSummary {
+        matches: matches.len(),
+        players: profiles.len(),
+        pages: 0,
+    };
+
+    let write = |relative_path: String, content: String| {
+        let path = output_dir.join(relative_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|source| ReportError::Write {
+                path: parent.to_path_buf(),
+                source,
+            })?;
+        }
+
+        fs::write(&path, content).map_err(|source| ReportError::Write { path, source })
+    };
+
+    write(STYLESHEET_FILE.to_string(), STYLESHEET.to_string())?;
+
+    let index_matches = matches.to_vec();
+    let index_profiles = profiles.clone();
+    write(
+        "index.html".to_string(),
+        render_page("", &identities, move |cx| view! { cx,
+            <Title text="Quake 3 Arena server log parser"/>
+            <div class="w-full flex flex-col gap-12">
+                <h1 class="text-3xl font-bold text-center">"Leaderboard"</h1>
+                <LeaderboardTable profiles=index_profiles/>
+                <h2 class="text-2xl font-bold text-center">"Matches"</h2>
+                <MatchListTable matches=index_matches/>
+            </div>
+        }.into_view(cx)),
+    )?;
+    summary.pages += 1;
+
+    for stored_match in matches.iter().cloned() {
+        let relative_path = format!("matches/{}.html", stored_match.id);
+        write(relative_path, render_page("../", &identities, move |cx| view! { cx, <MatchDetail stored_match/> }.into_view(cx)))?;
+        summary.pages += 1;
+    }
+
+    for profile in profiles.into_iter() {
+        let relative_path = format!("players/{}.html", static_file_stem(&profile.name));
+        write(relative_path, render_page("../", &identities, move |cx| view! { cx, <PlayerProfileView profile/> }.into_view(cx)))?;
+        summary.pages += 1;
+    }
+
+    Ok(summary)
+}
+
+/// Renders a page of the report, `root` being the relative path back to the report root.
+fn render_page<F>(root: &str, identities: &IdentityResolver, page: F) -> String
+where
+    F: FnOnce(Scope) -> View + 'static,
+{
+    let root = root.to_string();
+    let identities = identities.clone();
+    let runtime = create_runtime();
+
+    let html = run_scope(runtime, move |cx| {
+        provide_meta_context(cx);
+        provide_context(cx, LinkStyle::StaticFiles { root: root.clone() });
+        provide_context(cx, identities);
+
+        let body = view! { cx,
+            <main class="w-screen h-full min-h-screen pt-12 bg-[#262f3d] text-[#e8ecf0]">
+                <div class="mx-auto max-w-[1080px]">
+                    <nav class="flex flex-row gap-6 mb-8 text-lg font-medium">
+                        <a href=format!("{root}index.html") class="hover:underline">"Leaderboard"</a>
+                    </nav>
+                    {page(cx)}
+                </div>
+            </main>
+        }
+        .into_view(cx)
+        .render_to_string(cx)
+        .to_string();
+
+        // Read after rendering, once the pages registered their titles and meta tags
+        let (head, body_tag) = generate_head_metadata_separated(cx);
+
+        format!(
+            "<!DOCTYPE html><html lang=\"en\"><head><meta charset=\"utf-8\"/><meta name=\"viewport\" content=\"width=device-width, initial-scale=1\"/><link rel=\"stylesheet\" href=\"{root}{STYLESHEET_FILE}\"/>{head}</head>{body_tag}{body}</body></html>"
+        )
+    });
+
+    runtime.dispose();
+
+    html
+}
+}}
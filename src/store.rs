@@ -0,0 +1,196 @@
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+
+use crate::model::parser::MatchRecord;
+
+/// A parsed match kept by the server, so it can be linked to and aggregated with other uploads.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StoredMatch {
+    pub id: String,
+    pub upload_id: String,
+    /// Key of the match inside its log, e.g. `game_3`.
+    pub match_key: String,
+    /// Seconds since the Unix epoch.
+    pub uploaded_at: u64,
+    pub record: MatchRecord,
+}
+
+/// Upload a match id such as `1a2b3c4d-3` belongs to.
+pub fn upload_id_of(match_id: &str) -> Option<&str> {
+    match_id.rsplit_once('-').map(|(upload_id, _)| upload_id)
+}
+
+/// Number of a match key such as `game_3`, used to keep the matches of a log in order.
+pub fn match_number(match_key: &str) -> u32 {
+    match_key
+        .chars()
+        .filter(|c| c.is_numeric())
+        .collect::<String>()
+        .parse::<u32>()
+        .unwrap_or_default()
+}
+
+cfg_if! { if #[cfg(feature = "ssr")] {
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sha2::{Digest, Sha256};
+
+use crate::model::identity::{AliasMap, IdentityResolver};
+
+/// Parsed uploads, kept in memory and written to one JSON file per upload under `dir`.
+#[derive(Clone)]
+pub struct MatchStore {
+    dir: PathBuf,
+    matches: Arc<RwLock<Vec<StoredMatch>>>,
+    aliases: Arc<AliasMap>,
+}
+
+impl MatchStore {
+    /// Loads every upload previously saved in `dir`, creating it when needed.
+    pub fn open(dir: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+
+        let mut matches = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+
+            match serde_json::from_slice::<Vec<StoredMatch>>(&fs::read(&path)?) {
+                Ok(upload) => matches.extend(upload),
+                Err(err) => log::warn!("Skipping {}: {}", path.display(), err),
+            }
+        }
+
+        matches.sort_by(|a, b| {
+            (a.uploaded_at, &a.upload_id, match_number(&a.match_key))
+                .cmp(&(b.uploaded_at, &b.upload_id, match_number(&b.match_key)))
+        });
+
+        Ok(MatchStore {
+            dir,
+            matches: Arc::new(RwLock::new(matches)),
+            aliases: Arc::new(AliasMap::default()),
+        })
+    }
+
+    /// Resolves player identities with `aliases` on top of the GUIDs and IPs of the logs.
+    pub fn with_aliases(mut self, aliases: AliasMap) -> Self {
+        self.aliases = Arc::new(aliases);
+        self
+    }
+
+    /// Stores the matches of an upload, setting the `id` each of them can be found by.
+    ///
+    /// Uploads are named after a hash of their log, so a log uploaded again keeps the ids of the
+    /// matches already stored instead of being counted twice.
+    pub fn save_upload(&self, log: &[u8], parsed_log: &mut HashMap<String, MatchRecord>) -> io::Result<()> {
+        let upload_id = format!("{:x}", Sha256::digest(log));
+        for (match_key, record) in parsed_log.iter_mut() {
+            record.id = Some(format!("{upload_id}-{}", match_number(match_key)));
+        }
+
+        let mut matches = self.matches.write().unwrap();
+        if matches.iter().any(|stored_match| stored_match.upload_id == upload_id) {
+            return Ok(());
+        }
+
+        let uploaded_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        let mut upload = parsed_log
+            .iter()
+            .map(|(match_key, record)| StoredMatch {
+                id: record.id.clone().unwrap_or_default(),
+                upload_id: upload_id.clone(),
+                match_key: match_key.clone(),
+                uploaded_at,
+                record: record.clone(),
+            })
+            .collect::<Vec<StoredMatch>>();
+        upload.sort_by_key(|stored_match| match_number(&stored_match.match_key));
+
+        let file_path = self.dir.join(format!("{upload_id}.json"));
+        let mut file = OpenOptions::new().write(true).create_new(true).open(file_path)?;
+        file.write_all(&serde_json::to_vec(&upload)?)?;
+
+        matches.extend(upload);
+
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<StoredMatch> {
+        self.matches
+            .read()
+            .unwrap()
+            .iter()
+            .find(|stored_match| stored_match.id == id)
+            .cloned()
+    }
+
+    /// Matches of a single upload, in match order.
+    pub fn upload(&self, upload_id: &str) -> Vec<StoredMatch> {
+        self.matches
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|stored_match| stored_match.upload_id == upload_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Every stored match, in upload order.
+    pub fn all(&self) -> Vec<StoredMatch> {
+        self.matches.read().unwrap().clone()
+    }
+
+    pub fn identities(&self) -> IdentityResolver {
+        IdentityResolver::build(&self.matches.read().unwrap(), &self.aliases)
+    }
+
+    /// Every stored match with players renamed to their identity, for stats across matches.
+    pub fn resolved(&self) -> Vec<StoredMatch> {
+        self.identities().resolve_matches(&self.all())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::parser::Parser;
+
+    const LOG: &str = "  0:00 InitGame: \\sv_hostname\\Test\\g_gametype\\0\\mapname\\q3dm17\n  0:10 Kill: 1022 2 22: <world> killed Isgalamido by MOD_TRIGGER_HURT\n  1:00 ShutdownGame:\n";
+
+    #[test]
+    fn a_log_uploaded_twice_is_stored_once() {
+        let dir = std::env::temp_dir().join(format!("q3a-store-{}", std::process::id()));
+        let store = MatchStore::open(dir.clone()).unwrap();
+
+        let mut first = Parser::parse_bytes(LOG.as_bytes()).unwrap();
+        store.save_upload(LOG.as_bytes(), &mut first).unwrap();
+        let mut second = Parser::parse_bytes(LOG.as_bytes()).unwrap();
+        store.save_upload(LOG.as_bytes(), &mut second).unwrap();
+
+        let ids = |parsed_log: &HashMap<String, MatchRecord>| {
+            parsed_log
+                .values()
+                .map(|record| record.id.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&first), ids(&second));
+        assert_eq!(store.all().len(), 1);
+        assert_eq!(upload_id_of(&store.all()[0].id).map(str::len), Some(64));
+        assert_eq!(MatchStore::open(dir.clone()).unwrap().all().len(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
+}}